@@ -0,0 +1,88 @@
+/* Compares the time it takes `cmp-tree` to compare two identical directory trees when reading
+ * regular files with the `read` backend (at a few buffer sizes) and with the `mmap` backend. The
+ * directory trees are generated in a temporary directory and contain a few large files and many
+ * small ones. Every configuration is run once to warm up the page cache before being timed, so
+ * the numbers reflect the cost of getting the data out of the page cache rather than the speed of
+ * the storage.
+ *
+ * Run with `cargo bench --bench io_backends`. */
+
+use std::path::Path;
+use std::time::{Duration,Instant};
+
+use cmp_tree::{IoMode,default_config};
+
+
+/// Creates a directory tree rooted at `root` containing `large_count` regular files of
+/// `large_size` bytes and `small_count` regular files of a few hundred bytes.
+fn generate_tree(root: &Path, large_count: usize, large_size: usize, small_count: usize) {
+    /* {{{ */
+    std::fs::create_dir_all(root.join("small")).unwrap();
+    for l in 0..large_count {
+        /* Cheap, deterministic and not entirely uniform content */
+        let content: Vec<u8> = (0..large_size).map(|i| ((i * 31 + l) % 251) as u8).collect();
+        std::fs::write(root.join(format!("large{}.bin", l)), content).unwrap();
+    }
+    for s in 0..small_count {
+        std::fs::write(root.join("small").join(format!("file{}.txt", s)),
+            format!("{}\n", s).repeat(64)).unwrap();
+    }
+    /* }}} */
+}
+
+
+/// Runs `cmp-tree` on the two directory trees `iterations` times with the given I/O backend and
+/// buffer size and returns the fastest run.
+fn time_comparison(first: &Path, second: &Path, io_mode: IoMode, buffer_size: usize,
+    iterations: usize) -> Duration {
+    /* {{{ */
+    let mut conf = default_config();
+    conf.io_mode = io_mode;
+    conf.buffer_size = buffer_size;
+    conf.silent = true;
+
+    /* Warm up the page cache */
+    assert_eq!(cmp_tree::cmp_tree(&conf, first, second), 0);
+
+    let mut fastest = Duration::MAX;
+    for _ in 0..iterations {
+        let start = Instant::now();
+        assert_eq!(cmp_tree::cmp_tree(&conf, first, second), 0);
+        fastest = std::cmp::min(fastest, start.elapsed());
+    }
+    return fastest;
+    /* }}} */
+}
+
+
+fn main() {
+    /* {{{ */
+    const LARGE_COUNT: usize = 4;
+    const LARGE_SIZE: usize = 64 << 20;
+    const SMALL_COUNT: usize = 2000;
+    const ITERATIONS: usize = 5;
+
+    let work_dir = std::env::temp_dir().join(format!("cmp-tree-io-bench-{}", std::process::id()));
+    let first = work_dir.join("first");
+    let second = work_dir.join("second");
+    generate_tree(&first, LARGE_COUNT, LARGE_SIZE, SMALL_COUNT);
+    generate_tree(&second, LARGE_COUNT, LARGE_SIZE, SMALL_COUNT);
+    let total_mib = (LARGE_COUNT * LARGE_SIZE) as f64 / (1 << 20) as f64;
+
+    println!("Comparing {} files of {} MiB and {} small files (fastest of {} runs)", LARGE_COUNT,
+        LARGE_SIZE >> 20, SMALL_COUNT, ITERATIONS);
+    let configurations: [(&str, IoMode, usize); 4] = [
+        ("read, 8 KiB buffer", IoMode::Read, 8 << 10),
+        ("read, 64 KiB buffer", IoMode::Read, 64 << 10),
+        ("read, 1 MiB buffer", IoMode::Read, 1 << 20),
+        ("mmap", IoMode::Mmap, 8 << 10),
+    ];
+    for (name, io_mode, buffer_size) in configurations {
+        let elapsed = time_comparison(&first, &second, io_mode, buffer_size, ITERATIONS);
+        println!("{:<22} {:>10.2?} ({:.0} MiB/s of large files)", name, elapsed,
+            total_mib / elapsed.as_secs_f64());
+    }
+
+    std::fs::remove_dir_all(&work_dir).unwrap();
+    /* }}} */
+}
@@ -0,0 +1,184 @@
+/* Counts the `stat`-family system calls (`stat`, `lstat`, `fstat`, `newfstatat` and `statx`) a
+ * single-threaded run of `cmp-tree` makes while comparing two identical directory trees, and how
+ * many that comes out to per compared file pair. The directory trees are generated in a temporary
+ * directory, and the system calls are counted by tracing `cmp-tree` with `ptrace(2)`.
+ *
+ * Run with `cargo bench --bench stat_syscalls`. Only supported on x86_64 Linux. To measure a
+ * change, build `cmp-tree` as it was before the change and point `CMP_TREE_BASELINE` at that
+ * binary: it is traced on the same directory trees, and the difference between the two is printed.
+ *
+ * On the 10050 file pairs this benchmark compares, release builds of `cmp-tree` made:
+ * - at 0b796c9 (before soft links could be followed and the directory trees were walked in
+ *   parallel), 120703 stat-family system calls (12.01 per file pair) and 201299 system calls in
+ *   all (20.03 per file pair).
+ * - at f106e1b (the directory-by-directory walk, right before every file's metadata was gathered
+ *   once during the walk and reused when comparing), 140703 (14.00 per file pair) and 221272
+ *   (22.02 per file pair).
+ * - at d93d314 (right after that change), 20303 (2.02 per file pair) and 100876 (10.04 per file
+ *   pair).
+ * All three were measured by running this benchmark with `CMP_TREE_BASELINE` pointing at a
+ * release build of the commit. */
+
+#[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+mod tracing {
+    use std::ffi::CString;
+    use std::path::Path;
+
+    /// Runs `program` with the arguments `args` under `ptrace(2)`, returning the number of
+    /// `stat`-family system calls it made and the total number of system calls it made.
+    ///
+    /// #### Parameters:
+    /// * `program` the file path to the program to run.
+    /// * `args` the arguments to pass to the program, not including the program itself.
+    /// #### Return:
+    /// * `(stat_calls, total_calls)` on success and `Err(())` on failure.
+    pub fn count_syscalls(program: &Path, args: &[&Path]) -> Result<(u64, u64), ()> {
+        /* {{{ */
+        let mut argv_owned: Vec<CString> = Vec::new();
+        argv_owned.push(CString::new(program.as_os_str().as_encoded_bytes()).unwrap());
+        for arg in args {
+            argv_owned.push(CString::new(arg.as_os_str().as_encoded_bytes()).unwrap());
+        }
+        let mut argv: Vec<*const libc::c_char> = argv_owned.iter().map(|a| a.as_ptr()).collect();
+        argv.push(std::ptr::null());
+        let dev_null = CString::new("/dev/null").unwrap();
+
+        let pid = unsafe { libc::fork() };
+        if pid < 0 {
+            return Err(());
+        }
+        if pid == 0 {
+            /* In the child: ask to be traced, silence the output and become `cmp-tree` */
+            unsafe {
+                libc::ptrace(libc::PTRACE_TRACEME, 0, 0, 0);
+                let fd = libc::open(dev_null.as_ptr(), libc::O_WRONLY);
+                libc::dup2(fd, 1);
+                libc::execv(argv[0], argv.as_ptr());
+                libc::_exit(127);
+            }
+        }
+
+        let mut status: libc::c_int = 0;
+        /* The child stops with a `SIGTRAP` once it has executed `cmp-tree` */
+        unsafe { libc::waitpid(pid, &mut status, 0) };
+        if !libc::WIFSTOPPED(status) {
+            return Err(());
+        }
+        unsafe {
+            libc::ptrace(libc::PTRACE_SETOPTIONS, pid, 0, libc::PTRACE_O_TRACESYSGOOD);
+        }
+
+        let mut stat_calls: u64 = 0;
+        let mut total_calls: u64 = 0;
+        /* Every system call stops the child twice: once on entry and once on exit */
+        let mut entering = true;
+        loop {
+            unsafe {
+                libc::ptrace(libc::PTRACE_SYSCALL, pid, 0, 0);
+                libc::waitpid(pid, &mut status, 0);
+            }
+            if libc::WIFEXITED(status) || libc::WIFSIGNALED(status) {
+                break;
+            }
+            if libc::WIFSTOPPED(status) && libc::WSTOPSIG(status) == (libc::SIGTRAP | 0x80) {
+                if entering {
+                    let mut regs: libc::user_regs_struct = unsafe { std::mem::zeroed() };
+                    unsafe {
+                        libc::ptrace(libc::PTRACE_GETREGS, pid, 0, &mut regs);
+                    }
+                    total_calls += 1;
+                    match regs.orig_rax as libc::c_long {
+                        libc::SYS_stat | libc::SYS_lstat | libc::SYS_fstat
+                            | libc::SYS_newfstatat | libc::SYS_statx => stat_calls += 1,
+                        _ => (),
+                    }
+                }
+                entering = !entering;
+            }
+        }
+
+        return Ok((stat_calls, total_calls));
+        /* }}} */
+    }
+}
+
+
+/// Creates a directory tree rooted at `root` containing `dir_count` directories that each contain
+/// `files_per_dir` small regular files.
+fn generate_tree(root: &std::path::Path, dir_count: usize, files_per_dir: usize) {
+    /* {{{ */
+    for d in 0..dir_count {
+        let dir = root.join(format!("dir{}", d));
+        std::fs::create_dir_all(&dir).unwrap();
+        for f in 0..files_per_dir {
+            std::fs::write(dir.join(format!("file{}.txt", f)), format!("{} {}\n", d, f)).unwrap();
+        }
+    }
+    /* }}} */
+}
+
+
+#[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+fn main() {
+    /* {{{ */
+    const DIR_COUNT: usize = 50;
+    const FILES_PER_DIR: usize = 200;
+
+    let work_dir = std::env::temp_dir().join(format!("cmp-tree-stat-bench-{}", std::process::id()));
+    let first = work_dir.join("first");
+    let second = work_dir.join("second");
+    generate_tree(&first, DIR_COUNT, FILES_PER_DIR);
+    generate_tree(&second, DIR_COUNT, FILES_PER_DIR);
+    /* Every directory and every regular file is one file pair */
+    let file_pairs = (DIR_COUNT + DIR_COUNT * FILES_PER_DIR) as f64;
+
+    let program = std::path::PathBuf::from(env!("CARGO_BIN_EXE_cmp-tree"));
+    let baseline_program = std::env::var_os("CMP_TREE_BASELINE").map(std::path::PathBuf::from);
+    let single_threaded = std::path::Path::new("-S");
+    let start = std::time::Instant::now();
+    let res = tracing::count_syscalls(&program, &[single_threaded, &first, &second]);
+    let elapsed = start.elapsed();
+    let baseline_res = baseline_program.as_ref().map(|baseline_program| {
+        return tracing::count_syscalls(baseline_program, &[single_threaded, &first, &second]);
+    });
+    std::fs::remove_dir_all(&work_dir).unwrap();
+
+    let (stat_calls, total_calls) = match res {
+        Ok(counts) => counts,
+        Err(_) => {
+            println!("ERROR: Failed to trace cmp-tree");
+            std::process::exit(1);
+        },
+    };
+    println!("File pairs compared:        {}", file_pairs);
+    println!("stat-family system calls:   {} ({:.2} per file pair)", stat_calls,
+        stat_calls as f64 / file_pairs);
+    println!("All system calls:           {} ({:.2} per file pair)", total_calls,
+        total_calls as f64 / file_pairs);
+    println!("Wall time (while traced):   {:?}", elapsed);
+
+    match baseline_res {
+        Some(Ok((baseline_stat_calls, baseline_total_calls))) => {
+            println!("Baseline stat-family calls: {} ({:.2} per file pair, {:+} now)",
+                baseline_stat_calls, baseline_stat_calls as f64 / file_pairs,
+                stat_calls as i64 - baseline_stat_calls as i64);
+            println!("Baseline system calls:      {} ({:.2} per file pair, {:+} now)",
+                baseline_total_calls, baseline_total_calls as f64 / file_pairs,
+                total_calls as i64 - baseline_total_calls as i64);
+        },
+        Some(Err(_)) => {
+            println!("ERROR: Failed to trace the baseline cmp-tree");
+            std::process::exit(1);
+        },
+        None => (),
+    }
+    /* }}} */
+}
+
+
+#[cfg(not(all(target_os = "linux", target_arch = "x86_64")))]
+fn main() {
+    /* {{{ */
+    println!("This benchmark is only supported on x86_64 Linux");
+    /* }}} */
+}
@@ -0,0 +1,1094 @@
+use std::collections::{BTreeMap,HashMap};
+use std::ffi::OsStr;
+use std::fs::File;
+use std::io::{Read,Seek,SeekFrom};
+use std::os::unix::ffi::OsStrExt; // For turning paths into bytes
+use std::os::unix::fs::MetadataExt; // For getting the permission bits and mtimes of files
+use std::path::{Component,Path,PathBuf};
+
+// Use statements to get rid of the `config::` and `data_structures::` prefixes
+use crate::config::{Config,IoMode,SymlinkMode};
+use crate::data_structures::{FileCmp,FullFileComparison,PartialFileComparison,SimpleFileType};
+
+// For reading compressed tar archives and comparing the contents of files
+use crate::decompress;
+
+
+/* The separator between the path of an archive and the path of a file inside it, as in
+ * `release.tar.gz!/src/main.rs` */
+pub const ARCHIVE_SEPARATOR: &str = "!/";
+
+/* The file name suffixes of the tar archives `cmp-tree` can read. Compressed tar archives are
+ * recognised by their contents, not their suffix, so any of these may be compressed with gzip, xz
+ * or zstd */
+const TAR_SUFFIXES: &[&str] = &[".tar", ".tar.gz", ".tgz", ".tar.xz", ".txz", ".tar.zst", ".tzst"];
+
+/* The file name suffixes of the zip archives `cmp-tree` can read, including the formats that are
+ * zip archives under another name (Java archives, Python wheels and Android packages) */
+const ZIP_SUFFIXES: &[&str] = &[".zip", ".jar", ".war", ".ear", ".whl", ".apk"];
+
+
+/* The archive formats `cmp-tree` can read */
+#[derive(Debug,PartialEq,Eq,Clone,Copy)]
+pub enum ArchiveFormat {
+    Tar,
+    Zip,
+}
+
+
+/* Where the contents of a regular file in a `VirtualTree` can be read from */
+#[derive(Debug,Clone)]
+enum Location {
+    /* The file is a file on disk, at the given path */
+    File(PathBuf),
+    /* The contents of the file start at the given offset in the (decompressed) tar archive */
+    TarOffset(u64),
+    /* The file is the entry at the given index in the zip archive */
+    ZipIndex(usize),
+    /* The file has no contents to read (e.g. it is a directory) */
+    Nowhere,
+}
+
+
+/* A file (in the broad sense) in a `VirtualTree` */
+#[derive(Debug,Clone)]
+pub struct VirtualEntry {
+    pub file_type: SimpleFileType,
+    /* The size of the file in bytes. Only meaningful for regular files */
+    pub size: u64,
+    /* The permission bits of the file, if known. Directories a tar archive only implies (because
+     * it has files in them, but no entries for them) have no known permission bits */
+    pub mode: Option<u32>,
+    /* The modification time of the file in seconds since the Unix epoch, if known */
+    pub mtime: Option<i64>,
+    /* The link path of the file, if it is a soft link */
+    pub link_target: Option<PathBuf>,
+    location: Location,
+}
+
+
+/* Where the files of a `VirtualTree` come from */
+#[derive(Debug)]
+enum Source {
+    Directory,
+    Tar { path: PathBuf, compressed: bool },
+    Zip(PathBuf),
+}
+
+
+/* A directory tree, or the files in an archive, enumerated in its entirety ahead of the
+ * comparison. Archives have no directory entries to list, so both directory trees are enumerated
+ * this way as soon as either of them is an archive */
+#[derive(Debug)]
+pub struct VirtualTree {
+    /* The path the files of the tree are output relative to */
+    display_root: PathBuf,
+    source: Source,
+    /* Every file in the tree, keyed (and sorted) by its path relative to the root of the tree */
+    pub entries: BTreeMap<PathBuf, VirtualEntry>,
+}
+
+
+/// Returns the archive format of a file, going by the suffix of its file name.
+///
+/// #### Parameters:
+/// * `path` a file path that points to the file.
+/// #### Return:
+/// * the format of the archive, or `None` if the file name is not that of an archive `cmp-tree`
+///     can read.
+pub fn archive_format(path: &Path) -> Option<ArchiveFormat> {
+    /* {{{ */
+    let name = match path.file_name() {
+        Some(name) => name.to_string_lossy().to_ascii_lowercase(),
+        None => return None,
+    };
+    if TAR_SUFFIXES.iter().any(|suffix| name.ends_with(suffix)) {
+        return Some(ArchiveFormat::Tar);
+    } else if ZIP_SUFFIXES.iter().any(|suffix| name.ends_with(suffix)) {
+        return Some(ArchiveFormat::Zip);
+    }
+    return None;
+    /* }}} */
+}
+
+
+/// Splits a root given to `cmp-tree` into the archive it refers to and the directory inside the
+/// archive it refers to. A root can be an archive (e.g. `release.tar.gz`), in which case the whole
+/// archive is the directory tree, or a directory inside an archive (e.g.
+/// `release.tar.gz!/release-1.0`), in which case only the files in that directory are.
+///
+/// #### Parameters:
+/// * `root` the root, as given to `cmp-tree`.
+/// #### Return:
+/// * the path to the archive and the path of the directory inside it (empty for the whole
+///     archive), or `None` if `root` does not refer to an archive.
+pub fn split_root(root: &Path) -> Option<(PathBuf, PathBuf)> {
+    /* {{{ */
+    if root.is_file() && archive_format(root).is_some() {
+        return Some((root.to_path_buf(), PathBuf::new()));
+    }
+    let bytes = root.as_os_str().as_bytes();
+    let separator = ARCHIVE_SEPARATOR.as_bytes();
+    for i in 0..bytes.len() {
+        if !bytes[i..].starts_with(separator) {
+            continue;
+        }
+        let archive = Path::new(OsStr::from_bytes(&bytes[..i]));
+        if archive.is_file() && archive_format(archive).is_some() {
+            let inner = Path::new(OsStr::from_bytes(&bytes[i + separator.len()..]));
+            return Some((archive.to_path_buf(), inner.to_path_buf()));
+        }
+    }
+    return None;
+    /* }}} */
+}
+
+
+/// Turns the path of a member of an archive into a path relative to the root of the archive,
+/// dropping any `.` components and leading `/`.
+///
+/// #### Parameters:
+/// * `path` the path of the member, as stored in the archive.
+/// #### Return:
+/// * the relative path of the member, or `None` if it is the root of the archive itself or it
+///     escapes the archive (i.e. it has `..` components).
+fn member_path(path: &Path) -> Option<PathBuf> {
+    /* {{{ */
+    let mut ret = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(name) => ret.push(name),
+            Component::CurDir | Component::RootDir => (),
+            Component::ParentDir | Component::Prefix(_) => return None,
+        }
+    }
+    match ret.as_os_str().is_empty() {
+        true => return None,
+        false => return Some(ret),
+    }
+    /* }}} */
+}
+
+
+/// Adds an entry for every directory that the files of an archive are in, but that the archive
+/// has no entry for itself. Archives made by some tools only have entries for regular files.
+///
+/// #### Parameters:
+/// * `entries` the entries of the archive.
+fn add_implied_directories(entries: &mut BTreeMap<PathBuf, VirtualEntry>) {
+    /* {{{ */
+    let mut implied: Vec<PathBuf> = Vec::new();
+    for rel_path in entries.keys() {
+        for ancestor in rel_path.ancestors().skip(1) {
+            if !ancestor.as_os_str().is_empty() && !entries.contains_key(ancestor) {
+                implied.push(ancestor.to_path_buf());
+            }
+        }
+    }
+    for rel_path in implied {
+        entries.insert(rel_path, VirtualEntry {
+            file_type: SimpleFileType::Directory,
+            size: 0,
+            mode: None,
+            mtime: None,
+            link_target: None,
+            location: Location::Nowhere,
+        });
+    }
+    /* }}} */
+}
+
+
+/// Enumerates every file in a directory tree on disk. Soft links are never followed.
+///
+/// #### Parameters:
+/// * `root` a file path that points to the root directory of the directory tree.
+/// #### Return:
+/// * every file in the directory tree, keyed by its path relative to `root`, on success and
+///     `Err(())` if any directory could not be listed.
+fn read_directory_tree(root: &Path) -> Result<BTreeMap<PathBuf, VirtualEntry>, ()> {
+    /* {{{ */
+    let mut ret: BTreeMap<PathBuf, VirtualEntry> = BTreeMap::new();
+    let mut pending_dirs: Vec<PathBuf> = vec![PathBuf::new()];
+
+    while let Some(dir) = pending_dirs.pop() {
+        let dir_entries = match std::fs::read_dir(root.join(&dir)) {
+            Ok(dir_entries) => dir_entries,
+            Err(_) => return Err(()),
+        };
+        for e in dir_entries {
+            let entry = match e {
+                Ok(entry) => entry,
+                Err(_) => return Err(()),
+            };
+            let metadata = match entry.metadata() {
+                Ok(md) => md,
+                Err(_) => return Err(()),
+            };
+            /* Files of other types (e.g. sockets) can't be stored in archives anyway */
+            let file_type = match SimpleFileType::try_from(&metadata.file_type()) {
+                Ok(file_type) => file_type,
+                Err(_) => continue,
+            };
+            let rel_path = dir.join(entry.file_name());
+            let link_target = match file_type {
+                SimpleFileType::SoftLink => match std::fs::read_link(entry.path()) {
+                    Ok(link_target) => Some(link_target),
+                    Err(_) => return Err(()),
+                },
+                _ => None,
+            };
+            if file_type == SimpleFileType::Directory {
+                pending_dirs.push(rel_path.clone());
+            }
+            ret.insert(rel_path, VirtualEntry {
+                file_type: file_type,
+                size: metadata.len(),
+                mode: Some(metadata.mode() & 0o7777),
+                mtime: Some(metadata.mtime()),
+                link_target: link_target,
+                location: Location::File(entry.path()),
+            });
+        }
+    }
+
+    return Ok(ret);
+    /* }}} */
+}
+
+
+/// Opens a tar archive, decompressing it if it is compressed.
+///
+/// #### Parameters:
+/// * `path` a file path that points to the tar archive.
+/// #### Return:
+/// * a reader that yields the (decompressed) tar archive and whether the archive was compressed on
+///     success, and `Err(())` if the archive could not be opened.
+fn open_tar(path: &Path) -> Result<(Box<dyn Read>, bool), ()> {
+    /* {{{ */
+    match decompress::decompressor(path) {
+        Ok(Some(reader)) => return Ok((reader, true)),
+        Ok(None) => match File::open(path) {
+            Ok(file) => return Ok((Box::new(file), false)),
+            Err(_) => return Err(()),
+        },
+        Err(_) => return Err(()),
+    }
+    /* }}} */
+}
+
+
+/// Enumerates every file in a tar archive. Hard links are enumerated as copies of the regular file
+/// they link to, and files of other types (e.g. device files) are left out.
+///
+/// #### Parameters:
+/// * `path` a file path that points to the tar archive.
+/// #### Return:
+/// * every file in the archive, keyed by its path relative to the root of the archive, and whether
+///     the archive is compressed on success, and `Err(())` if the archive could not be read.
+fn read_tar(path: &Path) -> Result<(BTreeMap<PathBuf, VirtualEntry>, bool), ()> {
+    /* {{{ */
+    let mut ret: BTreeMap<PathBuf, VirtualEntry> = BTreeMap::new();
+    let (reader, compressed) = match open_tar(path) {
+        Ok(opened) => opened,
+        Err(_) => return Err(()),
+    };
+    let mut archive = tar::Archive::new(reader);
+    let entries = match archive.entries() {
+        Ok(entries) => entries,
+        Err(_) => return Err(()),
+    };
+
+    for e in entries {
+        let entry = match e {
+            Ok(entry) => entry,
+            Err(_) => return Err(()),
+        };
+        let rel_path = match entry.path() {
+            Ok(path) => match member_path(&path) {
+                Some(rel_path) => rel_path,
+                None => continue,
+            },
+            Err(_) => return Err(()),
+        };
+        let header = entry.header();
+        let link_target = match entry.link_name() {
+            Ok(link_target) => link_target.map(|target| target.into_owned()),
+            Err(_) => return Err(()),
+        };
+        let mut virtual_entry = VirtualEntry {
+            file_type: SimpleFileType::RegFile,
+            size: 0,
+            mode: header.mode().ok().map(|mode| mode & 0o7777),
+            mtime: header.mtime().ok().map(|mtime| mtime as i64),
+            link_target: None,
+            location: Location::Nowhere,
+        };
+        match header.entry_type() {
+            tar::EntryType::Regular | tar::EntryType::Continuous => {
+                virtual_entry.size = entry.size();
+                virtual_entry.location = Location::TarOffset(entry.raw_file_position());
+            },
+            tar::EntryType::Directory => virtual_entry.file_type = SimpleFileType::Directory,
+            tar::EntryType::Symlink => {
+                virtual_entry.file_type = SimpleFileType::SoftLink;
+                virtual_entry.link_target = link_target;
+            },
+            /* A hard link has the same contents as the file it links to, which came before it */
+            tar::EntryType::Link => {
+                match link_target.as_deref().and_then(member_path).and_then(|t| ret.get(&t)) {
+                    Some(target) => virtual_entry = target.clone(),
+                    None => continue,
+                }
+            },
+            _ => continue,
+        }
+        /* If the archive has several entries for the same file, the last one wins */
+        ret.insert(rel_path, virtual_entry);
+    }
+
+    add_implied_directories(&mut ret);
+    return Ok((ret, compressed));
+    /* }}} */
+}
+
+
+/// Enumerates every file in a zip archive. The modification times of files are only known if the
+/// archive stores them as Unix timestamps, since the MS-DOS timestamps every zip archive has are
+/// in an unknown time zone.
+///
+/// #### Parameters:
+/// * `path` a file path that points to the zip archive.
+/// #### Return:
+/// * every file in the archive, keyed by its path relative to the root of the archive, on success
+///     and `Err(())` if the archive could not be read.
+fn read_zip(path: &Path) -> Result<BTreeMap<PathBuf, VirtualEntry>, ()> {
+    /* {{{ */
+    let mut ret: BTreeMap<PathBuf, VirtualEntry> = BTreeMap::new();
+    let mut archive = match File::open(path).map(zip::ZipArchive::new) {
+        Ok(Ok(archive)) => archive,
+        _ => return Err(()),
+    };
+
+    for i in 0..archive.len() {
+        let mut file = match archive.by_index(i) {
+            Ok(file) => file,
+            Err(_) => return Err(()),
+        };
+        let rel_path = match file.enclosed_name().as_deref().and_then(member_path) {
+            Some(rel_path) => rel_path,
+            None => continue,
+        };
+        let mtime = file.extra_data_fields().find_map(|field| match field {
+            zip::ExtraField::ExtendedTimestamp(timestamp) => timestamp.mod_time(),
+            _ => None,
+        });
+        let mut virtual_entry = VirtualEntry {
+            file_type: SimpleFileType::RegFile,
+            size: 0,
+            mode: file.unix_mode().map(|mode| mode & 0o7777),
+            mtime: mtime.map(|mtime| mtime as i64),
+            link_target: None,
+            location: Location::Nowhere,
+        };
+        if file.is_dir() {
+            virtual_entry.file_type = SimpleFileType::Directory;
+        /* The contents of a soft link in a zip archive are its link path */
+        } else if file.is_symlink() {
+            let mut link_target: Vec<u8> = Vec::new();
+            if file.read_to_end(&mut link_target).is_err() {
+                return Err(());
+            }
+            virtual_entry.file_type = SimpleFileType::SoftLink;
+            virtual_entry.link_target = Some(PathBuf::from(OsStr::from_bytes(&link_target)));
+        } else {
+            virtual_entry.size = file.size();
+            virtual_entry.location = Location::ZipIndex(i);
+        }
+        ret.insert(rel_path, virtual_entry);
+    }
+
+    add_implied_directories(&mut ret);
+    return Ok(ret);
+    /* }}} */
+}
+
+
+impl VirtualTree {
+    /* {{{ */
+    /// Enumerates every file in the directory tree rooted at `root`, which can be a directory, an
+    /// archive or a directory inside an archive (see `split_root()`).
+    ///
+    /// #### Parameters:
+    /// * `root` the root of the directory tree, as given to `cmp-tree`.
+    /// #### Return:
+    /// * the enumerated directory tree on success and `Err(())` if it could not be read (or
+    ///     `root` is a directory inside an archive that the archive does not have).
+    pub fn open(root: &Path) -> Result<VirtualTree, ()> {
+        /* {{{ */
+        let (archive, inner) = match split_root(root) {
+            Some(split) => split,
+            None => match read_directory_tree(root) {
+                Ok(entries) => return Ok(VirtualTree {
+                    display_root: root.to_path_buf(),
+                    source: Source::Directory,
+                    entries: entries,
+                }),
+                Err(_) => return Err(()),
+            },
+        };
+
+        let (source, mut entries) = match archive_format(&archive) {
+            Some(ArchiveFormat::Tar) => match read_tar(&archive) {
+                Ok((entries, compressed)) => {
+                    (Source::Tar { path: archive.clone(), compressed: compressed }, entries)
+                },
+                Err(_) => return Err(()),
+            },
+            Some(ArchiveFormat::Zip) => match read_zip(&archive) {
+                Ok(entries) => (Source::Zip(archive.clone()), entries),
+                Err(_) => return Err(()),
+            },
+            None => return Err(()),
+        };
+
+        /* Only keep the files in the directory inside the archive, relative to that directory */
+        let inner = member_path(&inner).unwrap_or_default();
+        if !inner.as_os_str().is_empty() {
+            match entries.get(&inner) {
+                Some(entry) if entry.file_type == SimpleFileType::Directory => (),
+                _ => return Err(()),
+            }
+            entries = entries.into_iter().filter_map(|(rel_path, entry)| {
+                match rel_path.strip_prefix(&inner) {
+                    Ok(inner_path) if !inner_path.as_os_str().is_empty() => {
+                        return Some((inner_path.to_path_buf(), entry));
+                    },
+                    _ => return None,
+                }
+            }).collect();
+        }
+
+        /* The files in the whole archive are output as `archive!/path/to/file` */
+        let display_root = match inner.as_os_str().is_empty() {
+            true => PathBuf::from(format!("{}!", archive.display())),
+            false => root.to_path_buf(),
+        };
+        return Ok(VirtualTree {
+            display_root: display_root,
+            source: source,
+            entries: entries,
+        });
+        /* }}} */
+    }
+
+    /// Returns the path a file in the tree is output as.
+    ///
+    /// #### Parameters:
+    /// * `rel_path` the path of the file relative to the root of the tree.
+    /// #### Return:
+    /// * the path of the file, rooted at the root of the tree as given to `cmp-tree`.
+    pub fn display_path(&self, rel_path: &Path) -> PathBuf {
+        /* {{{ */
+        return self.display_root.join(rel_path);
+        /* }}} */
+    }
+
+    /// Returns whether the tree is read from a compressed tar archive, which can only be read from
+    /// start to end.
+    fn is_compressed_tar(&self) -> bool {
+        /* {{{ */
+        return matches!(self.source, Source::Tar { compressed: true, .. });
+        /* }}} */
+    }
+
+    /// Calls `f` with a reader that yields the contents of a regular file in the tree.
+    ///
+    /// #### Parameters:
+    /// * `entry` the regular file. The tree must not be a compressed tar archive.
+    /// * `zip_archive` the zip archive the tree is read from, opened the first time a file in it is
+    ///     read and reused after that.
+    /// * `f` the function to call with the reader.
+    /// #### Return:
+    /// * whatever `f` returns, or `Err(())` if the file could not be opened.
+    fn with_reader<T>(&self, entry: &VirtualEntry, zip_archive: &mut Option<zip::ZipArchive<File>>,
+        f: impl FnOnce(&mut dyn Read) -> Result<T, ()>) -> Result<T, ()> {
+        /* {{{ */
+        match (&entry.location, &self.source) {
+            (Location::File(path), _) => match File::open(path) {
+                Ok(mut file) => return f(&mut file),
+                Err(_) => return Err(()),
+            },
+            (Location::TarOffset(offset), Source::Tar { path, .. }) => match File::open(path) {
+                Ok(mut file) => {
+                    if file.seek(SeekFrom::Start(*offset)).is_err() {
+                        return Err(());
+                    }
+                    return f(&mut file.take(entry.size));
+                },
+                Err(_) => return Err(()),
+            },
+            (Location::ZipIndex(i), Source::Zip(path)) => {
+                if zip_archive.is_none() {
+                    match File::open(path).map(zip::ZipArchive::new) {
+                        Ok(Ok(archive)) => *zip_archive = Some(archive),
+                        _ => return Err(()),
+                    }
+                }
+                match zip_archive.as_mut().unwrap().by_index(*i) {
+                    Ok(mut file) => return f(&mut file),
+                    Err(_) => return Err(()),
+                }
+            },
+            _ => return Err(()),
+        }
+        /* }}} */
+    }
+    /* }}} */
+}
+
+
+/* A temporary file that is deleted when it is dropped */
+struct TempFile {
+    path: PathBuf,
+}
+
+
+impl Drop for TempFile {
+    /* {{{ */
+    fn drop(&mut self) {
+        /* {{{ */
+        let _ = std::fs::remove_file(&self.path);
+        /* }}} */
+    }
+    /* }}} */
+}
+
+
+/// Decompresses a compressed tar archive into a temporary file, so that the files in it can be
+/// read in any order.
+///
+/// #### Parameters:
+/// * `path` a file path that points to the compressed tar archive.
+/// #### Return:
+/// * the temporary file on success and `Err(())` if the archive could not be decompressed.
+fn decompress_to_temp_file(path: &Path) -> Result<TempFile, ()> {
+    /* {{{ */
+    static COUNTER: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+    let n = COUNTER.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+    let temp_file = TempFile {
+        path: std::env::temp_dir().join(format!("cmp-tree-{}-{}.tar", std::process::id(), n)),
+    };
+
+    let mut reader = match open_tar(path) {
+        Ok((reader, _)) => reader,
+        Err(_) => return Err(()),
+    };
+    let mut file = match File::create_new(&temp_file.path) {
+        Ok(file) => file,
+        Err(_) => return Err(()),
+    };
+    match std::io::copy(&mut reader, &mut file) {
+        Ok(_) => return Ok(temp_file),
+        Err(_) => return Err(()),
+    }
+    /* }}} */
+}
+
+
+/// Compares the contents of corresponding regular files in two virtual trees. Files are read from
+/// the archives they are in as they are compared, without being extracted. A compressed tar
+/// archive can only be read from start to end, so if one of the trees is one, the files are
+/// compared in the order they are stored in it. If both trees are, the second one is decompressed
+/// into a temporary file first.
+///
+/// #### Parameters:
+/// * `config` a `Config` representing a configuration for executing `cmp-tree`, usually modified
+///     through command line arguments to the program.
+/// * `first` the first virtual tree.
+/// * `second` the second virtual tree.
+/// * `rel_paths` the paths of the files to compare, relative to the roots of the trees. Every file
+///     must be a regular file in both trees, of the same size in both.
+/// #### Return:
+/// * whether the contents of each file are identical in both trees, keyed by its relative path,
+///     on success and `Err(())` if any file could not be read.
+fn compare_contents(config: &Config, first: &mut VirtualTree, second: &mut VirtualTree,
+    rel_paths: &[PathBuf]) -> Result<HashMap<PathBuf, bool>, ()> {
+    /* {{{ */
+    let mut ret: HashMap<PathBuf, bool> = HashMap::new();
+    let mut first_zip: Option<zip::ZipArchive<File>> = None;
+    let mut second_zip: Option<zip::ZipArchive<File>> = None;
+
+    let _temp_file: TempFile;
+    if first.is_compressed_tar() && second.is_compressed_tar() {
+        let second_path = match &second.source {
+            Source::Tar { path, .. } => path.clone(),
+            _ => return Err(()),
+        };
+        _temp_file = match decompress_to_temp_file(&second_path) {
+            Ok(temp_file) => temp_file,
+            Err(_) => return Err(()),
+        };
+        /* The offsets of the files are the same in the decompressed archive */
+        second.source = Source::Tar { path: _temp_file.path.clone(), compressed: false };
+    }
+
+    /* If neither tree is a compressed tar archive, every file can be read in the sorted order */
+    if !first.is_compressed_tar() && !second.is_compressed_tar() {
+        for rel_path in rel_paths {
+            let first_entry = &first.entries[rel_path];
+            let second_entry = &second.entries[rel_path];
+            let identical = first.with_reader(first_entry, &mut first_zip, |first_reader| {
+                return second.with_reader(second_entry, &mut second_zip, |second_reader| {
+                    return decompress::compare_streams(first_reader, second_reader,
+                        config.buffer_size);
+                });
+            });
+            match identical {
+                Ok(identical) => ret.insert(rel_path.clone(), identical),
+                Err(_) => return Err(()),
+            };
+        }
+        return Ok(ret);
+    }
+
+    /* Otherwise, read the compressed tar archive from start to end, and compare every file in it
+     * to the corresponding file in the other tree as it comes */
+    let (streamed, other, other_zip) = match first.is_compressed_tar() {
+        true => (&*first, &*second, &mut second_zip),
+        false => (&*second, &*first, &mut first_zip),
+    };
+    let mut rel_paths_at: HashMap<u64, Vec<&PathBuf>> = HashMap::new();
+    for rel_path in rel_paths {
+        if let Location::TarOffset(offset) = streamed.entries[rel_path].location {
+            rel_paths_at.entry(offset).or_default().push(rel_path);
+        }
+    }
+    let archive_path = match &streamed.source {
+        Source::Tar { path, .. } => path,
+        _ => return Err(()),
+    };
+    let reader = match open_tar(archive_path) {
+        Ok((reader, _)) => reader,
+        Err(_) => return Err(()),
+    };
+    let mut archive = tar::Archive::new(reader);
+    let entries = match archive.entries() {
+        Ok(entries) => entries,
+        Err(_) => return Err(()),
+    };
+    for e in entries {
+        let mut entry = match e {
+            Ok(entry) => entry,
+            Err(_) => return Err(()),
+        };
+        let rel_paths_here = match rel_paths_at.get(&entry.raw_file_position()) {
+            Some(rel_paths_here) => rel_paths_here,
+            None => continue,
+        };
+        /* Hard links share their contents with the file they link to, so when several files need
+         * the same contents, the contents are read into memory once */
+        let mut contents: Vec<u8> = Vec::new();
+        if rel_paths_here.len() > 1 && entry.read_to_end(&mut contents).is_err() {
+            return Err(());
+        }
+        for rel_path in rel_paths_here {
+            let identical = other.with_reader(&other.entries[*rel_path], other_zip,
+                |other_reader| {
+                match rel_paths_here.len() {
+                    1 => return decompress::compare_streams(&mut entry, other_reader,
+                        config.buffer_size),
+                    _ => return decompress::compare_streams(&mut contents.as_slice(),
+                        other_reader, config.buffer_size),
+                }
+            });
+            match identical {
+                Ok(identical) => ret.insert((*rel_path).clone(), identical),
+                Err(_) => return Err(()),
+            };
+        }
+    }
+
+    /* Every file should have been found in the archive */
+    if ret.len() != rel_paths.len() {
+        return Err(());
+    }
+    return Ok(ret);
+    /* }}} */
+}
+
+
+/// Compares two corresponding files (in the broad sense) of two virtual trees in every way except
+/// the contents of regular files, which is left to `compare_contents()`.
+///
+/// #### Parameters:
+/// * `first` the file in the first virtual tree, if it exists.
+/// * `second` the file in the second virtual tree, if it exists.
+/// #### Return:
+/// * how the two files compare, and whether they are two regular files of the same size whose
+///     contents still need to be compared.
+fn compare_entries(first: Option<&VirtualEntry>, second: Option<&VirtualEntry>) ->
+    (PartialFileComparison, bool) {
+    /* {{{ */
+    let mut ret = PartialFileComparison {
+        file_cmp: FileCmp::Match,
+        first_ft: first.map(|entry| entry.file_type.clone()),
+        second_ft: second.map(|entry| entry.file_type.clone()),
+    };
+    let (first, second) = match (first, second) {
+        (Some(first), Some(second)) => (first, second),
+        (Some(_), None) => {
+            ret.file_cmp = FileCmp::ExistenceOnlyFirstFile;
+            return (ret, false);
+        },
+        (None, Some(_)) => {
+            ret.file_cmp = FileCmp::ExistenceOnlySecondFile;
+            return (ret, false);
+        },
+        (None, None) => {
+            ret.file_cmp = FileCmp::ExistenceNeitherFile;
+            return (ret, false);
+        },
+    };
+
+    if first.file_type != second.file_type {
+        ret.file_cmp = FileCmp::FileTypeTypeMismatch;
+        return (ret, false);
+    }
+    match first.file_type {
+        SimpleFileType::RegFile if first.size != second.size => {
+            ret.file_cmp = FileCmp::SubstanceRegFileContentMismatch;
+            return (ret, false);
+        },
+        SimpleFileType::RegFile => return (ret, true),
+        SimpleFileType::SoftLink if first.link_target != second.link_target => {
+            ret.file_cmp = FileCmp::SubstanceSoftLinkLinkMismatch;
+            return (ret, false);
+        },
+        _ => return (ret, false),
+    }
+    /* }}} */
+}
+
+
+/// Compares the metadata of two corresponding files of two virtual trees whose substance
+/// matches. Metadata that is not known for either file (e.g. the modification time of a file in a
+/// zip archive without Unix timestamps) is not compared.
+///
+/// #### Parameters:
+/// * `config` a `Config` representing a configuration for executing `cmp-tree`, usually modified
+///     through command line arguments to the program.
+/// * `first` the file in the first virtual tree.
+/// * `second` the file in the second virtual tree.
+/// #### Return:
+/// * a `FileCmp` that represents whether the two files are equivalent in terms of their
+///     metadata.
+fn compare_entry_metadata(config: &Config, first: &VirtualEntry, second: &VirtualEntry) ->
+    FileCmp {
+    /* {{{ */
+    let differ = |first: Option<i64>, second: Option<i64>| match (first, second) {
+        (Some(first), Some(second)) => first != second,
+        _ => false,
+    };
+    if config.compare_modification_times && differ(first.mtime, second.mtime) {
+        return FileCmp::MetadataModificationTimeMismatch;
+    }
+    if config.compare_modes && first.file_type != SimpleFileType::SoftLink
+        && differ(first.mode.map(i64::from), second.mode.map(i64::from)) {
+        return FileCmp::MetadataModeMismatch;
+    }
+    return FileCmp::Match;
+    /* }}} */
+}
+
+
+/// Returns the command line flags set in `config` that change how files are deemed to match, but
+/// that files in archives cannot honour. Files in archives are only ever compared byte-for-byte
+/// (and by their metadata), without being read as text, as data or by their payloads, and soft
+/// links in archives are never followed.
+///
+/// #### Parameters:
+/// * `config` a `Config` representing a configuration for executing `cmp-tree`, usually modified
+///     through command line arguments to the program.
+/// #### Return:
+/// * the flags (e.g. `--text`) that cannot be used when either directory tree is an archive.
+pub fn unsupported_flags(config: &Config) -> Vec<&'static str> {
+    /* {{{ */
+    let mut ret: Vec<&'static str> = Vec::new();
+    let flags = [
+        (config.text, "--text"),
+        (config.ignore_bom, "--ignore-bom"),
+        (config.ignore_all_space, "--ignore-all-space"),
+        (config.ignore_trailing_space, "--ignore-trailing-space"),
+        (config.ignore_blank_lines, "--ignore-blank-lines"),
+        (config.ignore_trailing_newline, "--ignore-trailing-newline"),
+        (!config.masks.is_empty(), "--mask/--mask-lines"),
+        (!config.structured.is_empty(), "--structured"),
+        (config.decompress, "--decompress"),
+        (config.elf, "--elf/--elf-sections"),
+        (config.images, "--images/--image-tolerance"),
+        (!config.external_commands.is_empty(), "--external"),
+        (config.symlink_mode == SymlinkMode::Follow, "--follow-symlinks"),
+        (config.symlink_mode == SymlinkMode::FollowAndCompare, "--strict-symlinks"),
+        (config.normalize_link_targets, "--normalize-link-targets"),
+        (config.compare_sparseness, "--sparseness"),
+    ];
+    for (is_set, flag) in flags {
+        if is_set {
+            ret.push(flag);
+        }
+    }
+    return ret;
+    /* }}} */
+}
+
+
+/// Returns the command line flags set in `config` that have no effect when either directory tree
+/// is an archive, but that cannot make files match that would otherwise differ either, as they
+/// only change how (or how much of) the files are read.
+///
+/// #### Parameters:
+/// * `config` a `Config` representing a configuration for executing `cmp-tree`, usually modified
+///     through command line arguments to the program.
+/// #### Return:
+/// * the flags (e.g. `--shallow`) that are ignored when either directory tree is an archive.
+pub fn ignored_flags(config: &Config) -> Vec<&'static str> {
+    /* {{{ */
+    let mut ret: Vec<&'static str> = Vec::new();
+    let flags = [
+        (config.io_mode != IoMode::Read, "--io"),
+        (config.disk_order, "--disk-order"),
+        (config.shallow, "--shallow"),
+        (config.sample_blocks.is_some(), "--sample"),
+        (config.large_file_threshold.is_some(), "--split-large-files"),
+        (config.shared_extents, "--shared-extents"),
+    ];
+    for (is_set, flag) in flags {
+        if is_set {
+            ret.push(flag);
+        }
+    }
+    return ret;
+    /* }}} */
+}
+
+
+/// If `config` asks for it and `full_cmp` is the comparison of two archive files on disk that are
+/// not byte-for-byte identical, compares the files in the two archives and hands each
+/// `FullFileComparison` over to `sink`, so that the files that make the archives differ are known.
+/// The files in the archives are output as the path to the archive followed by `!/` and their
+/// path inside it (e.g. `lib/app.jar!/META-INF/MANIFEST.MF`). Archives inside archives are not
+/// recursed into, and the files in archives are compared the way `stream_archive_comparison()`
+/// compares them, whatever else `config` asks for (see `unsupported_flags()`).
+///
+/// #### Parameters:
+/// * `config` a `Config` representing a configuration for executing `cmp-tree`, usually modified
+///     through command line arguments to the program.
+/// * `full_cmp` the comparison of two corresponding files.
+/// * `sink` a closure that is called once for every file comparison that was performed.
+/// #### Return:
+/// * `Ok(())` if the files in the archives were compared (or there was nothing to recurse into)
+///     and `Err(())` if either archive could not be read.
+pub fn recurse_into_archives(config: &Config, full_cmp: &FullFileComparison,
+    sink: &mut dyn FnMut(FullFileComparison)) -> Result<(), ()> {
+    /* {{{ */
+    if !config.recurse_archives
+        || full_cmp.partial_cmp.file_cmp != FileCmp::SubstanceRegFileContentMismatch {
+        return Ok(());
+    }
+    /* Only archives that are files on disk are recursed into, not archives inside archives */
+    let is_archive_file = |path: &Path| match split_root(path) {
+        Some((archive, inner)) => archive == path && inner.as_os_str().is_empty(),
+        None => false,
+    };
+    if !is_archive_file(&full_cmp.first_path) || !is_archive_file(&full_cmp.second_path) {
+        return Ok(());
+    }
+    return stream_archive_comparison(config, &full_cmp.first_path, &full_cmp.second_path, sink);
+    /* }}} */
+}
+
+
+/// Compares two directory trees, at least one of which is an archive or a directory inside an
+/// archive (see `split_root()`), and hands each `FullFileComparison` over to `sink` in the sorted
+/// order of the relative paths of the files. Both directory trees are enumerated in their entirety
+/// before any file is compared. Soft links are never followed, and regular files are only ever
+/// compared byte for byte.
+///
+/// #### Parameters:
+/// * `config` a `Config` representing a configuration for executing `cmp-tree`, usually modified
+///     through command line arguments to the program.
+/// * `first_root` the root of the first directory tree: a directory, an archive or a directory
+///     inside an archive.
+/// * `second_root` the root of the second directory tree.
+/// * `sink` a closure that is called once for every file comparison that was performed.
+/// #### Return:
+/// * `Ok(())` if the comparison was carried out and `Err(())` if either directory tree could not
+///     be read.
+pub fn stream_archive_comparison(config: &Config, first_root: &Path, second_root: &Path,
+    sink: &mut dyn FnMut(FullFileComparison)) -> Result<(), ()> {
+    /* {{{ */
+    let (mut first, mut second) = match (VirtualTree::open(first_root),
+        VirtualTree::open(second_root)) {
+        (Ok(first), Ok(second)) => (first, second),
+        _ => return Err(()),
+    };
+
+    let mut rel_paths: Vec<PathBuf> = first.entries.keys().chain(second.entries.keys()).cloned()
+        .collect();
+    rel_paths.sort_unstable();
+    rel_paths.dedup();
+
+    let mut cmps: Vec<(PathBuf, PartialFileComparison)> = Vec::with_capacity(rel_paths.len());
+    let mut contents_to_compare: Vec<PathBuf> = Vec::new();
+    for rel_path in rel_paths {
+        let (partial_cmp, compare_contents) = compare_entries(first.entries.get(&rel_path),
+            second.entries.get(&rel_path));
+        if compare_contents {
+            contents_to_compare.push(rel_path.clone());
+        }
+        cmps.push((rel_path, partial_cmp));
+    }
+
+    let identical = match compare_contents(config, &mut first, &mut second, &contents_to_compare) {
+        Ok(identical) => identical,
+        Err(_) => return Err(()),
+    };
+
+    for (rel_path, mut partial_cmp) in cmps {
+        if identical.get(&rel_path) == Some(&false) {
+            partial_cmp.file_cmp = FileCmp::SubstanceRegFileContentMismatch;
+        }
+        if partial_cmp.file_cmp == FileCmp::Match {
+            partial_cmp.file_cmp = compare_entry_metadata(config, &first.entries[&rel_path],
+                &second.entries[&rel_path]);
+        }
+        let is_match = partial_cmp.file_cmp.is_match();
+        sink(FullFileComparison {
+            first_path: first.display_path(&rel_path),
+            second_path: second.display_path(&rel_path),
+            partial_cmp: partial_cmp,
+        });
+        /* When the comparison should stop at the first mismatch, stop handing comparisons over */
+        if config.quick && !is_match {
+            break;
+        }
+    }
+
+    return Ok(());
+    /* }}} */
+}
+
+
+/* Unit tests */
+#[test]
+fn ut_split_root_001() {
+    /* {{{ */
+    let archive = Path::new("../../tests/033/second/release-1.0.tar.gz");
+    let root = PathBuf::from("../../tests/033/second/release-1.0.tar.gz!/release-1.0");
+
+    assert_eq!(split_root(archive), Some((archive.to_path_buf(), PathBuf::new())));
+    assert_eq!(split_root(&root), Some((archive.to_path_buf(), PathBuf::from("release-1.0"))));
+    /* Directories, and archives that don't exist, are not archives */
+    assert_eq!(split_root(Path::new("../../tests/033/first")), None);
+    assert_eq!(split_root(Path::new("../../tests/033/second/missing.zip!/release-1.0")), None);
+    /* }}} */
+}
+
+#[test]
+fn ut_stream_archive_comparison_001() {
+    /* {{{ */
+    let mut conf = crate::config::default_config();
+    conf.compare_modes = true;
+    let first_root = Path::new("../../tests/033/first/release-1.0");
+
+    for second_root in ["../../tests/033/second/release-1.0.tar.gz!/release-1.0",
+        "../../tests/033/second/release-1.0.zip!/release-1.0"] {
+        let mut cmps: Vec<FullFileComparison> = Vec::new();
+        let ret = stream_archive_comparison(&conf, first_root, Path::new(second_root),
+            &mut |full_cmp| cmps.push(full_cmp));
+        assert_eq!(ret, Ok(()));
+        /* `README`, `README.link`, `bin`, `bin/run.sh`, `src` and `src/main.c` */
+        assert_eq!(cmps.len(), 6);
+        assert!(cmps.iter().all(|cmp| cmp.partial_cmp.file_cmp == FileCmp::Match));
+    }
+    /* }}} */
+}
+
+#[test]
+fn ut_stream_archive_comparison_002() {
+    /* {{{ */
+    /* Both archives are compressed the first time and only one is the second time */
+    let conf = crate::config::default_config();
+    let first_root = Path::new("../../tests/033/second/release-1.0.tar.gz");
+
+    for (second_root, expected_mismatches) in [
+        ("../../tests/033/second/release-1.0.tar.zst", Vec::new()),
+        ("../../tests/033/second/changed.tar", vec![Path::new("release-1.0/src/main.c")]),
+    ] {
+        let mut mismatches: Vec<PathBuf> = Vec::new();
+        let ret = stream_archive_comparison(&conf, first_root, Path::new(second_root),
+            &mut |full_cmp| {
+            if !full_cmp.partial_cmp.file_cmp.is_match() {
+                let display_root = format!("{}!", first_root.display());
+                mismatches.push(full_cmp.first_path.strip_prefix(display_root).unwrap().into());
+            }
+        });
+        assert_eq!(ret, Ok(()));
+        assert_eq!(mismatches, expected_mismatches);
+    }
+    /* }}} */
+}
+
+#[test]
+fn ut_recurse_into_archives_001() {
+    /* {{{ */
+    let mut conf = crate::config::default_config();
+    let archive_cmp = |name: &str| FullFileComparison {
+        partial_cmp: PartialFileComparison {
+            file_cmp: FileCmp::SubstanceRegFileContentMismatch,
+            first_ft: Some(SimpleFileType::RegFile),
+            second_ft: Some(SimpleFileType::RegFile),
+        },
+        first_path: Path::new("../../tests/034/first/lib").join(name),
+        second_path: Path::new("../../tests/034/second/lib").join(name),
+    };
+
+    /* Nothing is recursed into unless the config asks for it */
+    let mut cmps: Vec<FullFileComparison> = Vec::new();
+    let ret = recurse_into_archives(&conf, &archive_cmp("app.jar"),
+        &mut |full_cmp| cmps.push(full_cmp));
+    assert_eq!(ret, Ok(()));
+    assert!(cmps.is_empty());
+
+    conf.recurse_archives = true;
+    for (name, expected_mismatches) in [
+        ("app.jar", vec![PathBuf::from("../../tests/034/first/lib/app.jar!/META-INF/MANIFEST.MF")]),
+        ("bundle.zip", Vec::new()),
+    ] {
+        let mut mismatches: Vec<PathBuf> = Vec::new();
+        let ret = recurse_into_archives(&conf, &archive_cmp(name), &mut |full_cmp| {
+            if !full_cmp.partial_cmp.file_cmp.is_match() {
+                mismatches.push(full_cmp.first_path);
+            }
+        });
+        assert_eq!(ret, Ok(()));
+        assert_eq!(mismatches, expected_mismatches);
+    }
+    /* }}} */
+}
+
+#[test]
+fn ut_unsupported_flags_001() {
+    /* {{{ */
+    let mut conf = crate::config::default_config();
+    assert!(unsupported_flags(&conf).is_empty());
+    assert!(ignored_flags(&conf).is_empty());
+
+    conf.text = true;
+    conf.decompress = true;
+    conf.shallow = true;
+    assert_eq!(unsupported_flags(&conf), vec!["--text", "--decompress"]);
+    assert_eq!(ignored_flags(&conf), vec!["--shallow"]);
+    /* }}} */
+}
@@ -1,48 +1,53 @@
 use std::fs::{File,Metadata,read_link};
-use std::io::Read; // For getting the SHA256 hash of a file
-use std::path::Path;
+use std::io::{ErrorKind,Read}; // For getting the SHA256 hash of a file
+use std::os::unix::ffi::OsStrExt; // For turning paths into bytes
+use std::os::unix::fs::FileExt; // For reading files at a given offset (`pread`)
+use std::os::unix::fs::MetadataExt; // For getting the number of blocks allocated to a file
+use std::path::{Component,Path,PathBuf};
+use std::sync::atomic::{AtomicBool,Ordering};
+use std::thread::{scope,ScopedJoinHandle};
+
+use memmap2::{Advice,Mmap}; // For memory-mapping files
 
 
 // Use statements to get rid of the `config::` prefix
-use crate::config::Config;
+use crate::config::{Config,IoMode,Mask,SymlinkMode};
 
 // Use statements to get rid of the `data_structures::` prefix
+use crate::data_structures::Equivalence;
 use crate::data_structures::FileCmp;
+use crate::data_structures::FilePair;
 use crate::data_structures::SimpleFileType;
 use crate::data_structures::PartialFileComparison;
 
+// For comparing regular files once decompressed, as text or as data
+use crate::decompress;
+use crate::elf;
+use crate::external;
+use crate::images::{self,ImageCmp};
+use crate::structured;
+use crate::text;
+
 
 /// Takes two paths and returns a result representing how the files compare. Both file paths must
-/// point to regular files and both regular files must exist.
+/// point to regular files and both regular files must exist. This function does not check the
+/// sizes of the files up front, callers that have the metadata of the files at hand should.
 ///
 /// #### Parameters:
 /// * `first_path` a file path that points to the first file we wish to compare.
 /// * `second_path` a file path that points to the second file we wish to compare.
+/// * `buffer_size` the number of bytes to read from each file at a time.
 /// #### Return:
 /// * `Ok(FileCmp)` on success and `Err(())` on failure.
-fn compare_regular_files(first_path: &Path, second_path: &Path) -> Result<FileCmp, ()> {
+fn compare_regular_files(first_path: &Path, second_path: &Path, buffer_size: usize) ->
+    Result<FileCmp, ()> {
     /* {{{ */
-    const BYTE_COUNT: usize = 8192;
-
     let first_file_res = File::open(first_path);
     let second_file_res = File::open(second_path);
     let mut first_file: File;
     let mut second_file: File;
-    let mut first_buf = [0; BYTE_COUNT];
-    let mut second_buf = [0; BYTE_COUNT];
-
-    /* Check if the files differ in size. If they do, they cannot be byte-for-byte identical */
-    match first_path.metadata() {
-        Ok(first_md) => match second_path.metadata() {
-            Ok(second_md) => {
-                if first_md.len() != second_md.len() {
-                    return Ok(FileCmp::SubstanceRegFileContentMismatch);
-                }
-            },
-            Err(_) => return Err(()),
-        },
-        Err(_) => return Err(()),
-    }
+    let mut first_buf = vec![0; buffer_size];
+    let mut second_buf = vec![0; buffer_size];
 
     if first_file_res.is_ok() && second_file_res.is_ok() {
         first_file = first_file_res.unwrap();
@@ -89,275 +94,1181 @@ fn compare_regular_files(first_path: &Path, second_path: &Path) -> Result<FileCm
 }
 
 
-/// Takes two paths and a result representing how the soft links compare. Both file paths must
-/// point to soft links and both soft links must exist.
+/// Does the same thing as `compare_regular_files()`, but memory-maps both files and compares them
+/// in large slices instead of reading them into buffers. This avoids copying the contents of the
+/// files out of the page cache. If either file cannot be mapped (some special file systems do not
+/// support it), the comparison falls back to `compare_regular_files()`. Both files must be
+/// `file_size` bytes long.
 ///
 /// #### Parameters:
-/// * `first_path` a file path that points to the first soft link we wish to compare.
-/// * `second_path` a file path that points to the second soft link we wish to compare.
+/// * `first_path` a file path that points to the first file we wish to compare.
+/// * `second_path` a file path that points to the second file we wish to compare.
+/// * `file_size` the size (in bytes) of both files.
+/// * `buffer_size` the buffer size to use if the comparison has to fall back to
+///     `compare_regular_files()`.
 /// #### Return:
 /// * `Ok(FileCmp)` on success and `Err(())` on failure.
-fn compare_soft_links(first_path: &Path, second_path: &Path) -> Result<FileCmp, ()> {
+fn compare_regular_files_mmap(first_path: &Path, second_path: &Path, file_size: u64,
+    buffer_size: usize) -> Result<FileCmp, ()> {
     /* {{{ */
-    match read_link(first_path) {
-        Ok(first_link_target) => match read_link(second_path) {
-            Ok(second_link_target) => {
-                /* If the two soft links point to the same file */
-                if first_link_target == second_link_target {
-                    return Ok(FileCmp::Match);
-                /* If the two soft links point to a different file */
-                } else {
-                    return Ok(FileCmp::SubstanceSoftLinkLinkMismatch);
-                }
-            },
-            Err(_) => {
-                return Err(());
-            }
+    /* The number of bytes compared at a time. Comparing slice by slice lets a mismatch near the
+     * start of two big files be found without touching the rest of them */
+    const SLICE_LEN: usize = 1 << 20;
+
+    /* Empty files cannot be mapped, but they are trivially identical */
+    if file_size == 0 {
+        return compare_regular_files(first_path, second_path, buffer_size);
+    }
+
+    let first_file: File;
+    let second_file: File;
+    match (File::open(first_path), File::open(second_path)) {
+        (Ok(first), Ok(second)) => {
+            first_file = first;
+            second_file = second;
         },
-        Err(_) => {
-            return Err(());
+        _ => return Err(()),
+    }
+
+    /* Mapping a file is unsafe because the mapped memory changes if the file is modified while it
+     * is mapped (and accessing it raises `SIGBUS` if the file is truncated). `cmp-tree` only ever
+     * reads the mapped memory, and comparing directory trees that are being modified is already
+     * unreliable no matter how the files are read */
+    let first_map = match unsafe { Mmap::map(&first_file) } {
+        Ok(map) => map,
+        Err(_) => return compare_regular_files(first_path, second_path, buffer_size),
+    };
+    let second_map = match unsafe { Mmap::map(&second_file) } {
+        Ok(map) => map,
+        Err(_) => return compare_regular_files(first_path, second_path, buffer_size),
+    };
+    /* Let the kernel know it should read ahead aggressively. This is only a hint, so failing to
+     * give it is not a problem */
+    let _ = first_map.advise(Advice::Sequential);
+    let _ = second_map.advise(Advice::Sequential);
+
+    /* The files may have changed size since their size was read */
+    if first_map.len() != second_map.len() {
+        return Ok(FileCmp::SubstanceRegFileContentMismatch);
+    }
+    let slices = first_map.chunks(SLICE_LEN).zip(second_map.chunks(SLICE_LEN));
+    for (first_slice, second_slice) in slices {
+        if first_slice != second_slice {
+            return Ok(FileCmp::SubstanceRegFileContentMismatch);
         }
     }
+
+    return Ok(FileCmp::Match);
     /* }}} */
 }
 
 
-/// Takes two paths and returns a `Result` that either contains a `FileCmp` that represents how the
-/// two files (understood in the broad sense) pointed to by the two paths compare in terms of their
-/// existence or an `Err` indicating that an error occurred in the process of comparing the two
-/// files.
+/* Two open regular files, along with everything the functions that compare ranges of them (see
+ * `compare_file_range()`) need to know to do so */
+struct OpenRegFiles {
+    first_file: File,
+    second_file: File,
+    /* The number of bytes to read from each file at a time */
+    buffer_size: usize,
+    /* Set as soon as a range of the files is found to differ, so that the threads comparing other
+     * ranges of the same files stop early when they see it */
+    mismatch_found: AtomicBool,
+}
+
+impl OpenRegFiles {
+    /* {{{ */
+    /// Opens two regular files so that ranges of them can be compared.
+    ///
+    /// #### Parameters:
+    /// * `first_path` a file path that points to the first file we wish to compare.
+    /// * `second_path` a file path that points to the second file we wish to compare.
+    /// * `buffer_size` the number of bytes to read from each file at a time.
+    /// #### Return:
+    /// * the two open files on success and `Err(())` if either could not be opened.
+    fn open(first_path: &Path, second_path: &Path, buffer_size: usize) ->
+        Result<OpenRegFiles, ()> {
+        /* {{{ */
+        match (File::open(first_path), File::open(second_path)) {
+            (Ok(first_file), Ok(second_file)) => return Ok(OpenRegFiles {
+                first_file: first_file,
+                second_file: second_file,
+                buffer_size: buffer_size,
+                mismatch_found: AtomicBool::new(false),
+            }),
+            _ => return Err(()),
+        }
+        /* }}} */
+    }
+    /* }}} */
+}
+
+
+/// Compares the bytes in the range `[start, end)` of two open regular files using positional
+/// reads, so that any number of threads can compare different ranges of the same two files at
+/// once.
 ///
 /// #### Parameters:
-/// * `first_path` a file path that points to the first file we wish to compare.
-/// * `second_path` a file path that points to the second file we wish to compare.
+/// * `files` the two open files we wish to compare. If another thread already found them to
+///     differ, the comparison stops early.
+/// * `start` the offset of the first byte of the range.
+/// * `end` the offset right after the last byte of the range.
 /// #### Return:
-/// * a `FileCmp` that represents whether the two files are equivalent in terms of existence and
-///     how they are different in this regard if they are.
-fn compare_existences(first_path: &Path, second_path: &Path) -> Result<FileCmp, ()> {
+/// * `Ok(FileCmp)` on success and `Err(())` on failure.
+fn compare_file_range(files: &OpenRegFiles, start: u64, end: u64) -> Result<FileCmp, ()> {
     /* {{{ */
-    let first_existence: bool;
-    let second_existence: bool;
+    let buffer_size = files.buffer_size;
+    let mismatch_found = &files.mismatch_found;
+    let mut first_buf = vec![0; buffer_size];
+    let mut second_buf = vec![0; buffer_size];
+    let mut offset = start;
 
-    /* Checking existences is a little bit trickier with symlinks. First, we need to check if our
-     * paths point to symlinks and the link itself exists (rather than whether the stuff it points
-     * to exists) */
-    if first_path.is_symlink() {
-        first_existence = true;
-    } else {
-        first_existence = first_path.exists();
-    }
-    if second_path.is_symlink() {
-        second_existence = true;
-    } else {
-        second_existence = second_path.exists();
-    }
+    while offset < end {
+        /* Another thread already found the files to differ, there is no point in continuing */
+        if mismatch_found.load(Ordering::Relaxed) {
+            return Ok(FileCmp::SubstanceRegFileContentMismatch);
+        }
 
-    if first_existence && second_existence {
-        return Ok(FileCmp::Match);
-    /* If only one of the file paths points to an existing file, note which file exists */
-    } else if first_existence && !second_existence {
-        return Ok(FileCmp::ExistenceOnlyFirstFile);
-    } else if !first_existence && second_existence {
-        return Ok(FileCmp::ExistenceOnlySecondFile);
-    /* !first_existence && !second_existence */
-    } else {
-        return Ok(FileCmp::ExistenceNeitherFile);
+        let len = std::cmp::min(buffer_size as u64, end - offset) as usize;
+        let first_res = files.first_file.read_exact_at(&mut first_buf[..len], offset);
+        let second_res = files.second_file.read_exact_at(&mut second_buf[..len], offset);
+        match (first_res, second_res) {
+            (Ok(_), Ok(_)) => (),
+            /* One of the files got shorter since we got its size, so the two files (as they are
+             * now) differ */
+            (Err(e), _) | (_, Err(e)) if e.kind() == ErrorKind::UnexpectedEof => {
+                mismatch_found.store(true, Ordering::Relaxed);
+                return Ok(FileCmp::SubstanceRegFileContentMismatch);
+            },
+            _ => return Err(()),
+        }
+        if first_buf[..len] != second_buf[..len] {
+            mismatch_found.store(true, Ordering::Relaxed);
+            return Ok(FileCmp::SubstanceRegFileContentMismatch);
+        }
+        offset += len as u64;
     }
+
+    return Ok(FileCmp::Match);
     /* }}} */
 }
 
 
-/// A helper function for `compare_files()`. Takes two paths and returns a `Result` that either
-/// contains a tuple of two `Option<Metadata>`s (representing possibly the metadata of the two
-/// files being compared) or an `Err` indicating that an error occurred in the process of acquiring
-/// the metadata.
+/// Splits ranges of two open regular files into `group_count` groups of about the same number of
+/// bytes, cutting ranges in two where need be. Groups are a multiple of the buffer size long, bar
+/// the last one, so that reads stay aligned.
 ///
 /// #### Parameters:
-/// * `first_path` a file path that points to the first file whose metadata we wish to get.
-/// * `second_path` a file path that points to the second file whose metadata we wish to get.
+/// * `ranges` the ranges `[start, end)` to split, in ascending order and not overlapping.
+/// * `group_count` the number of groups to split the ranges into.
+/// * `buffer_size` the number of bytes to read from each file at a time.
 /// #### Return:
-/// * a `Result<(Option<Metadata>, Option<Metadata>), ()>` that either contains possibly the
-///     metadata of the two files or an Err indicating that this function failed to get the
-///     metadata on the two files successfully.
-fn get_metadata(first_path: &Path, second_path: &Path) ->
-    Result<(Option<Metadata>, Option<Metadata>), ()> {
+/// * at most `group_count` groups of ranges that together cover `ranges`, in ascending order.
+fn group_ranges(ranges: &[(u64, u64)], group_count: usize, buffer_size: usize) ->
+    Vec<Vec<(u64, u64)>> {
     /* {{{ */
+    let total_len: u64 = ranges.iter().map(|(start, end)| end - start).sum();
+    let buffer_size_u64 = std::cmp::max(buffer_size, 1) as u64;
+    let group_count_u64 = std::cmp::max(group_count, 1) as u64;
+    let buffers = total_len.div_ceil(buffer_size_u64);
+    let group_len = std::cmp::max(buffers.div_ceil(group_count_u64), 1) * buffer_size_u64;
+
+    let mut ret: Vec<Vec<(u64, u64)>> = Vec::new();
+    let mut group: Vec<(u64, u64)> = Vec::new();
+    let mut group_left = group_len;
+    for &(mut start, end) in ranges {
+        while start < end {
+            let len = std::cmp::min(end - start, group_left);
+            group.push((start, start + len));
+            start += len;
+            group_left -= len;
+            if group_left == 0 {
+                ret.push(std::mem::take(&mut group));
+                group_left = group_len;
+            }
+        }
+    }
+    if !group.is_empty() {
+        ret.push(group);
+    }
+    return ret;
+    /* }}} */
+}
 
-    let mut first_file_metadata: Option<Metadata> = None;
-    let mut second_file_metadata: Option<Metadata> = None;
 
-    if first_path.exists() {
-        let first_file_metadata_res;
+/// Compares ranges of two open regular files, split into `group_count` groups (see
+/// `group_ranges()`) that are compared concurrently, one thread per group.
+///
+/// #### Parameters:
+/// * `files` the two open files we wish to compare.
+/// * `ranges` the ranges `[start, end)` to compare, in ascending order and not overlapping.
+/// * `group_count` the number of groups (and threads) to split the comparison into. If it is 1,
+///     the ranges are compared on the calling thread.
+/// #### Return:
+/// * `Ok(FileCmp)` on success and `Err(())` on failure.
+fn compare_file_ranges_concurrently(files: &OpenRegFiles, ranges: &[(u64, u64)],
+    group_count: usize) -> Result<FileCmp, ()> {
+    /* {{{ */
+    let compare_group = |group: &[(u64, u64)]| -> Result<FileCmp, ()> {
+        for &(start, end) in group {
+            match compare_file_range(files, start, end) {
+                Ok(FileCmp::Match) => (),
+                other => return other,
+            }
+        }
+        return Ok(FileCmp::Match);
+    };
+    if group_count <= 1 {
+        return compare_group(ranges);
+    }
+
+    let mut ret: Result<FileCmp, ()> = Ok(FileCmp::Match);
+    scope(|s| {
+        let mut thread_handles: Vec<ScopedJoinHandle<'_, Result<FileCmp, ()>>> = Vec::new();
+        for group in group_ranges(ranges, group_count, files.buffer_size) {
+            let compare_group = &compare_group;
+            thread_handles.push(s.spawn(move || -> Result<FileCmp, ()> {
+                return compare_group(&group);
+            }));
+        }
 
-        /* Collect the metadata on the current files. By default, Path.metadata() follows symlinks,
-         * so we need to check if the files we're looking at are symlinks and gets their metadata
-         * appropriately */
-        match first_path.is_symlink() {
-            true => first_file_metadata_res = first_path.symlink_metadata(),
-            false => first_file_metadata_res = first_path.metadata(),
+        for handle in thread_handles.into_iter() {
+            match handle.join() {
+                Ok(Ok(FileCmp::Match)) => (),
+                /* A mismatch takes precedence over an error, since the mismatch is certain */
+                Ok(Ok(range_cmp)) => ret = Ok(range_cmp),
+                _ => if ret == Ok(FileCmp::Match) {
+                    ret = Err(());
+                },
+            }
         }
+    });
+    return ret;
+    /* }}} */
+}
 
-        if let Ok(md) = first_file_metadata_res {
-            first_file_metadata = Some(md);
-        } else {
-            return Err(());
+
+/// Does the same thing as `compare_regular_files()`, but splits the two files into `range_count`
+/// ranges that are compared concurrently, one thread per range. This lets a single pair of very
+/// large files make use of fast storage that one thread reading sequentially cannot saturate.
+/// Both files must be `file_size` bytes long.
+///
+/// #### Parameters:
+/// * `first_path` a file path that points to the first file we wish to compare.
+/// * `second_path` a file path that points to the second file we wish to compare.
+/// * `file_size` the size (in bytes) of both files.
+/// * `buffer_size` the number of bytes each thread reads from each file at a time.
+/// * `range_count` the number of ranges (and threads) to split the comparison into.
+/// #### Return:
+/// * `Ok(FileCmp)` on success and `Err(())` on failure.
+fn compare_regular_files_in_ranges(first_path: &Path, second_path: &Path, file_size: u64,
+    buffer_size: usize, range_count: usize) -> Result<FileCmp, ()> {
+    /* {{{ */
+    let files = match OpenRegFiles::open(first_path, second_path, buffer_size) {
+        Ok(files) => files,
+        Err(_) => return Err(()),
+    };
+
+    let ret = compare_file_ranges_concurrently(&files, &[(0, file_size)], range_count);
+
+    /* The size was only read once, before the comparison. If a file grew in the meantime, the
+     * ranges do not cover it entirely */
+    if ret == Ok(FileCmp::Match) {
+        let mut first_extra = [0; 1];
+        let mut second_extra = [0; 1];
+        match (files.first_file.read_at(&mut first_extra, file_size),
+            files.second_file.read_at(&mut second_extra, file_size)) {
+            (Ok(0), Ok(0)) => (),
+            (Ok(_), Ok(_)) => return Ok(FileCmp::SubstanceRegFileContentMismatch),
+            _ => return Err(()),
         }
     }
 
-    if second_path.exists() {
-        let second_file_metadata_res;
+    return ret;
+    /* }}} */
+}
 
-        /* Collect the metadata on the current files. By default, Path.metadata() follows symlinks,
-         * so we need to check if the files we're looking at are symlinks and gets their metadata
-         * appropriately */
-        match second_path.is_symlink() {
-            true => second_file_metadata_res = second_path.symlink_metadata(),
-            false => second_file_metadata_res = second_path.metadata(),
-        }
 
-        if let Ok(md) = second_file_metadata_res {
-            second_file_metadata = Some(md);
-        } else {
-            return Err(());
+/// Returns the ranges of `file` that contain data, as opposed to holes, using `SEEK_DATA` and
+/// `SEEK_HOLE`. Holes read as zeros, but take up no space on disk.
+///
+/// #### Parameters:
+/// * `file` an open regular file.
+/// * `file_size` the size (in bytes) of the file.
+/// #### Return:
+/// * the ranges `[start, end)` of the file that contain data, in ascending order, on success and
+///     `None` if the file system does not support `SEEK_DATA` and `SEEK_HOLE`.
+#[cfg(target_os = "linux")]
+fn data_ranges(file: &File, file_size: u64) -> Option<Vec<(u64, u64)>> {
+    /* {{{ */
+    use std::os::fd::AsRawFd;
+
+    let mut ret: Vec<(u64, u64)> = Vec::new();
+    let mut offset: u64 = 0;
+
+    while offset < file_size {
+        let data = unsafe { libc::lseek(file.as_raw_fd(), offset as libc::off_t, libc::SEEK_DATA) };
+        if data < 0 {
+            /* There is no data past `offset`, only a hole that runs to the end of the file */
+            match std::io::Error::last_os_error().raw_os_error() {
+                Some(libc::ENXIO) => break,
+                _ => return None,
+            }
         }
+        let hole = unsafe { libc::lseek(file.as_raw_fd(), data, libc::SEEK_HOLE) };
+        if hole < 0 {
+            return None;
+        }
+        /* The file may have grown since its size was read */
+        let end = std::cmp::min(hole as u64, file_size);
+        if (data as u64) < end {
+            ret.push((data as u64, end));
+        }
+        offset = hole as u64;
     }
 
+    return Some(ret);
+    /* }}} */
+}
+
 
-    return Ok((first_file_metadata, second_file_metadata));
+/// On systems other than Linux, holes are never looked for.
+#[cfg(not(target_os = "linux"))]
+fn data_ranges(_file: &File, _file_size: u64) -> Option<Vec<(u64, u64)>> {
+    /* {{{ */
+    return None;
     /* }}} */
 }
 
 
-/// A helper function for `compare_files()`. Takes two paths that point to two files of the same
-/// file type and returns a `Result` that either contains a `FileCmp` that represents how the two
-/// files (understood in the broad sense) compare in terms of their substance or an `Err`
-/// indicating that an error occurred in the process of comparing the two files.
+/// Returns whether the regular file represented by `metadata` is sparse, i.e. whether fewer bytes
+/// are allocated to it on disk than its size, which means that it must contain holes.
+///
+/// #### Parameters:
+/// * `metadata` the metadata of a regular file.
+/// #### Return:
+/// * `true` if the file is sparse and `false` if it isn't.
+fn is_sparse(metadata: &Metadata) -> bool {
+    /* {{{ */
+    /* `blocks()` is always in units of 512 bytes */
+    return metadata.blocks() * 512 < metadata.len();
+    /* }}} */
+}
+
+
+/// Does the same thing as `compare_regular_files()`, but for sparse files. Only the ranges that
+/// contain data in either file are read, since a range that is a hole in both files reads as
+/// zeros in both files. Like `compare_regular_files_in_ranges()`, the ranges that contain data can
+/// be compared concurrently. Both files must be `file_size` bytes long.
 ///
 /// #### Parameters:
 /// * `first_path` a file path that points to the first file we wish to compare.
-/// * `representative_filetype` a file type, usually derived from one of the two files, that is the
-///     same between the two files pointed to by the two paths.
 /// * `second_path` a file path that points to the second file we wish to compare.
+/// * `file_size` the size (in bytes) of both files.
+/// * `buffer_size` the number of bytes to read from each file at a time.
+/// * `range_count` the number of threads to split the comparison into.
 /// #### Return:
-/// * a `FileCmp` that represents whether the two files are equivalent in terms of substance and
-///     how they are different in this regard if they are.
-fn compare_substance(first_path: &Path, representative_filetype: SimpleFileType,
-    second_path: &Path) -> Result<FileCmp, ()> {
+/// * `Ok(FileCmp)` on success and `Err(())` on failure.
+fn compare_regular_files_sparse(first_path: &Path, second_path: &Path, file_size: u64,
+    buffer_size: usize, range_count: usize) -> Result<FileCmp, ()> {
     /* {{{ */
+    let files = match OpenRegFiles::open(first_path, second_path, buffer_size) {
+        Ok(files) => files,
+        Err(_) => return Err(()),
+    };
 
-    /* TODO: The substance of directories are currently evaluated as being a match simply if both
-     * directories exist. I'm not sure if there's anything else to evaluate with directories */
-    match representative_filetype {
-        SimpleFileType::Directory => return Ok(FileCmp::Match),
-        SimpleFileType::RegFile => return compare_regular_files(first_path, second_path),
-        SimpleFileType::SoftLink => return compare_soft_links(first_path, second_path),
-        /* TODO: No other file types have support. At the moment, the commented out line below
-         * would have them treated the same way directories are: if they both exist, and are of the
-         * same type, return that they match. */
-        // _ => return Ok(FileCmp::Match),
+    /* If the holes of either file can't be found, every byte of both files has to be read */
+    let mut ranges = match (data_ranges(&files.first_file, file_size),
+        data_ranges(&files.second_file, file_size)) {
+        (Some(first_ranges), Some(second_ranges)) => [first_ranges, second_ranges].concat(),
+        _ => Vec::from([(0, file_size)]),
+    };
+    ranges.sort_unstable();
+
+    /* Compare the union of the ranges that contain data in either file */
+    let mut merged: Vec<(u64, u64)> = Vec::new();
+    let mut i = 0;
+    while i < ranges.len() {
+        let (start, mut end) = ranges[i];
+        i += 1;
+        while i < ranges.len() && ranges[i].0 <= end {
+            end = std::cmp::max(end, ranges[i].1);
+            i += 1;
+        }
+        merged.push((start, end));
     }
+
+    return compare_file_ranges_concurrently(&files, &merged, range_count);
     /* }}} */
 }
 
 
-/// A helper function for `compare_files()`. Takes two paths that point to two files of the same
-/// file type and returns a `Result` that either contains a `FileCmp` that represents how the two
-/// files (understood in the broad sense) compare in terms of their modification time or an `Err`
-/// indicating that an error occurred in the process of comparing the two files.
+/// A helper function for `compare_files()`. Takes two paths that point to two regular files with
+/// identical contents and returns a `Result` that either contains a `FileCmp` that represents
+/// whether the two files have their holes in the same places or an `Err` indicating that an error
+/// occurred in the process of comparing the two files.
 ///
 /// #### Parameters:
-/// * `first_metadata` the file metadata of the first file we wish to compare.
-/// * `second_metadata` the file metadata of the second file we wish to compare.
+/// * `first_path` a file path that points to the first file we wish to compare.
+/// * `second_path` a file path that points to the second file we wish to compare.
+/// * `file_size` the size (in bytes) of both files.
 /// #### Return:
-/// * a `FileCmp` that represents whether the two files are equivalent in terms of their
-///     modification time.
-fn compare_modification_time(first_metadata: &Metadata,
-    second_metadata: &Metadata) -> Result<FileCmp, ()> {
+/// * a `FileCmp` that represents whether the two files are equivalent in terms of sparseness. If
+///     the holes of the files can't be found, they are deemed equivalent.
+fn compare_sparseness(first_path: &Path, second_path: &Path, file_size: u64) ->
+    Result<FileCmp, ()> {
     /* {{{ */
-
-    match first_metadata.modified() {
-        Ok(first_mod_time) => match second_metadata.modified() {
-            Ok(second_mod_time) => {
-                match first_mod_time == second_mod_time {
-                    true => (),
-                    false => return Ok(FileCmp::MetadataModificationTimeMismatch),
-                }
-            },
-            Err(_) => return Err(()),
+    match (File::open(first_path), File::open(second_path)) {
+        (Ok(first_file), Ok(second_file)) => {
+            match (data_ranges(&first_file, file_size), data_ranges(&second_file, file_size)) {
+                (Some(first_ranges), Some(second_ranges)) if first_ranges != second_ranges => {
+                    return Ok(FileCmp::MetadataSparsenessMismatch);
+                },
+                _ => return Ok(FileCmp::Match),
+            }
         },
-        Err(_) => return Err(()),
-    };
+        _ => return Err(()),
+    }
+    /* }}} */
+}
 
-    return Ok(FileCmp::Match);
+
+/// A helper function for `compare_regular_files_sampled()`. Advances the state of a splitmix64
+/// pseudorandom number generator and returns the next number it generates. splitmix64 is not
+/// suitable for anything security related, but it is fast and its output only depends on the seed
+/// it started from, which is what makes sampling reproducible.
+///
+/// #### Parameters:
+/// * `state` the state of the generator, which is updated in place.
+/// #### Return:
+/// * the next pseudorandom number.
+fn splitmix64(state: &mut u64) -> u64 {
+    /* {{{ */
+    *state = state.wrapping_add(0x9E3779B97F4A7C15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+    return z ^ (z >> 31);
     /* }}} */
 }
 
 
-/// Takes two paths and returns a `Result` that either contains a `PartialFileComparison` that
-/// represents how the two files (understood in the broad sense) pointed to by the two paths
-/// compare or an `Err` indicating that an error occurred in the process of comparing the two
-/// files.
+/// Compares two regular files of the same size by comparing `sample_count` blocks of
+/// `block_size` bytes, chosen at random, rather than every byte of the files. The blocks depend
+/// only on `seed`, `key` and the size of the files, so running the same comparison again samples
+/// the very same blocks. Files that don't have more than `sample_count` blocks are compared in
+/// their entirety.
 ///
 /// #### Parameters:
-/// * `config` a `Config` representing a configuration for executing `cmp-tree`, usually modified
-///     through command line arguments to the program.
 /// * `first_path` a file path that points to the first file we wish to compare.
 /// * `second_path` a file path that points to the second file we wish to compare.
+/// * `file_size` the size (in bytes) of both files.
+/// * `block_size` the size (in bytes) of every sampled block.
+/// * `sample_count` the number of blocks to sample.
+/// * `seed` the seed every file pair's blocks are chosen from.
+/// * `key` something that identifies the file pair (e.g. its relative path), so that different
+///     file pairs get different blocks out of the same seed.
 /// #### Return:
-/// * a `PartialFileComparison` that represents whether the two files are equivalent, if they
-///     differ and how they differ, as well as the two file types of the files.
-pub fn compare_files(config: &Config, first_path: &Path, second_path: &Path) ->
-    Result<PartialFileComparison, ()> {
+/// * `Ok(FileCmp::MatchSampled)` if every sampled block matched, `Ok(FileCmp::Match)` if the
+///     files were compared in their entirety and matched, another `Ok(FileCmp)` if they differ and
+///     `Err(())` on failure.
+fn compare_regular_files_sampled(first_path: &Path, second_path: &Path, file_size: u64,
+    block_size: usize, sample_count: u64, seed: u64, key: &[u8]) -> Result<FileCmp, ()> {
     /* {{{ */
+    let block_size_u64 = std::cmp::max(block_size, 1) as u64;
+    let blocks = file_size.div_ceil(block_size_u64);
+    if blocks <= sample_count {
+        return compare_regular_files(first_path, second_path, block_size);
+    }
 
-    let mut ret_partial_cmp: PartialFileComparison;
-
-    /* 1. Compare the existence of both files */
-    match compare_existences(first_path, second_path) {
-        Ok(existence_cmp) => {
-            ret_partial_cmp = PartialFileComparison {
-                first_ft: None,
-                second_ft: None,
-                file_cmp: existence_cmp,
-            };
-            /* If both files don't exist at this point, we can return that they experienced a
-             * mismatch. However, so long as one of them exists, we want to get the file type of
-             * that existing file. Normally we would perform an early return here if there was a
-             * mismatch, but we will delay the return until we get the file types */
-        },
+    let files = match OpenRegFiles::open(first_path, second_path, block_size) {
+        Ok(files) => files,
         Err(_) => return Err(()),
+    };
+
+    /* Mix the key into the seed (with FNV-1a) so that every file pair has its own blocks */
+    let mut state = seed;
+    for byte in key {
+        state = (state ^ *byte as u64).wrapping_mul(0x100000001B3);
     }
+    /* Read the blocks in order, so that reading them is as sequential as it can be */
+    let mut sampled_blocks: Vec<u64> = (0..sample_count)
+        .map(|_| splitmix64(&mut state) % blocks)
+        .collect();
+    sampled_blocks.sort_unstable();
+    sampled_blocks.dedup();
 
-    /* INTERMEDIATE: Get the metadata of the two files. We will need this metadata for several
-     * types of comparisons coming up. */
-    let first_metadata: Metadata;
-    let second_metadata: Metadata;
+    for block in sampled_blocks {
+        let start = block * block_size_u64;
+        let end = std::cmp::min(start + block_size_u64, file_size);
+        match compare_file_range(&files, start, end) {
+            Ok(FileCmp::Match) => (),
+            other => return other,
+        }
+    }
 
-    match get_metadata(first_path, second_path) {
-        /* If we were able to successfully get the metadata from both files, save the metadata
-         * and continue execution */
-        Ok((Some(first_meta), Some(second_meta))) => {
-            first_metadata = first_meta;
-            second_metadata = second_meta;
-        },
-        /* If we were only able to get the metadata for one file (which is what would happen if the
-         * existence comparison resulted in a mismatch), save the file type of the existing file
-         * and return early */
-        Ok((Some(first_meta), None)) => {
-            match SimpleFileType::try_from(&first_meta.file_type()) {
-                Ok(ft) => ret_partial_cmp.first_ft = Some(ft),
-                /* If we weren't able to get a `SimpleFileType` for the first file, return early
-                 * with an error */
-                Err(_) => return Err(()),
-            }
-            ret_partial_cmp.second_ft = None;
-            return Ok(ret_partial_cmp);
-        },
-        /* Same as previous comment */
-        Ok((None, Some(second_meta))) => {
-            ret_partial_cmp.first_ft = None;
-            match SimpleFileType::try_from(&second_meta.file_type()) {
-                Ok(ft) => ret_partial_cmp.second_ft = Some(ft),
+    return Ok(FileCmp::MatchSampled);
+    /* }}} */
+}
+
+
+/// A helper function for `normalize_link_target()`. Takes a path and lexically normalizes it,
+/// removing all `.` components and resolving every `..` component that follows a regular
+/// component. The file system is never consulted, so soft links along the path are not resolved.
+///
+/// #### Parameters:
+/// * `path` the file path we wish to normalize.
+/// #### Return:
+/// * the normalized file path. For example, `a/./b/../c/` becomes `a/c` and `./../d` becomes
+///     `../d`.
+fn normalize_lexically(path: &Path) -> PathBuf {
+    /* {{{ */
+    let mut ret = PathBuf::new();
+
+    for component in path.components() {
+        match component {
+            Component::CurDir => (),
+            Component::ParentDir => {
+                /* A `..` can only cancel out a regular component. If there is none to cancel out
+                 * (e.g. `../a`), the `..` has to be kept. At the root of the file system however,
+                 * `..` refers to the root itself */
+                match ret.components().next_back() {
+                    Some(Component::Normal(_)) => { ret.pop(); },
+                    Some(Component::RootDir) | Some(Component::Prefix(_)) => (),
+                    _ => ret.push(".."),
+                }
+            },
+            _ => ret.push(component),
+        }
+    }
+
+    return ret;
+    /* }}} */
+}
+
+
+/// A helper function for `compare_soft_links()`. Takes the link path of a soft link and rewrites
+/// it so that it no longer depends on the location of the directory tree the soft link is in.
+/// Relative link paths are made relative to `root` instead of to the directory containing the
+/// soft link, and absolute link paths that point inside the directory tree rooted at `root` are
+/// made relative to `root` as well. The rewritten link path is then normalized lexically. This
+/// means that `lib -> ./lib64`, `lib -> lib64` and `lib -> /path/to/root/lib64` all end up with
+/// the same link path.
+///
+/// #### Parameters:
+/// * `root` a file path that points to the root directory of the directory tree the soft link is
+///     in.
+/// * `link` a file path that points to the soft link. It must begin with `root`.
+/// * `link_target` the link path of the soft link.
+/// #### Return:
+/// * the rewritten link path. Absolute link paths that point outside the directory tree rooted at
+///     `root` remain absolute.
+fn normalize_link_target(root: &Path, link: &Path, link_target: &Path) -> PathBuf {
+    /* {{{ */
+    if link_target.is_absolute() {
+        let normalized_target = normalize_lexically(link_target);
+        /* The absolute link path may have been written in terms of the root as given, or in terms
+         * of where the root actually resides (e.g. if the root itself is reached through a soft
+         * link), so try both */
+        let possible_roots = [std::path::absolute(root), root.canonicalize()];
+        for possible_root in possible_roots.into_iter().flatten() {
+            let normalized_root = normalize_lexically(&possible_root);
+            if let Ok(root_relative_target) = normalized_target.strip_prefix(&normalized_root) {
+                return root_relative_target.to_path_buf();
+            }
+        }
+        return normalized_target;
+    }
+
+    /* Relative link paths are relative to the directory the soft link is in, so find where that
+     * directory is relative to the root */
+    let link_dir = match link.strip_prefix(root) {
+        Ok(root_relative_link) => root_relative_link.parent().unwrap_or(Path::new("")),
+        Err(_) => Path::new(""),
+    };
+    return normalize_lexically(&link_dir.join(link_target));
+    /* }}} */
+}
+
+
+/// Takes two paths and a result representing how the soft links compare. Both file paths must
+/// point to soft links and both soft links must exist.
+///
+/// #### Parameters:
+/// * `config` a `Config` representing a configuration for executing `cmp-tree`, usually modified
+///     through command line arguments to the program.
+/// * `first_root` a file path that points to the root directory of the directory tree the first
+///     soft link is in.
+/// * `first_path` a file path that points to the first soft link we wish to compare.
+/// * `second_root` a file path that points to the root directory of the directory tree the second
+///     soft link is in.
+/// * `second_path` a file path that points to the second soft link we wish to compare.
+/// #### Return:
+/// * `Ok(FileCmp)` on success and `Err(())` on failure.
+fn compare_soft_links(config: &Config, first_root: &Path, first_path: &Path, second_root: &Path,
+    second_path: &Path) -> Result<FileCmp, ()> {
+    /* {{{ */
+    match read_link(first_path) {
+        Ok(mut first_link_target) => match read_link(second_path) {
+            Ok(mut second_link_target) => {
+                /* If requested, rewrite both link paths so that link paths that are written
+                 * differently but point to the same place within their directory trees are
+                 * identical */
+                if config.normalize_link_targets {
+                    first_link_target =
+                        normalize_link_target(first_root, first_path, &first_link_target);
+                    second_link_target =
+                        normalize_link_target(second_root, second_path, &second_link_target);
+                }
+                /* If the two soft links point to the same file */
+                if first_link_target == second_link_target {
+                    return Ok(FileCmp::Match);
+                /* If the two soft links point to a different file */
+                } else {
+                    return Ok(FileCmp::SubstanceSoftLinkLinkMismatch);
+                }
+            },
+            Err(_) => {
+                return Err(());
+            }
+        },
+        Err(_) => {
+            return Err(());
+        }
+    }
+    /* }}} */
+}
+
+
+/// Takes the metadata of two files (soft links not followed), if the files exist, and returns a
+/// `Result` that either contains a `FileCmp` that represents how the two files (understood in the
+/// broad sense) compare in terms of their existence or an `Err` indicating that an error occurred
+/// in the process of comparing the two files.
+///
+/// #### Parameters:
+/// * `first_link_metadata` the metadata of the first file we wish to compare, or `None` if it
+///     does not exist.
+/// * `second_link_metadata` the metadata of the second file we wish to compare, or `None` if it
+///     does not exist.
+/// #### Return:
+/// * a `FileCmp` that represents whether the two files are equivalent in terms of existence and
+///     how they are different in this regard if they are.
+fn compare_existences(first_link_metadata: &Option<Metadata>,
+    second_link_metadata: &Option<Metadata>) -> Result<FileCmp, ()> {
+    /* {{{ */
+    /* Since soft links were not followed when getting the metadata, a soft link exists even if
+     * the file it points to does not */
+    let first_existence: bool = first_link_metadata.is_some();
+    let second_existence: bool = second_link_metadata.is_some();
+
+    if first_existence && second_existence {
+        return Ok(FileCmp::Match);
+    /* If only one of the file paths points to an existing file, note which file exists */
+    } else if first_existence && !second_existence {
+        return Ok(FileCmp::ExistenceOnlyFirstFile);
+    } else if !first_existence && second_existence {
+        return Ok(FileCmp::ExistenceOnlySecondFile);
+    /* !first_existence && !second_existence */
+    } else {
+        return Ok(FileCmp::ExistenceNeitherFile);
+    }
+    /* }}} */
+}
+
+
+/// Takes a path and returns the metadata of the file it points to without following soft links,
+/// or `None` if there is no such file (or its metadata cannot be read). This is the only stat
+/// `compare_files()` needs per file, unless soft links are being followed.
+///
+/// #### Parameters:
+/// * `path` a file path that points to the file whose metadata we wish to get.
+/// #### Return:
+/// * an `Option<Metadata>` containing the metadata of the file itself if it exists.
+pub fn get_link_metadata(path: &Path) -> Option<Metadata> {
+    /* {{{ */
+    match path.symlink_metadata() {
+        Ok(md) => return Some(md),
+        Err(_) => return None,
+    }
+    /* }}} */
+}
+
+
+/// A helper function for `get_metadata()`. Takes a path and the metadata of the file it points to
+/// (soft links not followed) and returns a `Result` that either contains possibly the metadata the
+/// file should be compared by or an `Err` indicating that an error occurred in the process of
+/// acquiring the metadata.
+///
+/// #### Parameters:
+/// * `config` a `Config` representing a configuration for executing `cmp-tree`, usually modified
+///     through command line arguments to the program.
+/// * `path` a file path that points to the file whose metadata we wish to get.
+/// * `link_metadata` the metadata of the file itself, or `None` if it does not exist.
+/// #### Return:
+/// * a `Result<Option<Metadata>, ()>` that either contains the metadata of the file (or `None` if
+///     no file exists at `path`) or an Err indicating that this function failed to get the
+///     metadata of the file successfully.
+fn get_file_metadata(config: &Config, path: &Path, link_metadata: &Option<Metadata>) ->
+    Result<Option<Metadata>, ()> {
+    /* {{{ */
+    match link_metadata {
+        /* When following soft links, we want the metadata of the file the soft link resolves to.
+         * If the soft link is dangling, there is no such file, so we fall back to the metadata of
+         * the soft link itself */
+        Some(md) if md.file_type().is_symlink() && config.symlink_mode != SymlinkMode::Compare => {
+            match path.metadata() {
+                Ok(target_md) => return Ok(Some(target_md)),
+                Err(_) => return Ok(Some(md.clone())),
+            }
+        },
+        Some(md) => return Ok(Some(md.clone())),
+        None => return Ok(None),
+    }
+    /* }}} */
+}
+
+
+/// A helper function for `compare_files()`. Takes two paths and the metadata of the files they
+/// point to (soft links not followed) and returns a `Result` that either contains a tuple of two
+/// `Option<Metadata>`s (representing possibly the metadata the two files should be compared by)
+/// or an `Err` indicating that an error occurred in the process of acquiring the metadata.
+///
+/// #### Parameters:
+/// * `config` a `Config` representing a configuration for executing `cmp-tree`, usually modified
+///     through command line arguments to the program.
+/// * `first_path` a file path that points to the first file whose metadata we wish to get.
+/// * `first_link_metadata` the metadata of the first file itself, or `None` if it does not exist.
+/// * `second_path` a file path that points to the second file whose metadata we wish to get.
+/// * `second_link_metadata` the metadata of the second file itself, or `None` if it does not
+///     exist.
+/// #### Return:
+/// * a `Result<(Option<Metadata>, Option<Metadata>), ()>` that either contains possibly the
+///     metadata of the two files or an Err indicating that this function failed to get the
+///     metadata on the two files successfully.
+fn get_metadata(config: &Config, first_path: &Path, first_link_metadata: &Option<Metadata>,
+    second_path: &Path, second_link_metadata: &Option<Metadata>) ->
+    Result<(Option<Metadata>, Option<Metadata>), ()> {
+    /* {{{ */
+
+    match get_file_metadata(config, first_path, first_link_metadata) {
+        Ok(first_file_metadata) => match get_file_metadata(config, second_path,
+            second_link_metadata) {
+            Ok(second_file_metadata) => return Ok((first_file_metadata, second_file_metadata)),
+            Err(_) => return Err(()),
+        },
+        Err(_) => return Err(()),
+    }
+    /* }}} */
+}
+
+
+/* What the metadata of two regular files of the same size says about their contents, which
+ * decides how `compare_substance()` goes about comparing them */
+struct RegFileLayout {
+    /* The size (in bytes) of both files */
+    size: u64,
+    /* Whether either file is sparse, in which case the holes both files share need not be read */
+    sparse: bool,
+}
+
+
+/// A helper function for `compare_files()`. Takes two paths that point to two files of the same
+/// file type and returns a `Result` that either contains a `FileCmp` that represents how the two
+/// files (understood in the broad sense) compare in terms of their substance or an `Err`
+/// indicating that an error occurred in the process of comparing the two files.
+///
+/// #### Parameters:
+/// * `config` a `Config` representing a configuration for executing `cmp-tree`, usually modified
+///     through command line arguments to the program.
+/// * `first_root` a file path that points to the root directory of the directory tree the first
+///     file is in.
+/// * `first_path` a file path that points to the first file we wish to compare.
+/// * `representative_filetype` a file type, usually derived from one of the two files, that is the
+///     same between the two files pointed to by the two paths.
+/// * `second_root` a file path that points to the root directory of the directory tree the second
+///     file is in.
+/// * `second_path` a file path that points to the second file we wish to compare.
+/// * `layout` what the metadata of the two files says about their contents. Only meaningful for
+///     regular files, which are only compared once they are known to be the same size.
+/// #### Return:
+/// * a `FileCmp` that represents whether the two files are equivalent in terms of substance and
+///     how they are different in this regard if they are.
+fn compare_substance(config: &Config, first_root: &Path, first_path: &Path,
+    representative_filetype: SimpleFileType, second_root: &Path, second_path: &Path,
+    layout: RegFileLayout) -> Result<FileCmp, ()> {
+    /* {{{ */
+    let file_size = layout.size;
+
+    /* TODO: The substance of directories are currently evaluated as being a match simply if both
+     * directories exist. I'm not sure if there's anything else to evaluate with directories */
+    match representative_filetype {
+        SimpleFileType::Directory => return Ok(FileCmp::Match),
+        SimpleFileType::RegFile => {
+            /* Files that share all of their extents on disk are identical, no need to read them */
+            #[cfg(target_os = "linux")]
+            if config.shared_extents && crate::fiemap::all_extents_shared(first_path, second_path)
+            {
+                return Ok(FileCmp::MatchSharedExtents(file_size));
+            }
+            /* When sampling, only some blocks of big files are compared */
+            if let Some(sample_count) = config.sample_blocks {
+                let key = match first_path.strip_prefix(first_root) {
+                    Ok(rel_path) => rel_path.as_os_str().as_bytes(),
+                    Err(_) => first_path.as_os_str().as_bytes(),
+                };
+                return compare_regular_files_sampled(first_path, second_path, file_size,
+                    config.buffer_size, sample_count, config.sample_seed, key);
+            }
+            /* Very large files are split into ranges that are compared concurrently */
+            let range_count = match config.large_file_threshold {
+                Some(threshold) if file_size >= threshold => crate::number_of_threads(config),
+                _ => 1,
+            };
+            /* Holes that line up in both files don't need to be read */
+            if layout.sparse {
+                return compare_regular_files_sparse(first_path, second_path, file_size,
+                    config.buffer_size, range_count);
+            }
+            if range_count > 1 {
+                return compare_regular_files_in_ranges(first_path, second_path, file_size,
+                    config.buffer_size, range_count);
+            }
+            /* There is no point in allocating a buffer bigger than the files themselves */
+            let buffer_size = std::cmp::min(config.buffer_size as u64, std::cmp::max(file_size, 1))
+                as usize;
+            match config.io_mode {
+                IoMode::Read => {
+                    return compare_regular_files(first_path, second_path, buffer_size);
+                },
+                IoMode::Mmap => {
+                    return compare_regular_files_mmap(first_path, second_path, file_size,
+                        buffer_size);
+                },
+                /* Files that could not be compared in a batch are read like usual */
+                #[cfg(all(feature = "io-uring", target_os = "linux"))]
+                IoMode::Uring => {
+                    return compare_regular_files(first_path, second_path, buffer_size);
+                },
+            }
+        },
+        SimpleFileType::SoftLink => return compare_soft_links(config, first_root, first_path,
+            second_root, second_path),
+        /* TODO: No other file types have support. At the moment, the commented out line below
+         * would have them treated the same way directories are: if they both exist, and are of the
+         * same type, return that they match. */
+        // _ => return Ok(FileCmp::Match),
+    }
+    /* }}} */
+}
+
+
+/// A helper function for `compare_files()`. Takes two paths that point to two regular files that
+/// are not byte-for-byte identical and returns a `Result` that either contains a `FileCmp` that
+/// represents whether the two files are nonetheless equivalent, in one of the ways `config` allows
+/// for, or an `Err` indicating that an error occurred in the process of comparing the two files.
+///
+/// #### Parameters:
+/// * `config` a `Config` representing a configuration for executing `cmp-tree`, usually modified
+///     through command line arguments to the program.
+/// * `first_root` a file path that points to the root directory of the directory tree the first
+///     file belongs to. Masks, structured data globs and external command globs are matched
+///     against the path of the file relative to it.
+/// * `first_path` a file path that points to the first file we wish to compare.
+/// * `first_size` the size (in bytes) of the first file.
+/// * `second_path` a file path that points to the second file we wish to compare.
+/// * `second_size` the size (in bytes) of the second file.
+/// #### Return:
+/// * `FileCmp::Equivalent` if the two files are equivalent,
+///     `FileCmp::SubstanceStructuredDataMismatch` if they are structured data files that hold
+///     different data, `FileCmp::SubstanceElfSectionMismatch` if they are ELF files that differ in
+///     the sections being compared, `FileCmp::SubstanceImageDimensionsMismatch` or
+///     `FileCmp::SubstanceImagePixelMismatch` if they are images that differ in what they look
+///     like, `FileCmp::SubstanceExternalCommandFailure` if the external command they are compared
+///     with failed and `FileCmp::SubstanceRegFileContentMismatch` if they are not equivalent
+///     otherwise.
+fn compare_equivalence(config: &Config, first_root: &Path, first_path: &Path, first_size: u64,
+    second_path: &Path, second_size: u64) -> Result<FileCmp, ()> {
+    /* {{{ */
+    let rel_path = first_path.strip_prefix(first_root).unwrap_or(first_path);
+    let masks: Vec<&Mask> = config.masks.iter().filter(|m| m.glob.is_match(rel_path)).collect();
+    /* An external command configured for the files has the final say over how they compare */
+    if let Some(command) = external::command_for(config, rel_path) {
+        match external::compare_externally(config, command, first_path, second_path) {
+            Ok(true) => return Ok(FileCmp::Equivalent(Equivalence::External)),
+            Ok(false) => return Ok(FileCmp::SubstanceRegFileContentMismatch),
+            Err(reason) => return Ok(FileCmp::SubstanceExternalCommandFailure(reason)),
+        }
+    }
+    /* Compressed files are decompressed as they are compared, so they can be of any size */
+    if config.decompress {
+        match decompress::compare_decompressed(first_path, second_path, config.buffer_size) {
+            Ok(true) => return Ok(FileCmp::Equivalent(Equivalence::Decompressed)),
+            Ok(false) => (),
+            Err(_) => return Err(()),
+        }
+    }
+    /* ELF files are memory-mapped and compared section by section, so they can be of any size */
+    if config.elf {
+        match elf::compare_elf_files(first_path, second_path, &config.elf_sections) {
+            Ok(Some(differing)) => match differing.is_empty() {
+                true => return Ok(FileCmp::Equivalent(Equivalence::ElfSections)),
+                false => return Ok(FileCmp::SubstanceElfSectionMismatch(differing)),
+            },
+            Ok(None) => (),
+            Err(_) => return Err(()),
+        }
+    }
+    /* Images are decoded in their entirety, but are compared by their pixels rather than their
+     * bytes, so their size is only bounded by the limits of the decoders */
+    if config.images {
+        match images::compare_images(first_path, second_path, config.image_tolerance) {
+            Ok(Some(ImageCmp::SamePixels)) => return Ok(FileCmp::Equivalent(Equivalence::Pixels)),
+            Ok(Some(ImageCmp::WithinTolerance)) => {
+                return Ok(FileCmp::Equivalent(Equivalence::PixelsWithinTolerance));
+            },
+            Ok(Some(ImageCmp::DimensionsMismatch(first_dims, second_dims))) => {
+                return Ok(FileCmp::SubstanceImageDimensionsMismatch(first_dims, second_dims));
+            },
+            Ok(Some(ImageCmp::PixelMismatch(x, y))) => {
+                return Ok(FileCmp::SubstanceImagePixelMismatch(x, y));
+            },
+            Ok(None) => (),
+            Err(_) => return Err(()),
+        }
+    }
+    let as_text = config.text || text::ignores_anything(config) || !masks.is_empty();
+    let format = match config.structured.iter().any(|glob| glob.is_match(rel_path)) {
+        true => structured::format_of(rel_path),
+        false => None,
+    };
+    if !as_text && format.is_none() {
+        return Ok(FileCmp::SubstanceRegFileContentMismatch);
+    }
+    /* Files are read into memory in their entirety to be compared as text or as data */
+    if first_size > text::MAX_TEXT_FILE_SIZE || second_size > text::MAX_TEXT_FILE_SIZE {
+        return Ok(FileCmp::SubstanceRegFileContentMismatch);
+    }
+    let first_contents: Vec<u8>;
+    let second_contents: Vec<u8>;
+    match (std::fs::read(first_path), std::fs::read(second_path)) {
+        (Ok(first), Ok(second)) => {
+            first_contents = first;
+            second_contents = second;
+        },
+        _ => return Err(()),
+    }
+
+    /* Only files that both look like text are compared as text. Masks on their own only ignore
+     * the content they match, so the text is only normalized if something else asks for it */
+    if as_text && text::is_text(&first_contents) && text::is_text(&second_contents) {
+        let normalizes = config.text || text::ignores_anything(config);
+        let mut first_text = match normalizes {
+            true => text::normalize(config, &first_contents),
+            false => first_contents.clone(),
+        };
+        let mut second_text = match normalizes {
+            true => text::normalize(config, &second_contents),
+            false => second_contents.clone(),
+        };
+        if first_text == second_text {
+            return Ok(FileCmp::Equivalent(Equivalence::Text));
+        }
+        /* Text that only differs in whitespace is told apart from text that differs in any other
+         * way */
+        if text::ignores_whitespace(config) {
+            first_text = text::normalize_whitespace(config, &first_text);
+            second_text = text::normalize_whitespace(config, &second_text);
+            if first_text == second_text {
+                return Ok(FileCmp::Equivalent(Equivalence::Whitespace));
+            }
+        }
+        /* Masks are applied last, so that they see text with the whitespace already normalized */
+        if !masks.is_empty() && text::apply_masks(&masks, &first_text)
+            == text::apply_masks(&masks, &second_text) {
+            return Ok(FileCmp::Equivalent(Equivalence::Masked));
+        }
+    }
+
+    /* Files that fail to parse in their format are only reported as differing in their bytes */
+    if let Some(format) = format {
+        match (structured::parse(format, &first_contents), structured::parse(format,
+            &second_contents)) {
+            (Some(first_data), Some(second_data)) => {
+                match structured::first_difference(&first_data, &second_data) {
+                    Some(key_path) => {
+                        return Ok(FileCmp::SubstanceStructuredDataMismatch(key_path));
+                    },
+                    None => return Ok(FileCmp::Equivalent(Equivalence::Semantic)),
+                }
+            },
+            _ => (),
+        }
+    }
+
+    return Ok(FileCmp::SubstanceRegFileContentMismatch);
+    /* }}} */
+}
+
+
+/// A helper function for `compare_files()`. Takes two paths that point to two files of the same
+/// file type and returns a `Result` that either contains a `FileCmp` that represents how the two
+/// files (understood in the broad sense) compare in terms of their modification time or an `Err`
+/// indicating that an error occurred in the process of comparing the two files.
+///
+/// #### Parameters:
+/// * `first_metadata` the file metadata of the first file we wish to compare.
+/// * `second_metadata` the file metadata of the second file we wish to compare.
+/// #### Return:
+/// * a `FileCmp` that represents whether the two files are equivalent in terms of their
+///     modification time.
+fn compare_modification_time(first_metadata: &Metadata,
+    second_metadata: &Metadata) -> Result<FileCmp, ()> {
+    /* {{{ */
+
+    match first_metadata.modified() {
+        Ok(first_mod_time) => match second_metadata.modified() {
+            Ok(second_mod_time) => {
+                match first_mod_time == second_mod_time {
+                    true => (),
+                    false => return Ok(FileCmp::MetadataModificationTimeMismatch),
+                }
+            },
+            Err(_) => return Err(()),
+        },
+        Err(_) => return Err(()),
+    };
+
+    return Ok(FileCmp::Match);
+    /* }}} */
+}
+
+
+/// A helper function for `compare_files()`. Takes the metadata of two files of the same file type
+/// and returns a `FileCmp` that represents how the two files compare in terms of their permission
+/// bits (the set-user-ID, set-group-ID and sticky bits included).
+///
+/// #### Parameters:
+/// * `first_metadata` the file metadata of the first file we wish to compare.
+/// * `second_metadata` the file metadata of the second file we wish to compare.
+/// #### Return:
+/// * a `FileCmp` that represents whether the two files are equivalent in terms of their
+///     permission bits.
+fn compare_mode(first_metadata: &Metadata, second_metadata: &Metadata) -> FileCmp {
+    /* {{{ */
+    match first_metadata.mode() & 0o7777 == second_metadata.mode() & 0o7777 {
+        true => return FileCmp::Match,
+        false => return FileCmp::MetadataModeMismatch,
+    }
+    /* }}} */
+}
+
+
+/// Takes two paths and returns a `Result` that either contains a `PartialFileComparison` that
+/// represents how the two files (understood in the broad sense) pointed to by the two paths
+/// compare or an `Err` indicating that an error occurred in the process of comparing the two
+/// files.
+///
+/// #### Parameters:
+/// * `config` a `Config` representing a configuration for executing `cmp-tree`, usually modified
+///     through command line arguments to the program.
+/// * `first_root` a file path that points to the root directory of the directory tree the first
+///     file is in.
+/// * `first_path` a file path that points to the first file we wish to compare.
+/// * `second_root` a file path that points to the root directory of the directory tree the second
+///     file is in.
+/// * `second_path` a file path that points to the second file we wish to compare.
+/// #### Return:
+/// * a `PartialFileComparison` that represents whether the two files are equivalent, if they
+///     differ and how they differ, as well as the two file types of the files.
+pub fn compare_files(config: &Config, first_root: &Path, first_path: &Path, second_root: &Path,
+    second_path: &Path) -> Result<PartialFileComparison, ()> {
+    /* {{{ */
+    let pair = FilePair {
+        first_path: first_path.to_path_buf(),
+        first_link_metadata: get_link_metadata(first_path),
+        second_path: second_path.to_path_buf(),
+        second_link_metadata: get_link_metadata(second_path),
+    };
+    return compare_file_pair(config, first_root, second_root, &pair, None);
+    /* }}} */
+}
+
+
+/// Does the same thing as `compare_files()`, but takes the metadata of the two files (soft links
+/// not followed) instead of getting it itself. This lets callers that already have the metadata
+/// at hand, such as a directory walk, avoid stat'ing every file a second time.
+///
+/// #### Parameters:
+/// * `config` a `Config` representing a configuration for executing `cmp-tree`, usually modified
+///     through command line arguments to the program.
+/// * `first_root` a file path that points to the root directory of the directory tree the first
+///     file is in.
+/// * `second_root` a file path that points to the root directory of the directory tree the second
+///     file is in.
+/// * `pair` the two files we wish to compare, alongside their metadata as returned by
+///     `get_link_metadata()`.
+/// * `contents_cmp` the result of comparing the contents of the two files, if it was already
+///     computed by some other means (such as `compare_file_pairs()` batching the comparison of
+///     many small files). Only used if both files turn out to be regular files of the same size.
+/// #### Return:
+/// * a `PartialFileComparison` that represents whether the two files are equivalent, if they
+///     differ and how they differ, as well as the two file types of the files.
+pub fn compare_file_pair(config: &Config, first_root: &Path, second_root: &Path, pair: &FilePair,
+    contents_cmp: Option<Result<FileCmp, ()>>) -> Result<PartialFileComparison, ()> {
+    /* {{{ */
+    let first_path: &Path = &pair.first_path;
+    let second_path: &Path = &pair.second_path;
+    let first_link_metadata = &pair.first_link_metadata;
+    let second_link_metadata = &pair.second_link_metadata;
+
+    let mut ret_partial_cmp: PartialFileComparison;
+
+    /* 1. Compare the existence of both files */
+    match compare_existences(first_link_metadata, second_link_metadata) {
+        Ok(existence_cmp) => {
+            ret_partial_cmp = PartialFileComparison {
+                first_ft: None,
+                second_ft: None,
+                file_cmp: existence_cmp,
+            };
+            /* If both files don't exist at this point, we can return that they experienced a
+             * mismatch. However, so long as one of them exists, we want to get the file type of
+             * that existing file. Normally we would perform an early return here if there was a
+             * mismatch, but we will delay the return until we get the file types */
+        },
+        Err(_) => return Err(()),
+    }
+
+    /* INTERMEDIATE: Get the metadata of the two files. We will need this metadata for several
+     * types of comparisons coming up. */
+    let first_metadata: Metadata;
+    let second_metadata: Metadata;
+
+    match get_metadata(config, first_path, first_link_metadata, second_path,
+        second_link_metadata) {
+        /* If we were able to successfully get the metadata from both files, save the metadata
+         * and continue execution */
+        Ok((Some(first_meta), Some(second_meta))) => {
+            first_metadata = first_meta;
+            second_metadata = second_meta;
+        },
+        /* If we were only able to get the metadata for one file (which is what would happen if the
+         * existence comparison resulted in a mismatch), save the file type of the existing file
+         * and return early */
+        Ok((Some(first_meta), None)) => {
+            match SimpleFileType::try_from(&first_meta.file_type()) {
+                Ok(ft) => ret_partial_cmp.first_ft = Some(ft),
+                /* If we weren't able to get a `SimpleFileType` for the first file, return early
+                 * with an error */
+                Err(_) => return Err(()),
+            }
+            ret_partial_cmp.second_ft = None;
+            return Ok(ret_partial_cmp);
+        },
+        /* Same as previous comment */
+        Ok((None, Some(second_meta))) => {
+            ret_partial_cmp.first_ft = None;
+            match SimpleFileType::try_from(&second_meta.file_type()) {
+                Ok(ft) => ret_partial_cmp.second_ft = Some(ft),
                 /* If we weren't able to get a `SimpleFileType` for the second file, return early
                  * with an error */
                 Err(_) => return Err(()),
@@ -370,131 +1281,1084 @@ pub fn compare_files(config: &Config, first_path: &Path, second_path: &Path) ->
             ret_partial_cmp.second_ft = None;
             return Ok(ret_partial_cmp);
         },
-        Err(_) => return Err(()),
+        Err(_) => return Err(()),
+    }
+
+    /* 2. Compare the file types of both files. */
+    let first_filetype = first_metadata.file_type();
+    let second_filetype = second_metadata.file_type();
+    /* Update the file types in our return struct now that we have them */
+    ret_partial_cmp.first_ft =
+        match SimpleFileType::try_from(&first_filetype) {
+            Ok(ft) => Some(ft),
+            Err(_) => None,
+        };
+    ret_partial_cmp.second_ft =
+        match SimpleFileType::try_from(&second_filetype) {
+            Ok(ft) => Some(ft),
+            Err(_) => None,
+        };
+
+    /* When following soft links, the only soft links left at this point are dangling ones, since
+     * every other soft link has been resolved to the file it points to. */
+    if config.symlink_mode != SymlinkMode::Compare {
+        /* If only one of the two files is a dangling soft link, return early. If both are, they
+         * will be compared by their link paths */
+        match (&ret_partial_cmp.first_ft, &ret_partial_cmp.second_ft) {
+            (Some(SimpleFileType::SoftLink), Some(SimpleFileType::SoftLink)) => (),
+            (Some(SimpleFileType::SoftLink), _) => {
+                ret_partial_cmp.file_cmp = FileCmp::FileTypeDanglingSoftLinkOnlyFirstFile;
+                return Ok(ret_partial_cmp);
+            },
+            (_, Some(SimpleFileType::SoftLink)) => {
+                ret_partial_cmp.file_cmp = FileCmp::FileTypeDanglingSoftLinkOnlySecondFile;
+                return Ok(ret_partial_cmp);
+            },
+            _ => (),
+        }
+    }
+
+    /* If the two paths point to files that are of different types (e.g. a directory vs. a symlink,
+     * a directory vs a regular file) then return early */
+    if ret_partial_cmp.first_ft != ret_partial_cmp.second_ft {
+        ret_partial_cmp.file_cmp = FileCmp::FileTypeTypeMismatch;
+        return Ok(ret_partial_cmp);
+    }
+
+    /* When following soft links, but also comparing their link paths, two corresponding files
+     * must either both be soft links with identical link paths or both not be soft links at all */
+    if config.symlink_mode == SymlinkMode::FollowAndCompare {
+        let is_symlink = |md: &Option<Metadata>| match md {
+            Some(md) => md.file_type().is_symlink(),
+            None => false,
+        };
+        match (is_symlink(first_link_metadata), is_symlink(second_link_metadata)) {
+            (true, true) => match compare_soft_links(config, first_root, first_path, second_root,
+                second_path) {
+                Ok(FileCmp::Match) => (),
+                Ok(link_cmp) => {
+                    ret_partial_cmp.file_cmp = link_cmp;
+                    return Ok(ret_partial_cmp);
+                },
+                Err(_) => return Err(()),
+            },
+            (false, false) => (),
+            (true, false) => {
+                ret_partial_cmp.first_ft = Some(SimpleFileType::SoftLink);
+                ret_partial_cmp.file_cmp = FileCmp::FileTypeTypeMismatch;
+                return Ok(ret_partial_cmp);
+            },
+            (false, true) => {
+                ret_partial_cmp.second_ft = Some(SimpleFileType::SoftLink);
+                ret_partial_cmp.file_cmp = FileCmp::FileTypeTypeMismatch;
+                return Ok(ret_partial_cmp);
+            },
+        }
+    }
+
+    /* 3. Compare the substance of both files. */
+    let is_reg_file = ret_partial_cmp.first_ft == Some(SimpleFileType::RegFile);
+    let same_size = first_metadata.len() == second_metadata.len();
+    /* In a shallow comparison, regular files with the same size and modification time are deemed
+     * a match without reading them */
+    if config.shallow && is_reg_file && same_size {
+        match compare_modification_time(&first_metadata, &second_metadata) {
+            Ok(FileCmp::Match) => {
+                ret_partial_cmp.file_cmp = FileCmp::MatchUnverified;
+                return Ok(ret_partial_cmp);
+            },
+            Ok(_) => (),
+            Err(_) => return Err(()),
+        }
+    }
+    let substance_res = match (&ret_partial_cmp.first_ft, contents_cmp) {
+        /* Regular files that differ in size cannot be byte-for-byte identical. Checking this here,
+         * with the metadata we already have, saves stat'ing the files again */
+        (Some(SimpleFileType::RegFile), _) if !same_size =>
+            Ok(FileCmp::SubstanceRegFileContentMismatch),
+        /* If the contents of the two regular files were already compared, don't do it again */
+        (Some(SimpleFileType::RegFile), Some(contents_res)) => contents_res,
+        (Some(ft), _) => compare_substance(config, first_root, first_path, ft.clone(),
+            second_root, second_path, RegFileLayout {
+                size: first_metadata.len(),
+                sparse: is_sparse(&first_metadata) || is_sparse(&second_metadata),
+            }),
+        /* Both files are of a type `SimpleFileType` cannot represent (e.g. FIFOs, sockets or
+         * device files), so their substance cannot be compared */
+        (None, _) => Err(()),
+    };
+    /* Regular files that are not byte-for-byte identical may still be equivalent */
+    let substance_res = match substance_res {
+        Ok(FileCmp::SubstanceRegFileContentMismatch) if is_reg_file => compare_equivalence(config,
+            first_root, first_path, first_metadata.len(), second_path, second_metadata.len()),
+        other => other,
+    };
+    match substance_res {
+
+        Ok(substance_cmp) => {
+            ret_partial_cmp.file_cmp = substance_cmp;
+            /* If the two files did not have identical substance, return early */
+            if !ret_partial_cmp.file_cmp.is_match() {
+                return Ok(ret_partial_cmp);
+            }
+        },
+        Err(_) => return Err(()),
+    }
+
+    /* 3. Compare the metadata of both files. */
+    /* Comparing metadata is optional, and by default is not enabled */
+    if config.compare_modification_times {
+        match compare_modification_time(&first_metadata, &second_metadata) {
+            /* Identical metadata leaves how the substance of the files matched as is */
+            Ok(FileCmp::Match) => (),
+            /* If the two files did not have identical metadata, return early */
+            Ok(metadata_cmp) => {
+                ret_partial_cmp.file_cmp = metadata_cmp;
+                return Ok(ret_partial_cmp);
+            },
+            Err(_) => return Err(()),
+        }
+    }
+    /* So is comparing permission bits. The permission bits of soft links are never used (and
+     * always all set on Linux), so they are not compared */
+    if config.compare_modes && ret_partial_cmp.first_ft != Some(SimpleFileType::SoftLink) {
+        match compare_mode(&first_metadata, &second_metadata) {
+            FileCmp::Match => (),
+            /* If the two files did not have identical permission bits, return early */
+            metadata_cmp => {
+                ret_partial_cmp.file_cmp = metadata_cmp;
+                return Ok(ret_partial_cmp);
+            },
+        }
+    }
+    /* Comparing sparseness is optional too. Regular files that aren't sparse have no holes, so
+     * two of them always have their holes in the same places */
+    if config.compare_sparseness && ret_partial_cmp.first_ft == Some(SimpleFileType::RegFile)
+        && (is_sparse(&first_metadata) || is_sparse(&second_metadata)) {
+        match compare_sparseness(first_path, second_path, first_metadata.len()) {
+            Ok(FileCmp::Match) => (),
+            /* If the two files did not have identical sparseness, return early */
+            Ok(metadata_cmp) => {
+                ret_partial_cmp.file_cmp = metadata_cmp;
+                return Ok(ret_partial_cmp);
+            },
+            Err(_) => return Err(()),
+        }
+    }
+
+    /* If we make it to this point, that means all the types of comparisons have resulted in a
+     * Match. We can return return struct. */
+    return Ok(ret_partial_cmp);
+    /* }}} */
+}
+
+
+/// Compares many pairs of corresponding files, the same way `compare_file_pair()` would compare
+/// each pair. When `config` selects the io_uring I/O engine, the contents of the small regular
+/// files among the pairs are compared in one batch, so that the system calls needed to open and
+/// read them are submitted together rather than one after the other.
+///
+/// #### Parameters:
+/// * `config` a `Config` representing a configuration for executing `cmp-tree`, usually modified
+///     through command line arguments to the program.
+/// * `first_root` a file path that points to the root directory of the directory tree the first
+///     file of every pair is in.
+/// * `second_root` a file path that points to the root directory of the directory tree the second
+///     file of every pair is in.
+/// * `pairs` the pairs of files we wish to compare.
+/// #### Return:
+/// * the result of comparing every pair, in the same order as `pairs`.
+pub fn compare_file_pairs(config: &Config, first_root: &Path, second_root: &Path,
+    pairs: &[FilePair]) -> Vec<Result<PartialFileComparison, ()>> {
+    /* {{{ */
+    let mut contents_cmps: Vec<Option<Result<FileCmp, ()>>> = Vec::new();
+    contents_cmps.resize_with(pairs.len(), || None);
+
+    #[cfg(all(feature = "io-uring", target_os = "linux"))]
+    if config.io_mode == IoMode::Uring {
+        /* Files bigger than this are compared one pair at a time, since reading them into memory
+         * in one go would use too much memory, and the system calls are no longer what dominates
+         * the cost of comparing them */
+        const MAX_BATCHED_FILE_SIZE: u64 = 1 << 20;
+
+        /* Only batch pairs of regular files that are known to be the same size (and that aren't
+         * going to be compared in ranges) */
+        let mut batch: Vec<(&Path, &Path, u64)> = Vec::new();
+        let mut batched: Vec<usize> = Vec::new();
+        for (i, pair) in pairs.iter().enumerate() {
+            if let (Some(first_md), Some(second_md)) =
+                (&pair.first_link_metadata, &pair.second_link_metadata) {
+                let size = first_md.len();
+                let split = match config.large_file_threshold {
+                    Some(threshold) => size >= threshold,
+                    None => false,
+                };
+                /* Files a shallow comparison won't read are left out of the batch */
+                let unread = config.shallow && match (first_md.modified(), second_md.modified()) {
+                    (Ok(first_time), Ok(second_time)) => first_time == second_time,
+                    _ => false,
+                };
+                /* As are files that are sampled rather than read in their entirety */
+                let sampled = match config.sample_blocks {
+                    Some(sample_count) => size > sample_count * config.buffer_size as u64,
+                    None => false,
+                };
+                if first_md.is_file() && second_md.is_file() && size == second_md.len()
+                    && size <= MAX_BATCHED_FILE_SIZE && !split && !unread && !sampled {
+                    /* Files that share all of their extents don't need to be read at all */
+                    if config.shared_extents
+                        && crate::fiemap::all_extents_shared(&pair.first_path, &pair.second_path) {
+                        contents_cmps[i] = Some(Ok(FileCmp::MatchSharedExtents(size)));
+                        continue;
+                    }
+                    batch.push((&pair.first_path, &pair.second_path, size));
+                    batched.push(i);
+                }
+            }
+        }
+        if let Some(results) = crate::uring::compare_regular_file_pairs(&batch) {
+            for (i, res) in batched.into_iter().zip(results) {
+                contents_cmps[i] = res;
+            }
+        }
+    }
+
+    return pairs.iter().zip(contents_cmps).map(|(pair, contents_cmp)| {
+        return compare_file_pair(config, first_root, second_root, pair, contents_cmp);
+    }).collect();
+    /* }}} */
+}
+
+
+/* Unit tests */
+#[test]
+fn ut_compare_regular_files_001() {
+    /* {{{ */
+    let first_file = Path::new("../../tests/001/first/Lorem.txt");
+    let second_file = Path::new("../../tests/001/second/Lorem.txt");
+    /* `expected_ret` would be `Ok(FileCmp::Match)` */
+    let expected_ret_content = FileCmp::Match;
+
+    let ret = compare_regular_files(first_file, second_file, 8192);
+    match ret {
+        Ok(ret_content) => {
+            assert_eq!(ret_content, expected_ret_content);
+        },
+        Err(_) => panic!(),
+    }
+    /* }}} */
+}
+
+#[test]
+fn ut_compare_regular_files_002() {
+    /* {{{ */
+    let first_file = Path::new("../../tests/001/first/cmp_man_pages.txt");
+    let second_file = Path::new("../../tests/001/second/cmp_man_pages.txt");
+    /* `expected_ret` would be `Ok(FileCmp::Match)` */
+    let expected_ret_content = FileCmp::Match;
+
+    let ret = compare_regular_files(first_file, second_file, 8192);
+    match ret {
+        Ok(ret_content) => {
+            assert_eq!(ret_content, expected_ret_content);
+        },
+        Err(_) => panic!(),
+    }
+    /* }}} */
+}
+
+#[test]
+fn ut_compare_regular_files_003() {
+    /* {{{ */
+    /* The two input files are text files with the same words, but every letter (but the very
+     * first) in the first file is in lower case whereas every letter in the second file is
+     * uppercase. */
+    let first_file = Path::new("../../tests/002/first/Lorem.txt");
+    let second_file = Path::new("../../tests/002/second/Lorem.txt");
+    /* `expected_ret` would be `Ok(FileCmp::SubstanceRegFileContentMismatch)` */
+    let expected_ret_content = FileCmp::SubstanceRegFileContentMismatch;
+
+    let ret = compare_regular_files(first_file, second_file, 8192);
+    match ret {
+        Ok(ret_content) => {
+            assert_eq!(ret_content, expected_ret_content);
+        },
+        Err(_) => panic!(),
+    }
+    /* }}} */
+}
+
+#[test]
+fn ut_compare_regular_files_in_ranges_001() {
+    /* {{{ */
+    /* A buffer size that doesn't divide the file size evenly makes sure the last range is cut
+     * short correctly */
+    let first_file = Path::new("../../tests/024/first/identical.bin");
+    let second_file = Path::new("../../tests/024/second/identical.bin");
+    let file_size = first_file.metadata().unwrap().len();
+    let expected_ret_content = FileCmp::Match;
+
+    let ret = compare_regular_files_in_ranges(first_file, second_file, file_size, 1000, 4);
+    match ret {
+        Ok(ret_content) => {
+            assert_eq!(ret_content, expected_ret_content);
+        },
+        Err(_) => panic!(),
+    }
+    /* }}} */
+}
+
+#[test]
+fn ut_compare_regular_files_in_ranges_002() {
+    /* {{{ */
+    /* The files only differ in their very last byte, which is in the last range */
+    let first_file = Path::new("../../tests/024/first/last-byte.bin");
+    let second_file = Path::new("../../tests/024/second/last-byte.bin");
+    let file_size = first_file.metadata().unwrap().len();
+    let expected_ret_content = FileCmp::SubstanceRegFileContentMismatch;
+
+    let ret = compare_regular_files_in_ranges(first_file, second_file, file_size, 4096, 3);
+    match ret {
+        Ok(ret_content) => {
+            assert_eq!(ret_content, expected_ret_content);
+        },
+        Err(_) => panic!(),
+    }
+    /* }}} */
+}
+
+#[test]
+fn ut_compare_regular_files_mmap_001() {
+    /* {{{ */
+    let first_file = Path::new("../../tests/024/first/identical.bin");
+    let second_file = Path::new("../../tests/024/second/identical.bin");
+    let file_size = first_file.metadata().unwrap().len();
+    let expected_ret_content = FileCmp::Match;
+
+    let ret = compare_regular_files_mmap(first_file, second_file, file_size, 8192);
+    match ret {
+        Ok(ret_content) => {
+            assert_eq!(ret_content, expected_ret_content);
+        },
+        Err(_) => panic!(),
+    }
+    /* }}} */
+}
+
+#[test]
+fn ut_compare_regular_files_mmap_002() {
+    /* {{{ */
+    let first_file = Path::new("../../tests/024/first/last-byte.bin");
+    let second_file = Path::new("../../tests/024/second/last-byte.bin");
+    let file_size = first_file.metadata().unwrap().len();
+    let expected_ret_content = FileCmp::SubstanceRegFileContentMismatch;
+
+    let ret = compare_regular_files_mmap(first_file, second_file, file_size, 8192);
+    match ret {
+        Ok(ret_content) => {
+            assert_eq!(ret_content, expected_ret_content);
+        },
+        Err(_) => panic!(),
+    }
+    /* }}} */
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn ut_compare_files_shared_extents_001() {
+    /* {{{ */
+    /* Files that share all of their extents are a match without being read. Where the file system
+     * can't tell where the extents of a file are, they are read like any other files */
+    let mut conf = crate::config::default_config();
+    conf.shared_extents = true;
+    let first_root = Path::new("../../tests/025/first");
+    let second_root = Path::new("../../tests/025/second");
+    let first_extents = |file: &str| match File::open(first_root.join(file)) {
+        Ok(f) => crate::fiemap::file_extents(&f, 1, true),
+        Err(_) => None,
+    };
+    let second_extents = |file: &str| match File::open(second_root.join(file)) {
+        Ok(f) => crate::fiemap::file_extents(&f, 1, true),
+        Err(_) => None,
+    };
+
+    for file in ["hard-linked.txt", "reflinked.txt"] {
+        /* A hard link always shares its extents, but a reflink copy only does on file systems
+         * that support reflinks */
+        let shares_extents = match (first_extents(file), second_extents(file)) {
+            (Some(first), Some(second)) => !first.is_empty() && first == second,
+            _ => false,
+        };
+        let ret = compare_files(&conf, first_root, &first_root.join(file), second_root,
+            &second_root.join(file));
+        match ret {
+            Ok(ret_content) => match shares_extents {
+                true => assert!(matches!(ret_content.file_cmp, FileCmp::MatchSharedExtents(_))),
+                false => assert_eq!(ret_content.file_cmp, FileCmp::Match),
+            },
+            Err(_) => panic!(),
+        }
+    }
+    /* Where the file system can tell, a hard link must be found to share its extents */
+    if first_extents("hard-linked.txt").is_some() {
+        assert_eq!(first_extents("hard-linked.txt"), second_extents("hard-linked.txt"));
+    }
+    /* }}} */
+}
+
+#[test]
+fn ut_compare_files_shared_extents_002() {
+    /* {{{ */
+    /* Files that were written separately never share their extents */
+    let mut conf = crate::config::default_config();
+    conf.shared_extents = true;
+    let first_root = Path::new("../../tests/025/first");
+    let second_root = Path::new("../../tests/025/second");
+
+    let ret = compare_files(&conf, first_root, &first_root.join("copied.txt"), second_root,
+        &second_root.join("copied.txt"));
+    match ret {
+        Ok(ret_content) => assert_eq!(ret_content.file_cmp, FileCmp::Match),
+        Err(_) => panic!(),
+    }
+    /* }}} */
+}
+
+#[test]
+fn ut_compare_files_shared_extents_003() {
+    /* {{{ */
+    /* Files of the same size that don't share their extents are read, and so are found to differ
+     * even if they only do so in their very last byte */
+    let mut conf = crate::config::default_config();
+    conf.shared_extents = true;
+    let first_root = Path::new("../../tests/024/first");
+    let second_root = Path::new("../../tests/024/second");
+
+    let ret = compare_files(&conf, first_root, &first_root.join("last-byte.bin"), second_root,
+        &second_root.join("last-byte.bin"));
+    match ret {
+        Ok(ret_content) => {
+            assert_eq!(ret_content.file_cmp, FileCmp::SubstanceRegFileContentMismatch);
+        },
+        Err(_) => panic!(),
     }
+    /* }}} */
+}
 
-    /* 2. Compare the file types of both files. */
-    let first_filetype = first_metadata.file_type();
-    let second_filetype = second_metadata.file_type();
-    /* Update the file types in our return struct now that we have them */
-    ret_partial_cmp.first_ft =
-        match SimpleFileType::try_from(&first_filetype) {
-            Ok(ft) => Some(ft),
-            Err(_) => None,
-        };
-    ret_partial_cmp.second_ft =
-        match SimpleFileType::try_from(&second_filetype) {
-            Ok(ft) => Some(ft),
-            Err(_) => None,
-        };
-    /* If the two paths point to files that are of different types (e.g. a directory vs. a symlink,
-     * a directory vs a regular file) then return early */
-    if ret_partial_cmp.first_ft != ret_partial_cmp.second_ft {
-        ret_partial_cmp.file_cmp = FileCmp::FileTypeTypeMismatch;
-        return Ok(ret_partial_cmp);
+#[test]
+fn ut_compare_files_shallow_001() {
+    /* {{{ */
+    /* Regular files with the same size and modification time are deemed a match without their
+     * contents being compared, even if their contents differ */
+    let mut conf = crate::config::default_config();
+    conf.shallow = true;
+    let first_root = Path::new("../../tests/026/first");
+    let second_root = Path::new("../../tests/026/second");
+
+    let ret = compare_files(&conf, first_root, &first_root.join("unverified.txt"), second_root,
+        &second_root.join("unverified.txt"));
+    match ret {
+        Ok(ret_content) => assert_eq!(ret_content.file_cmp, FileCmp::MatchUnverified),
+        Err(_) => panic!(),
     }
+    /* }}} */
+}
 
-    /* 3. Compare the substance of both files. */
-    /* We know the unwrap call won't fail because of the large match statement above will return
-     * early on any case where it was not able to get a `SimpleFileType` representation of both
-     * files' file types. */
-    match compare_substance(first_path, ret_partial_cmp.first_ft.clone().unwrap(),
-        second_path) {
+#[test]
+fn ut_compare_files_shallow_002() {
+    /* {{{ */
+    /* Regular files with different modification times have their contents compared */
+    let mut conf = crate::config::default_config();
+    conf.shallow = true;
+    let first_root = Path::new("../../tests/026/first");
+    let second_root = Path::new("../../tests/026/second");
 
-        Ok(substance_cmp) => {
-            ret_partial_cmp.file_cmp = substance_cmp;
-            /* If the two files did not have identical substance, return early */
-            match ret_partial_cmp.file_cmp {
-                FileCmp::Match => (),
-                _ => return Ok(ret_partial_cmp),
-            }
+    let ret = compare_files(&conf, first_root, &first_root.join("touched.txt"), second_root,
+        &second_root.join("touched.txt"));
+    match ret {
+        Ok(ret_content) => assert_eq!(ret_content.file_cmp, FileCmp::Match),
+        Err(_) => panic!(),
+    }
+    /* }}} */
+}
+
+#[test]
+fn ut_compare_regular_files_sparse_001() {
+    /* {{{ */
+    let first_file = Path::new("../../tests/027/first/dense.img");
+    let second_file = Path::new("../../tests/027/second/dense.img");
+    let file_size = first_file.metadata().unwrap().len();
+    let expected_ret_content = FileCmp::Match;
+
+    let ret = compare_regular_files_sparse(first_file, second_file, file_size, 8192, 1);
+    match ret {
+        Ok(ret_content) => {
+            assert_eq!(ret_content, expected_ret_content);
         },
-        Err(_) => return Err(()),
+        Err(_) => panic!(),
     }
+    /* }}} */
+}
 
-    /* 3. Compare the metadata of both files. */
-    /* Comparing metadata is optional, and by default is not enabled */
-    if config.compare_modification_times {
-        match compare_modification_time(&first_metadata, &second_metadata) {
-            Ok(metadata_cmp) => {
-                ret_partial_cmp.file_cmp = metadata_cmp;
-                /* If the two files did not have identical metadata, return early */
-                match ret_partial_cmp.file_cmp {
-                    FileCmp::Match => (),
-                    _ => return Ok(ret_partial_cmp),
-                }
+#[test]
+fn ut_compare_regular_files_sparse_002() {
+    /* {{{ */
+    /* Files without holes are compared in their entirety */
+    let first_file = Path::new("../../tests/024/first/last-byte.bin");
+    let second_file = Path::new("../../tests/024/second/last-byte.bin");
+    let file_size = first_file.metadata().unwrap().len();
+    let expected_ret_content = FileCmp::SubstanceRegFileContentMismatch;
+
+    let ret = compare_regular_files_sparse(first_file, second_file, file_size, 8192, 1);
+    match ret {
+        Ok(ret_content) => {
+            assert_eq!(ret_content, expected_ret_content);
+        },
+        Err(_) => panic!(),
+    }
+    /* }}} */
+}
+
+#[test]
+fn ut_compare_regular_files_sparse_003() {
+    /* {{{ */
+    /* The ranges that contain data can be compared concurrently too */
+    let first_file = Path::new("../../tests/027/first/dense.img");
+    let second_file = Path::new("../../tests/027/second/dense.img");
+    let file_size = first_file.metadata().unwrap().len();
+    let expected_ret_content = FileCmp::Match;
+
+    let ret = compare_regular_files_sparse(first_file, second_file, file_size, 8192, 4);
+    match ret {
+        Ok(ret_content) => {
+            assert_eq!(ret_content, expected_ret_content);
+        },
+        Err(_) => panic!(),
+    }
+    /* }}} */
+}
+
+#[test]
+fn ut_group_ranges_001() {
+    /* {{{ */
+    let ranges = [(0, 10), (100, 130)];
+    let expected_groups = vec![
+        vec![(0, 10), (100, 110)],
+        vec![(110, 130)],
+    ];
+
+    assert_eq!(group_ranges(&ranges, 2, 10), expected_groups);
+    /* A single group holds every range */
+    assert_eq!(group_ranges(&ranges, 1, 10), vec![ranges.to_vec()]);
+    /* }}} */
+}
+
+#[test]
+fn ut_compare_files_text_001() {
+    /* {{{ */
+    /* Text files that only differ in their line endings are equivalent when comparing text, but
+     * a byte order mark or a missing trailing newline still makes them differ */
+    let mut conf = crate::config::default_config();
+    conf.text = true;
+    let first_root = Path::new("../../tests/028/first");
+    let second_root = Path::new("../../tests/028/second");
+
+    for (file, expected_ret_content) in [
+        ("crlf.txt", FileCmp::Equivalent(Equivalence::Text)),
+        ("bom.txt", FileCmp::SubstanceRegFileContentMismatch),
+        ("trailing-newline.txt", FileCmp::SubstanceRegFileContentMismatch),
+        ("binary.bin", FileCmp::SubstanceRegFileContentMismatch),
+    ] {
+        let ret = compare_files(&conf, first_root, &first_root.join(file), second_root,
+            &second_root.join(file));
+        match ret {
+            Ok(ret_content) => assert_eq!(ret_content.file_cmp, expected_ret_content),
+            Err(_) => panic!(),
+        }
+    }
+    /* }}} */
+}
+
+#[test]
+fn ut_compare_files_text_002() {
+    /* {{{ */
+    /* Byte order marks and trailing newlines can be ignored too, but binary files are never
+     * compared as text */
+    let mut conf = crate::config::default_config();
+    conf.text = true;
+    conf.ignore_bom = true;
+    conf.ignore_trailing_newline = true;
+    let first_root = Path::new("../../tests/028/first");
+    let second_root = Path::new("../../tests/028/second");
+
+    for (file, expected_ret_content) in [
+        ("crlf.txt", FileCmp::Equivalent(Equivalence::Text)),
+        ("bom.txt", FileCmp::Equivalent(Equivalence::Text)),
+        ("trailing-newline.txt", FileCmp::Equivalent(Equivalence::Text)),
+        ("binary.bin", FileCmp::SubstanceRegFileContentMismatch),
+    ] {
+        let ret = compare_files(&conf, first_root, &first_root.join(file), second_root,
+            &second_root.join(file));
+        match ret {
+            Ok(ret_content) => assert_eq!(ret_content.file_cmp, expected_ret_content),
+            Err(_) => panic!(),
+        }
+    }
+    /* }}} */
+}
+
+#[test]
+fn ut_compare_files_text_003() {
+    /* {{{ */
+    /* Ignoring part of text files is enough for them to be compared as text, but not for their
+     * line endings to be ignored */
+    let first_root = Path::new("../../tests/028/first");
+    let second_root = Path::new("../../tests/028/second");
+    let mut bom_conf = crate::config::default_config();
+    bom_conf.ignore_bom = true;
+    let mut blank_lines_conf = crate::config::default_config();
+    blank_lines_conf.ignore_blank_lines = true;
+    let mut trailing_newline_conf = crate::config::default_config();
+    trailing_newline_conf.ignore_trailing_newline = true;
+
+    for (conf, file) in [
+        (bom_conf, "bom.txt"),
+        (blank_lines_conf, "crlf.txt"),
+        (trailing_newline_conf, "trailing-newline.txt"),
+    ] {
+        let ret = compare_files(&conf, first_root, &first_root.join(file), second_root,
+            &second_root.join(file));
+        match ret {
+            Ok(ret_content) => {
+                assert_eq!(ret_content.file_cmp, FileCmp::SubstanceRegFileContentMismatch);
             },
-            Err(_) => return Err(()),
+            Err(_) => panic!(),
         }
     }
+    /* }}} */
+}
 
-    /* If we make it to this point, that means all the types of comparisons have resulted in a
-     * Match. We can return return struct. */
-    return Ok(ret_partial_cmp);
+#[test]
+fn ut_compare_files_whitespace_001() {
+    /* {{{ */
+    /* Ignoring trailing whitespace doesn't ignore indentation or blank lines */
+    let mut conf = crate::config::default_config();
+    conf.ignore_trailing_space = true;
+    let first_root = Path::new("../../tests/029/first");
+    let second_root = Path::new("../../tests/029/second");
+
+    for (file, expected_ret_content) in [
+        ("trailing-space.rs", FileCmp::Equivalent(Equivalence::Whitespace)),
+        ("indentation.rs", FileCmp::SubstanceRegFileContentMismatch),
+        ("blank-lines.rs", FileCmp::SubstanceRegFileContentMismatch),
+    ] {
+        let ret = compare_files(&conf, first_root, &first_root.join(file), second_root,
+            &second_root.join(file));
+        match ret {
+            Ok(ret_content) => assert_eq!(ret_content.file_cmp, expected_ret_content),
+            Err(_) => panic!(),
+        }
+    }
     /* }}} */
 }
 
+#[test]
+fn ut_compare_files_whitespace_002() {
+    /* {{{ */
+    let mut conf = crate::config::default_config();
+    conf.ignore_all_space = true;
+    conf.ignore_blank_lines = true;
+    let first_root = Path::new("../../tests/029/first");
+    let second_root = Path::new("../../tests/029/second");
+
+    for file in ["trailing-space.rs", "indentation.rs", "blank-lines.rs"] {
+        let ret = compare_files(&conf, first_root, &first_root.join(file), second_root,
+            &second_root.join(file));
+        match ret {
+            Ok(ret_content) => {
+                assert_eq!(ret_content.file_cmp, FileCmp::Equivalent(Equivalence::Whitespace));
+            },
+            Err(_) => panic!(),
+        }
+    }
+    /* }}} */
+}
 
-/* Unit tests */
 #[test]
-fn ut_compare_regular_files_001() {
+fn ut_splitmix64_001() {
     /* {{{ */
-    let first_file = Path::new("../../tests/001/first/Lorem.txt");
-    let second_file = Path::new("../../tests/001/second/Lorem.txt");
-    /* `expected_ret` would be `Ok(FileCmp::Match)` */
-    let expected_ret_content = FileCmp::Match;
+    /* The first number splitmix64 generates from a state of 0 is well known */
+    let mut state: u64 = 0;
+    assert_eq!(splitmix64(&mut state), 0xE220A8397B1DCDAF);
+    /* }}} */
+}
+
+#[test]
+fn ut_compare_regular_files_sampled_001() {
+    /* {{{ */
+    let first_file = Path::new("../../tests/024/first/identical.bin");
+    let second_file = Path::new("../../tests/024/second/identical.bin");
+    let file_size = first_file.metadata().unwrap().len();
+    let expected_ret_content = FileCmp::MatchSampled;
 
-    let ret = compare_regular_files(&first_file, &second_file);
+    let ret = compare_regular_files_sampled(first_file, second_file, file_size, 8192, 8, 0,
+        b"identical.bin");
     match ret {
         Ok(ret_content) => {
             assert_eq!(ret_content, expected_ret_content);
         },
-        Err(_) => assert!(false),
+        Err(_) => panic!(),
     }
     /* }}} */
 }
 
 #[test]
-fn ut_compare_regular_files_002() {
+fn ut_compare_regular_files_sampled_002() {
     /* {{{ */
-    let first_file = Path::new("../../tests/001/first/cmp_man_pages.txt");
-    let second_file = Path::new("../../tests/001/second/cmp_man_pages.txt");
-    /* `expected_ret` would be `Ok(FileCmp::Match)` */
-    let expected_ret_content = FileCmp::Match;
+    /* Files with no more blocks than the number of blocks to sample are compared in their
+     * entirety, so even a difference in the very last byte is found */
+    let first_file = Path::new("../../tests/024/first/last-byte.bin");
+    let second_file = Path::new("../../tests/024/second/last-byte.bin");
+    let file_size = first_file.metadata().unwrap().len();
+    let expected_ret_content = FileCmp::SubstanceRegFileContentMismatch;
 
-    let ret = compare_regular_files(&first_file, &second_file);
+    let ret = compare_regular_files_sampled(first_file, second_file, file_size, 8192, 1000, 0,
+        b"last-byte.bin");
     match ret {
         Ok(ret_content) => {
             assert_eq!(ret_content, expected_ret_content);
         },
-        Err(_) => assert!(false),
+        Err(_) => panic!(),
     }
     /* }}} */
 }
 
+#[cfg(test)]
+fn test_mask(glob: &str, regex: &str, whole_line: bool) -> Mask {
+    /* {{{ */
+    return Mask {
+        glob: globset::Glob::new(glob).unwrap().compile_matcher(),
+        regex: regex::bytes::Regex::new(regex).unwrap(),
+        whole_line,
+    };
+    /* }}} */
+}
+
 #[test]
-fn ut_compare_regular_files_003() {
+fn ut_compare_files_masked_001() {
     /* {{{ */
-    /* The two input files are text files with the same words, but every letter (but the very
-     * first) in the first file is in lower case whereas every letter in the second file is
-     * uppercase. */
-    let first_file = Path::new("../../tests/002/first/Lorem.txt");
-    let second_file = Path::new("../../tests/002/second/Lorem.txt");
-    /* `expected_ret` would be `Ok(FileCmp::SubstanceRegFileContentMismatch)` */
-    let expected_ret_content = FileCmp::SubstanceRegFileContentMismatch;
+    /* A mask only applies to the files its glob matches */
+    let mut conf = crate::config::default_config();
+    conf.masks.push(test_mask("*.h", "[0-9T:-]{19}", false));
+    let first_root = Path::new("../../tests/030/first");
+    let second_root = Path::new("../../tests/030/second");
+
+    for (file, expected_ret_content) in [
+        ("version.h", FileCmp::Equivalent(Equivalence::Masked)),
+        ("logs/build.log", FileCmp::SubstanceRegFileContentMismatch),
+    ] {
+        let ret = compare_files(&conf, first_root, &first_root.join(file), second_root,
+            &second_root.join(file));
+        match ret {
+            Ok(ret_content) => assert_eq!(ret_content.file_cmp, expected_ret_content),
+            Err(_) => panic!(),
+        }
+    }
+    /* }}} */
+}
+
+#[test]
+fn ut_compare_files_masked_002() {
+    /* {{{ */
+    /* A whole-line mask masks the entire line, a span mask only the text its regex matches */
+    let mut conf = crate::config::default_config();
+    let first_root = Path::new("../../tests/030/first");
+    let second_root = Path::new("../../tests/030/second");
+    let file = "logs/build.log";
+
+    for (mask, expected_ret_content) in [
+        (test_mask("logs/*.log", "^Host:", true), FileCmp::Equivalent(Equivalence::Masked)),
+        (test_mask("logs/*.log", "^Host:", false), FileCmp::SubstanceRegFileContentMismatch),
+    ] {
+        conf.masks = vec![mask];
+        let ret = compare_files(&conf, first_root, &first_root.join(file), second_root,
+            &second_root.join(file));
+        match ret {
+            Ok(ret_content) => assert_eq!(ret_content.file_cmp, expected_ret_content),
+            Err(_) => panic!(),
+        }
+    }
+    /* }}} */
+}
+
+#[test]
+fn ut_compare_files_masked_003() {
+    /* {{{ */
+    /* Only the files a mask applies to are compared as text because of it */
+    let mut conf = crate::config::default_config();
+    conf.masks.push(test_mask("*.h", "[0-9T:-]{19}", false));
+    let first_root = Path::new("../../tests/028/first");
+    let second_root = Path::new("../../tests/028/second");
 
-    let ret = compare_regular_files(&first_file, &second_file);
+    let ret = compare_files(&conf, first_root, &first_root.join("crlf.txt"), second_root,
+        &second_root.join("crlf.txt"));
     match ret {
         Ok(ret_content) => {
-            assert_eq!(ret_content, expected_ret_content);
+            assert_eq!(ret_content.file_cmp, FileCmp::SubstanceRegFileContentMismatch);
+        },
+        Err(_) => panic!(),
+    }
+    /* }}} */
+}
+
+#[test]
+fn ut_compare_files_masked_004() {
+    /* {{{ */
+    /* Masks don't ignore line endings, only `--text` does */
+    let mut conf = crate::config::default_config();
+    conf.masks.push(test_mask("*.h", "[0-9T:-]{19}", false));
+    let first_root = Path::new("../../tests/030/first");
+    let second_root = Path::new("../../tests/030/second");
+    let file = "windows/version.h";
+
+    for (text, expected_ret_content) in [
+        (false, FileCmp::SubstanceRegFileContentMismatch),
+        (true, FileCmp::Equivalent(Equivalence::Masked)),
+    ] {
+        conf.text = text;
+        let ret = compare_files(&conf, first_root, &first_root.join(file), second_root,
+            &second_root.join(file));
+        match ret {
+            Ok(ret_content) => assert_eq!(ret_content.file_cmp, expected_ret_content),
+            Err(_) => panic!(),
+        }
+    }
+    /* }}} */
+}
+
+#[test]
+fn ut_compare_files_structured_001() {
+    /* {{{ */
+    let mut conf = crate::config::default_config();
+    conf.structured.push(globset::Glob::new("*").unwrap().compile_matcher());
+    let first_root = Path::new("../../tests/031/first");
+    let second_root = Path::new("../../tests/031/second");
+
+    for (file, expected_ret_content) in [
+        ("app.json", FileCmp::Equivalent(Equivalence::Semantic)),
+        ("Cargo.toml", FileCmp::Equivalent(Equivalence::Semantic)),
+        ("deploy/values.yaml",
+            FileCmp::SubstanceStructuredDataMismatch(String::from("$.image.tag"))),
+    ] {
+        let ret = compare_files(&conf, first_root, &first_root.join(file), second_root,
+            &second_root.join(file));
+        match ret {
+            Ok(ret_content) => assert_eq!(ret_content.file_cmp, expected_ret_content),
+            Err(_) => panic!(),
+        }
+    }
+    /* }}} */
+}
+
+#[test]
+fn ut_compare_files_structured_002() {
+    /* {{{ */
+    /* Files are only compared as data when their paths match one of the globs */
+    let mut conf = crate::config::default_config();
+    conf.structured.push(globset::Glob::new("*.json").unwrap().compile_matcher());
+    let first_root = Path::new("../../tests/031/first");
+    let second_root = Path::new("../../tests/031/second");
+
+    for (file, expected_ret_content) in [
+        ("app.json", FileCmp::Equivalent(Equivalence::Semantic)),
+        ("Cargo.toml", FileCmp::SubstanceRegFileContentMismatch),
+        ("deploy/values.yaml", FileCmp::SubstanceRegFileContentMismatch),
+    ] {
+        let ret = compare_files(&conf, first_root, &first_root.join(file), second_root,
+            &second_root.join(file));
+        match ret {
+            Ok(ret_content) => assert_eq!(ret_content.file_cmp, expected_ret_content),
+            Err(_) => panic!(),
+        }
+    }
+    /* }}} */
+}
+
+#[test]
+fn ut_compare_files_decompress_001() {
+    /* {{{ */
+    let mut conf = crate::config::default_config();
+    conf.decompress = true;
+    let first_root = Path::new("../../tests/032/first");
+    let second_root = Path::new("../../tests/032/second");
+
+    for (file, expected_ret_content) in [
+        ("payload.txt.gz", FileCmp::Equivalent(Equivalence::Decompressed)),
+        ("payload.txt.xz", FileCmp::Equivalent(Equivalence::Decompressed)),
+        ("payload.txt.zst", FileCmp::Equivalent(Equivalence::Decompressed)),
+        ("changed.txt.gz", FileCmp::SubstanceRegFileContentMismatch),
+    ] {
+        let ret = compare_files(&conf, first_root, &first_root.join(file), second_root,
+            &second_root.join(file));
+        match ret {
+            Ok(ret_content) => assert_eq!(ret_content.file_cmp, expected_ret_content),
+            Err(_) => panic!(),
+        }
+    }
+    /* }}} */
+}
+
+#[test]
+fn ut_compare_files_decompress_002() {
+    /* {{{ */
+    /* Without `decompress`, only the compressed bytes are compared */
+    let conf = crate::config::default_config();
+    let first_root = Path::new("../../tests/032/first");
+    let second_root = Path::new("../../tests/032/second");
+
+    for file in ["payload.txt.gz", "payload.txt.xz", "payload.txt.zst"] {
+        let ret = compare_files(&conf, first_root, &first_root.join(file), second_root,
+            &second_root.join(file));
+        match ret {
+            Ok(ret_content) => {
+                assert_eq!(ret_content.file_cmp, FileCmp::SubstanceRegFileContentMismatch);
+            },
+            Err(_) => panic!(),
+        }
+    }
+    /* }}} */
+}
+
+#[test]
+fn ut_compare_files_elf_001() {
+    /* {{{ */
+    let mut conf = crate::config::default_config();
+    conf.elf = true;
+    let first_root = Path::new("../../tests/035/first");
+    let second_root = Path::new("../../tests/035/second");
+    let compare = |conf: &Config, file: &str| {
+        match compare_files(conf, first_root, &first_root.join(file), second_root,
+            &second_root.join(file)) {
+            Ok(ret_content) => return ret_content.file_cmp,
+            Err(_) => panic!("failed to compare {file}"),
+        }
+    };
+
+    /* The builds of `app` only differ in their build ID and debug sections */
+    assert_eq!(compare(&conf, "bin/app"), FileCmp::Equivalent(Equivalence::ElfSections));
+    match compare(&conf, "changed/app") {
+        FileCmp::SubstanceElfSectionMismatch(sections) => {
+            assert!(sections.contains(&String::from(".text")));
         },
-        Err(_) => assert!(false),
+        ret => panic!("unexpected comparison {ret:?}"),
     }
+
+    /* Explicitly selected sections are compared whether or not they are loaded into memory */
+    conf.elf_sections = vec![String::from(".text"), String::from(".note.gnu.build-id")];
+    assert_eq!(compare(&conf, "bin/app"),
+        FileCmp::SubstanceElfSectionMismatch(vec![String::from(".note.gnu.build-id")]));
+    assert_eq!(compare(&conf, "changed/app"),
+        FileCmp::SubstanceElfSectionMismatch(vec![String::from(".text")]));
+    /* }}} */
+}
+
+#[test]
+fn ut_compare_files_images_001() {
+    /* {{{ */
+    let mut conf = crate::config::default_config();
+    conf.images = true;
+    let first_root = Path::new("../../tests/036/first");
+    let second_root = Path::new("../../tests/036/second");
+
+    for (tolerance, file, expected_ret_content) in [
+        (0, "logo.png", FileCmp::Equivalent(Equivalence::Pixels)),
+        (0, "icon.png", FileCmp::SubstanceImagePixelMismatch(2, 1)),
+        (0, "banner.png", FileCmp::SubstanceImageDimensionsMismatch((4, 4), (8, 4))),
+        (2, "icon.png", FileCmp::SubstanceImagePixelMismatch(2, 1)),
+        (3, "icon.png", FileCmp::Equivalent(Equivalence::PixelsWithinTolerance)),
+        (3, "logo.png", FileCmp::Equivalent(Equivalence::Pixels)),
+    ] {
+        conf.image_tolerance = tolerance;
+        let ret = compare_files(&conf, first_root, &first_root.join(file), second_root,
+            &second_root.join(file));
+        match ret {
+            Ok(ret_content) => assert_eq!(ret_content.file_cmp, expected_ret_content),
+            Err(_) => panic!(),
+        }
+    }
+    /* }}} */
+}
+
+#[cfg(test)]
+fn test_external(command: &[&str]) -> crate::config::ExternalCommand {
+    /* {{{ */
+    return crate::config::ExternalCommand {
+        glob: globset::Glob::new("*.txt").unwrap().compile_matcher(),
+        argv: command.iter().map(|arg| arg.to_string()).collect(),
+    };
+    /* }}} */
+}
+
+#[test]
+fn ut_compare_files_external_001() {
+    /* {{{ */
+    let mut conf = crate::config::default_config();
+    conf.external_timeout = Some(std::time::Duration::from_secs(1));
+    let first_root = Path::new("../../tests/037/first");
+    let second_root = Path::new("../../tests/037/second");
+
+    for (command, file, expected_ret_content) in [
+        (vec!["../../tests/037/cmp-ignoring-case.sh"], "same/notes.txt",
+            FileCmp::Equivalent(Equivalence::External)),
+        (vec!["../../tests/037/cmp-ignoring-case.sh"], "changed/notes.txt",
+            FileCmp::SubstanceRegFileContentMismatch),
+        (vec!["sh", "-c", "exit 3"], "same/notes.txt",
+            FileCmp::SubstanceExternalCommandFailure(String::from("`sh` exited with 3"))),
+        (vec!["sh", "-c", "sleep 5", "{first}"], "same/notes.txt",
+            FileCmp::SubstanceExternalCommandFailure(String::from("`sh` timed out after 1s"))),
+    ] {
+        conf.external_commands = vec![test_external(&command)];
+        let ret = compare_files(&conf, first_root, &first_root.join(file), second_root,
+            &second_root.join(file));
+        match ret {
+            Ok(ret_content) => assert_eq!(ret_content.file_cmp, expected_ret_content),
+            Err(_) => panic!(),
+        }
+    }
+    /* }}} */
+}
+
+#[test]
+fn ut_normalize_lexically_001() {
+    /* {{{ */
+    assert_eq!(normalize_lexically(Path::new("a/./b/../c/")), PathBuf::from("a/c"));
+    assert_eq!(normalize_lexically(Path::new("./../d")), PathBuf::from("../d"));
+    assert_eq!(normalize_lexically(Path::new("a/../../e")), PathBuf::from("../e"));
+    assert_eq!(normalize_lexically(Path::new("/../f")), PathBuf::from("/f"));
+    /* }}} */
+}
+
+#[test]
+fn ut_normalize_link_target_001() {
+    /* {{{ */
+    let root = Path::new("../../tests/023/first");
+    /* Relative link paths are made relative to the root */
+    assert_eq!(normalize_link_target(root, &root.join("lib"), Path::new("./lib64")),
+        PathBuf::from("lib64"));
+    assert_eq!(normalize_link_target(root, &root.join("subdir/lib"), Path::new("../lib64/")),
+        PathBuf::from("lib64"));
+    /* Absolute link paths that point into the directory tree are made relative to the root */
+    let abs_target = std::path::absolute(root).unwrap().join("lib64");
+    assert_eq!(normalize_link_target(root, &root.join("abslib"), &abs_target),
+        PathBuf::from("lib64"));
+    /* Absolute link paths that point outside the directory tree stay absolute */
+    assert_eq!(normalize_link_target(root, &root.join("abslib"), Path::new("/usr/./lib64")),
+        PathBuf::from("/usr/lib64"));
     /* }}} */
 }
 
 // TODO: Add tests for:
-// fn compare_soft_links(first_path: &Path, second_path: &Path) -> Result<FileCmp, ()>
+// fn compare_soft_links(config: &Config, first_root: &Path, first_path: &Path, second_root: &Path, second_path: &Path) -> Result<FileCmp, ()>
 
 // TODO: Add tests for:
 // fn compare_files_compare_existences(first_path: &Path, second_path: &Path) -> Result<FileCmp, ()>
@@ -1,12 +1,148 @@
+use globset::GlobMatcher;
+use regex::bytes::Regex;
+use std::time::Duration;
+
+
+/* An enum used to define how `cmp-tree` treats the soft links it comes across in the two directory
+ * trees. */
+#[derive(Debug,PartialEq,Eq,Clone,Copy)]
+pub enum SymlinkMode {
+    /* Soft links are never followed. Two soft links match if their link paths are identical. */
+    Compare,
+    /* Soft links are dereferenced, both when walking the directory trees and when comparing files.
+     * Two soft links match if the files they resolve to match. */
+    Follow,
+    /* Soft links are dereferenced like with `Follow`, but two soft links only match if their link
+     * paths are identical AND the files they resolve to match. */
+    FollowAndCompare,
+}
+
+
+/* An enum used to define how `cmp-tree` reads the contents of regular files when comparing them.
+ * */
+#[derive(Debug,PartialEq,Eq,Clone,Copy)]
+pub enum IoMode {
+    /* Both files are read into buffers of `Config.buffer_size` bytes, one buffer at a time. */
+    Read,
+    /* Both files are memory-mapped and compared in large slices. If a file cannot be mapped, the
+     * comparison falls back to `Read`. */
+    Mmap,
+    /* Small files are opened and read through io_uring, many file pairs at a time. Other files
+     * are compared like with `Read`. Only available on Linux, when built with the `io-uring`
+     * feature. */
+    #[cfg(all(feature = "io-uring", target_os = "linux"))]
+    Uring,
+}
+
+
+/* A regex whose matches are masked out of text files before they are compared, so that content
+ * that is expected to differ (e.g. build timestamps) does not make two files differ. A mask only
+ * applies to the files whose paths, relative to the root of their directory tree, match its glob.
+ * */
+#[derive(Debug,Clone)]
+pub struct Mask {
+    pub glob: GlobMatcher,
+    pub regex: Regex,
+    /* Whether every line the regex matches is masked in its entirety, rather than only the spans
+     * of text the regex matches */
+    pub whole_line: bool,
+}
+
+
+/* An external command that compares regular files that are not byte-for-byte identical, instead of
+ * `cmp-tree` itself. A command only applies to the files whose paths, relative to the root of
+ * their directory tree, match its glob (see `external`). */
+#[derive(Debug,Clone)]
+pub struct ExternalCommand {
+    pub glob: GlobMatcher,
+    /* The program to run followed by its arguments, in which `{first}` and `{second}` stand for the
+     * paths to the two files */
+    pub argv: Vec<String>,
+}
+
+
 /* A struct used to define the configuration `cmp-tree` functions will run under. Many functions
  * within `cmp-tree` will require a Config struct and the values of said struct will affect how
  * they work or run. */
 pub struct Config {
+    /* The number of bytes read from each regular file at a time when comparing their contents */
+    pub buffer_size: usize,
     pub compare_modification_times: bool,
+    /* Whether two files must also have the same permission bits to match */
+    pub compare_modes: bool,
+    /* Whether two regular files with identical contents must also have their holes in the same
+     * places to match */
+    pub compare_sparseness: bool,
+    /* Whether regular files compressed with gzip, xz or zstd that are not byte-for-byte identical
+     * are compared again once decompressed (see `decompress`) */
+    pub decompress: bool,
+    /* Whether regular files are compared in the order they are stored on disk rather than in the
+     * sorted order of their file paths. The output is sorted either way. */
+    pub disk_order: bool,
+    /* Whether ELF files that are not byte-for-byte identical are compared again by a selection of
+     * their sections (see `elf`) */
+    pub elf: bool,
+    /* The names of the sections ELF files are compared by. If it is empty, the sections that are
+     * loaded into memory when the program runs are, bar the ones that only identify the build */
+    pub elf_sections: Vec<String>,
+    /* The external commands regular files are compared with, the first one whose glob matches
+     * applying (see `external`) */
+    pub external_commands: Vec<ExternalCommand>,
+    /* The largest number of external commands that run at the same time, if limited */
+    pub external_jobs: Option<usize>,
+    /* How long an external command may run for before it is killed, if limited */
+    pub external_timeout: Option<Duration>,
+    /* Whether images that are not byte-for-byte identical are compared again by their pixels (see
+     * `images`) */
+    pub images: bool,
+    /* The largest difference allowed between the same channel of the same pixel in two images, in
+     * 8 bit units */
+    pub image_tolerance: u8,
+    /* Whether a UTF-8 byte order mark at the start of text files is ignored (see `text`) */
+    pub ignore_bom: bool,
+    /* Whether all whitespace, trailing whitespace and blank lines in text files are ignored,
+     * respectively (see `text`) */
+    pub ignore_all_space: bool,
+    pub ignore_trailing_space: bool,
+    pub ignore_blank_lines: bool,
+    /* Whether a newline at the very end of text files is ignored (see `text`) */
+    pub ignore_trailing_newline: bool,
+    pub io_mode: IoMode,
+    pub jobs: Option<usize>,
+    /* Regular files at least this many bytes long are split into ranges that are compared
+     * concurrently. `None` means no file is ever split. */
+    pub large_file_threshold: Option<u64>,
+    /* The masks applied to text files before they are compared (see `Mask`) */
+    pub masks: Vec<Mask>,
     pub matches: bool,
+    pub normalize_link_targets: bool,
     pub pretty: bool,
+    /* When set, big regular files are compared by sampling this many blocks (of `buffer_size`
+     * bytes) from them rather than comparing every byte. `sample_seed` decides which blocks are
+     * sampled */
+    pub sample_blocks: Option<u64>,
+    pub sample_seed: u64,
+    /* Whether the comparison stops at the first mismatch */
+    pub quick: bool,
+    /* Whether the files in two archives (e.g. zip archives) that are not byte-for-byte identical
+     * are compared too (see `archive`) */
+    pub recurse_archives: bool,
+    /* Whether regular files with the same size and modification time are deemed a match without
+     * their contents being compared */
+    pub shallow: bool,
+    /* Whether regular files that share all of their extents on disk (e.g. reflink copies) are
+     * declared a match without being read */
+    pub shared_extents: bool,
     pub silent: bool,
     pub single_threaded: bool,
+    /* Regular files whose paths, relative to the root of their directory tree, match any of these
+     * globs are compared as data if they are JSON, YAML or TOML files (see `structured`) */
+    pub structured: Vec<GlobMatcher>,
+    pub symlink_mode: SymlinkMode,
+    /* Whether regular files that both look like text and only differ in their line endings are
+     * deemed equivalent. Ignoring any part of text files (e.g. `ignore_bom`) has the same effect,
+     * whether or not this is set */
+    pub text: bool,
     pub totals: bool,
 }
 
@@ -18,11 +154,42 @@ pub struct Config {
 pub fn default_config() -> Config {
     /* {{{ */
     return Config {
+        buffer_size: 8192,
         compare_modification_times: false,
+        compare_modes: false,
+        compare_sparseness: false,
+        decompress: false,
+        disk_order: false,
+        elf: false,
+        elf_sections: Vec::new(),
+        external_commands: Vec::new(),
+        external_jobs: None,
+        external_timeout: None,
+        images: false,
+        image_tolerance: 0,
+        ignore_bom: false,
+        ignore_all_space: false,
+        ignore_trailing_space: false,
+        ignore_blank_lines: false,
+        ignore_trailing_newline: false,
+        io_mode: IoMode::Read,
+        jobs: None,
+        large_file_threshold: None,
+        masks: Vec::new(),
         matches: false,
+        normalize_link_targets: false,
         pretty: false,
+        sample_blocks: None,
+        sample_seed: 0,
+        quick: false,
+        recurse_archives: false,
+        shallow: false,
+        shared_extents: false,
         silent: false,
         single_threaded: false,
+        structured: Vec::new(),
+        symlink_mode: SymlinkMode::Compare,
+        text: false,
         totals: false,
     };
     /* }}} */
@@ -1,5 +1,5 @@
 use std::cmp::Ordering;
-use std::fs::FileType;
+use std::fs::{FileType,Metadata};
 use std::path::PathBuf;
 
 
@@ -16,18 +16,112 @@ pub enum FileCmp {
     /* For when the two files (understood in the broad sense) mismatch in their type (e.g. one is a
     * directory, one is a regular file). */
     FileTypeTypeMismatch,
+    /* For when soft links are being followed and only the first of the two files is a soft link
+    * that does not resolve to an existing file (i.e. it is dangling). */
+    FileTypeDanglingSoftLinkOnlyFirstFile,
+    /* For when soft links are being followed and only the second of the two files is a soft link
+    * that does not resolve to an existing file (i.e. it is dangling). */
+    FileTypeDanglingSoftLinkOnlySecondFile,
     /* (3) For Substance Comparisons */
     /* For when the two files mismatch in their content (i.e. they are not byte-for-byte
     * identical). */
     SubstanceRegFileContentMismatch,
+    /* For when the two files are structured data files (e.g. JSON) that mismatch in their data.
+    * Holds the key path of the first place the data differs (e.g. `$.server.port`). */
+    SubstanceStructuredDataMismatch(String),
+    /* For when the two files are ELF files that mismatch in the sections being compared. Holds the
+    * names of the sections that differ (e.g. `.text`), in the order they appear in the first
+    * file. */
+    SubstanceElfSectionMismatch(Vec<String>),
+    /* For when the two files are images of different dimensions. Holds the width and height of the
+    * first image, then those of the second. */
+    SubstanceImageDimensionsMismatch((u32, u32), (u32, u32)),
+    /* For when the two files are images that mismatch in their pixels. Holds the coordinates
+    * (x, y) of the first pixel that differs, in row-major order. */
+    SubstanceImagePixelMismatch(u32, u32),
+    /* For when the external command the two files are compared with failed to compare them (e.g.
+    * it timed out or exited with an exit code other than 0 or 1). Holds a description of what
+    * happened to the command. */
+    SubstanceExternalCommandFailure(String),
     /* For when the two soft links mismatch in their link path */
     SubstanceSoftLinkLinkMismatch,
     /* (4) For Metadata Comparisons */
     MetadataModificationTimeMismatch,
+    /* For when the two files have different permission bits (e.g. one is executable and the other
+    * is not). */
+    MetadataModeMismatch,
+    /* For when the two regular files have identical contents, but their holes (see `SEEK_HOLE`)
+    * are not in the same places. */
+    MetadataSparsenessMismatch,
     /* (5) For complete matches */
     /* For when the two files (understood in the broad sense) match don't mismatch in any of the
     * possible ways represented above */
     Match,
+    /* For when the two regular files match because they share all of their extents on disk (e.g.
+    * one is a reflink copy of the other), which means that the given number of bytes did not need
+    * to be read from either file. */
+    MatchSharedExtents(u64),
+    /* For when the two regular files are deemed a match because they have the same size and
+    * modification time, without their contents having been compared. */
+    MatchUnverified,
+    /* For when the two regular files are deemed a match because they have the same size and every
+    * block sampled from them matched. The blocks that were not sampled may still differ. */
+    MatchSampled,
+    /* For when the two regular files are not byte-for-byte identical, but are equivalent in the
+    * given way (e.g. they are the same text with different line endings). */
+    Equivalent(Equivalence),
+}
+
+
+/* The ways in which two regular files that are not byte-for-byte identical can be equivalent */
+#[derive(Debug,PartialEq,Eq,Clone,Copy,PartialOrd,Ord)]
+pub enum Equivalence {
+    /* For when the two files are the same text once line endings (and possibly a byte order mark
+    * and a trailing newline) are normalized. */
+    Text,
+    /* For when the two files are the same text once their whitespace is normalized too, in the
+    * ways the configuration asks for (e.g. ignoring trailing whitespace or blank lines). */
+    Whitespace,
+    /* For when the two files are the same text once the content matched by the masks that apply to
+    * them is masked out too (see `config::Mask`). */
+    Masked,
+    /* For when the two files are structured data files (e.g. JSON, YAML or TOML) that hold the same
+    * data, even though it is ordered or formatted differently. */
+    Semantic,
+    /* For when the two files are compressed files (e.g. `.gz` files) that decompress to identical
+    * contents, even though their compressed representations differ. */
+    Decompressed,
+    /* For when the two files are ELF files that only differ outside of the sections being compared
+    * (e.g. in `.note.gnu.build-id` or in debug sections). */
+    ElfSections,
+    /* For when the two files are images with the same pixels, even though they are encoded
+    * differently (e.g. with different metadata chunks or compression levels). */
+    Pixels,
+    /* For when the two files are images with pixels that differ, but by no more than the tolerance
+    * the configuration allows for. */
+    PixelsWithinTolerance,
+    /* For when the external command the two files are compared with deemed them to match. */
+    External,
+}
+
+
+impl FileCmp {
+    /* {{{ */
+    /// Returns whether a `FileCmp` represents a match, however it was established. Files that are
+    /// equivalent without being identical count as a match.
+    ///
+    /// #### Return:
+    /// * `true` if the two files compared match and `false` if they mismatch in some way.
+    pub fn is_match(&self) -> bool {
+        /* {{{ */
+        match self {
+            FileCmp::Match | FileCmp::MatchSharedExtents(_) | FileCmp::MatchUnverified
+                | FileCmp::MatchSampled | FileCmp::Equivalent(_) => return true,
+            _ => return false,
+        }
+        /* }}} */
+    }
+    /* }}} */
 }
 
 
@@ -76,7 +170,7 @@ impl Ord for PartialFileComparison {
     fn cmp(&self, other: &Self) -> Ordering {
         /* Compare the `file_cmp` member. If that comparison returns a `Less` or `Greater`
          * Ordering, our work is done, otherwise proceed to compare the next member */
-        match (&(self.file_cmp)).cmp(&(other.file_cmp)) {
+        match (self.file_cmp).cmp(&(other.file_cmp)) {
             Ordering::Less => return Ordering::Less,
             Ordering::Greater => return Ordering::Greater,
             Ordering::Equal => {
@@ -120,3 +214,13 @@ pub struct FullFileComparison {
     pub first_path: PathBuf,
     pub second_path: PathBuf,
 }
+
+
+/* A struct representing two corresponding files that are about to be compared, alongside the
+ * metadata of the files themselves (soft links not followed), if they exist. */
+pub struct FilePair {
+    pub first_path: PathBuf,
+    pub first_link_metadata: Option<Metadata>,
+    pub second_path: PathBuf,
+    pub second_link_metadata: Option<Metadata>,
+}
@@ -0,0 +1,181 @@
+use std::fs::File;
+use std::io::{BufReader,ErrorKind,Read};
+use std::path::Path;
+
+
+/* The compression formats `cmp-tree` can decompress files from, to compare their payloads */
+#[derive(Debug,PartialEq,Eq,Clone,Copy)]
+pub enum Compression {
+    Gzip,
+    Xz,
+    Zstd,
+}
+
+
+/* The magic numbers compressed files of each format start with */
+const GZIP_MAGIC: &[u8] = b"\x1F\x8B";
+const XZ_MAGIC: &[u8] = b"\xFD7zXZ\x00";
+const ZSTD_MAGIC: &[u8] = b"\x28\xB5\x2F\xFD";
+
+
+/// Returns the compression format of a file, going by the magic number it starts with rather than
+/// by the extension of its file name.
+///
+/// #### Parameters:
+/// * `file` an open regular file, positioned at its start. The file is read from, so it must be
+///     rewound before it is read again.
+/// #### Return:
+/// * the compression format of the file, or `None` if it is not compressed in a format `cmp-tree`
+///     can decompress (or could not be read).
+pub fn compression_of(file: &mut File) -> Option<Compression> {
+    /* {{{ */
+    let mut magic = [0; 6];
+    let len = match fill(file, &mut magic) {
+        Ok(len) => len,
+        Err(_) => return None,
+    };
+
+    let magic = &magic[..len];
+    if magic.starts_with(GZIP_MAGIC) {
+        return Some(Compression::Gzip);
+    } else if magic.starts_with(XZ_MAGIC) {
+        return Some(Compression::Xz);
+    } else if magic.starts_with(ZSTD_MAGIC) {
+        return Some(Compression::Zstd);
+    }
+    return None;
+    /* }}} */
+}
+
+
+/// Opens a compressed file and returns a reader that decompresses it. Files made of several
+/// compressed streams one after the other (e.g. concatenated `.gz` files) are decompressed in
+/// their entirety, like `gzip -d`, `xz -d` and `zstd -d` do.
+///
+/// #### Parameters:
+/// * `path` a file path that points to a regular file.
+/// #### Return:
+/// * a reader that yields the decompressed contents of the file, or `None` if the file is not
+///     compressed in a format `cmp-tree` can decompress. `Err(())` if the file could not be
+///     opened.
+pub fn decompressor(path: &Path) -> Result<Option<Box<dyn Read>>, ()> {
+    /* {{{ */
+    let mut file = match File::open(path) {
+        Ok(file) => file,
+        Err(_) => return Err(()),
+    };
+    let compression = compression_of(&mut file);
+    /* Reopen the file rather than seeking back, so that the decompressor starts at the magic
+     * number */
+    let file = match File::open(path) {
+        Ok(file) => BufReader::new(file),
+        Err(_) => return Err(()),
+    };
+    match compression {
+        Some(Compression::Gzip) => {
+            return Ok(Some(Box::new(flate2::bufread::MultiGzDecoder::new(file))));
+        },
+        Some(Compression::Xz) => {
+            return Ok(Some(Box::new(xz2::bufread::XzDecoder::new_multi_decoder(file))));
+        },
+        Some(Compression::Zstd) => match zstd::stream::read::Decoder::with_buffer(file) {
+            Ok(decoder) => return Ok(Some(Box::new(decoder))),
+            Err(_) => return Ok(None),
+        },
+        None => return Ok(None),
+    }
+    /* }}} */
+}
+
+
+/// Reads from `reader` until `buf` is full or the reader has nothing more to give. Unlike
+/// `Read::read_exact()`, reaching the end of the reader early is not an error.
+///
+/// #### Parameters:
+/// * `reader` the reader to read from.
+/// * `buf` the buffer to fill.
+/// #### Return:
+/// * the number of bytes read into `buf` on success, which is only less than the length of `buf`
+///     at the end of the reader, and `Err(())` if reading failed.
+fn fill(reader: &mut dyn Read, buf: &mut [u8]) -> Result<usize, ()> {
+    /* {{{ */
+    let mut len = 0;
+    while len < buf.len() {
+        match reader.read(&mut buf[len..]) {
+            Ok(0) => break,
+            Ok(n) => len += n,
+            Err(e) if e.kind() == ErrorKind::Interrupted => (),
+            Err(_) => return Err(()),
+        }
+    }
+    return Ok(len);
+    /* }}} */
+}
+
+
+/// Compares the contents of two readers, one buffer at a time, so that contents of any size can
+/// be compared without being read into memory in their entirety.
+///
+/// #### Parameters:
+/// * `first` the reader of the first contents.
+/// * `second` the reader of the second contents.
+/// * `buffer_size` the number of bytes to compare at a time.
+/// #### Return:
+/// * `Ok(true)` if both readers yield identical contents and `Ok(false)` if they don't. `Err(())`
+///     if reading from either reader failed.
+pub fn compare_streams(first: &mut dyn Read, second: &mut dyn Read, buffer_size: usize) ->
+    Result<bool, ()> {
+    /* {{{ */
+    let mut first_buf = vec![0; buffer_size];
+    let mut second_buf = vec![0; buffer_size];
+
+    loop {
+        let (first_len, second_len) = match (fill(first, &mut first_buf),
+            fill(second, &mut second_buf)) {
+            (Ok(first_len), Ok(second_len)) => (first_len, second_len),
+            _ => return Err(()),
+        };
+        if first_len != second_len || first_buf[..first_len] != second_buf[..second_len] {
+            return Ok(false);
+        }
+        if first_len < buffer_size {
+            return Ok(true);
+        }
+    }
+    /* }}} */
+}
+
+
+/// Compares the decompressed contents of two compressed files, one buffer at a time, so that
+/// files of any size can be compared without being decompressed into memory or onto disk. The two
+/// files do not need to be compressed in the same format.
+///
+/// #### Parameters:
+/// * `first_path` a file path that points to the first regular file.
+/// * `second_path` a file path that points to the second regular file.
+/// * `buffer_size` the number of decompressed bytes to compare at a time.
+/// #### Return:
+/// * `Ok(true)` if both files are compressed and decompress to identical contents, and
+///     `Ok(false)` if they decompress to different contents, either of them is not compressed or
+///     either of them fails to decompress (e.g. because it is corrupt or truncated). `Err(())` if
+///     either file could not be opened.
+pub fn compare_decompressed(first_path: &Path, second_path: &Path, buffer_size: usize) ->
+    Result<bool, ()> {
+    /* {{{ */
+    let mut first_reader = match decompressor(first_path) {
+        Ok(Some(reader)) => reader,
+        Ok(None) => return Ok(false),
+        Err(_) => return Err(()),
+    };
+    let mut second_reader = match decompressor(second_path) {
+        Ok(Some(reader)) => reader,
+        Ok(None) => return Ok(false),
+        Err(_) => return Err(()),
+    };
+    match compare_streams(&mut first_reader, &mut second_reader, buffer_size) {
+        Ok(identical) => return Ok(identical),
+        /* A file that fails to decompress is deemed to differ from any other file */
+        Err(_) => return Ok(false),
+    }
+    /* }}} */
+}
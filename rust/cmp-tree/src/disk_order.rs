@@ -0,0 +1,64 @@
+use std::fs::Metadata;
+use std::os::unix::fs::MetadataExt; // For getting the inode number of files
+use std::path::Path;
+
+
+/// Returns the physical location (in bytes from the start of the device) of the first extent of
+/// the regular file pointed to by `path`, as reported by the `FS_IOC_FIEMAP` ioctl.
+///
+/// #### Parameters:
+/// * `path` a file path that points to a regular file.
+/// #### Return:
+/// * the physical location of the start of the file, or `None` if the file system does not support
+///     `FS_IOC_FIEMAP`, the file has no extents (e.g. because it is empty or inlined in its inode)
+///     or the file could not be opened.
+#[cfg(target_os = "linux")]
+pub fn physical_location(path: &Path) -> Option<u64> {
+    /* {{{ */
+    let file = match std::fs::File::open(path) {
+        Ok(file) => file,
+        Err(_) => return None,
+    };
+    match crate::fiemap::file_extents(&file, 1, false) {
+        Some(extents) => return extents.first().map(|extent| extent.fe_physical),
+        None => return None,
+    }
+    /* }}} */
+}
+
+
+/// On systems other than Linux, the physical location of files is never known.
+#[cfg(not(target_os = "linux"))]
+pub fn physical_location(_path: &Path) -> Option<u64> {
+    /* {{{ */
+    return None;
+    /* }}} */
+}
+
+
+/// Returns a key that roughly orders files by where they are stored on disk, so that reading
+/// files in ascending order of their keys keeps the disk from seeking back and forth. For regular
+/// files, the physical location of their first extent is used when it is available. Otherwise,
+/// the inode number is used as a proxy, since file systems tend to allocate the data of files
+/// close to their inode.
+///
+/// #### Parameters:
+/// * `path` a file path that points to the file.
+/// * `metadata` the metadata of the file (soft links not followed).
+/// #### Return:
+/// * the key of the file. Keys from the physical location and keys from inode numbers are not
+///     comparable to one another, so all physical locations are ordered after all inode numbers.
+pub fn read_order_key(path: &Path, metadata: &Metadata) -> u64 {
+    /* {{{ */
+    /* Only regular files have contents worth ordering the reads of */
+    if !metadata.is_file() {
+        return 0;
+    }
+    match physical_location(path) {
+        /* Halve the physical location to make room for the inode numbers below it. Neighbouring
+         * files stay neighbours */
+        Some(location) => return (1 << 63) | (location >> 1),
+        None => return metadata.ino() & ((1 << 63) - 1),
+    }
+    /* }}} */
+}
@@ -0,0 +1,158 @@
+use memmap2::Mmap; // For memory-mapping files
+use object::{Object,ObjectSection,SectionFlags};
+use object::elf::SHF_ALLOC;
+use object::read::File as ObjectFile;
+use std::fs::File;
+use std::path::Path;
+
+
+/* The magic number ELF files start with */
+const ELF_MAGIC: &[u8] = b"\x7FELF";
+
+/* The sections that are loaded into memory but have no bearing on how a program runs. They differ
+ * between otherwise identical builds, so they are left out of the default selection of sections */
+const NON_FUNCTIONAL_SECTIONS: &[&str] = &[".note.gnu.build-id", ".note.go.buildid"];
+
+/* The name under which differences in the ELF file headers (e.g. the entry point) are reported */
+pub const HEADER: &str = "<header>";
+
+
+/// Returns whether a section is one that is compared when no sections are selected explicitly.
+/// These are the sections that are loaded into memory when the program runs, bar the ones that
+/// only identify the build. Debug sections, symbol tables and comments are not loaded, and so are
+/// not compared.
+///
+/// #### Parameters:
+/// * `name` the name of the section.
+/// * `flags` the flags of the section.
+/// #### Return:
+/// * `true` if the section is compared by default and `false` otherwise.
+fn is_functional(name: &str, flags: SectionFlags) -> bool {
+    /* {{{ */
+    let is_allocated = match flags {
+        SectionFlags::Elf { sh_flags } => sh_flags & u64::from(SHF_ALLOC) != 0,
+        _ => false,
+    };
+    return is_allocated && !NON_FUNCTIONAL_SECTIONS.contains(&name);
+    /* }}} */
+}
+
+
+/// Returns the names of the sections to compare in an ELF file, in the order they appear in it.
+///
+/// #### Parameters:
+/// * `file` the parsed ELF file.
+/// * `sections` the names of the sections to compare. If it is empty, the sections that are
+///     compared by default (see `is_functional()`) are returned.
+/// #### Return:
+/// * the names of the sections in `file` that are to be compared.
+fn selected_sections(file: &ObjectFile, sections: &[String]) -> Vec<String> {
+    /* {{{ */
+    let mut ret: Vec<String> = Vec::new();
+    for section in file.sections() {
+        let name = match section.name() {
+            Ok(name) => name,
+            Err(_) => continue,
+        };
+        let selected = match sections.is_empty() {
+            true => is_functional(name, section.flags()),
+            false => sections.iter().any(|s| s == name),
+        };
+        if selected && !ret.iter().any(|s| s == name) {
+            ret.push(name.to_string());
+        }
+    }
+    return ret;
+    /* }}} */
+}
+
+
+/// Compares the same section in two ELF files by its address, its size and its contents. Sections
+/// that take up no space in the file (e.g. `.bss`) are compared by their address and size alone.
+///
+/// #### Parameters:
+/// * `first` the first parsed ELF file.
+/// * `second` the second parsed ELF file.
+/// * `name` the name of the section to compare.
+/// #### Return:
+/// * `true` if both files have the section and it is the same in both, and `false` otherwise.
+fn section_matches(first: &ObjectFile, second: &ObjectFile, name: &str) -> bool {
+    /* {{{ */
+    let (first_section, second_section) = match (first.section_by_name(name),
+        second.section_by_name(name)) {
+        (Some(first_section), Some(second_section)) => (first_section, second_section),
+        _ => return false,
+    };
+    if first_section.address() != second_section.address()
+        || first_section.size() != second_section.size() {
+        return false;
+    }
+    match (first_section.data(), second_section.data()) {
+        (Ok(first_data), Ok(second_data)) => return first_data == second_data,
+        _ => return false,
+    }
+    /* }}} */
+}
+
+
+/// Compares two ELF files by a selection of their sections, rather than by every byte, so that
+/// builds which only differ in sections that have no bearing on how the program runs (e.g.
+/// `.note.gnu.build-id` or debug sections) are deemed equivalent. The two files are
+/// memory-mapped, so they can be of any size.
+///
+/// #### Parameters:
+/// * `first_path` a file path that points to the first regular file.
+/// * `second_path` a file path that points to the second regular file.
+/// * `sections` the names of the sections to compare. If it is empty, the sections that are loaded
+///     into memory when the program runs are compared, bar the ones that only identify the build.
+/// #### Return:
+/// * `Ok(Some(differing))` if both files are ELF files, where `differing` holds the names of the
+///     selected sections that differ between them (or are missing from either), in the order they
+///     appear in the first file, and is empty if none do. Differences in the file headers (e.g. in
+///     the architecture or the entry point) are reported as `HEADER`. `Ok(None)` if either file is
+///     not an ELF file or fails to parse, and `Err(())` if either file could not be read.
+pub fn compare_elf_files(first_path: &Path, second_path: &Path, sections: &[String]) ->
+    Result<Option<Vec<String>>, ()> {
+    /* {{{ */
+    let (first_file, second_file) = match (File::open(first_path), File::open(second_path)) {
+        (Ok(first_file), Ok(second_file)) => (first_file, second_file),
+        _ => return Err(()),
+    };
+    /* SAFETY: the files are only read from, and are assumed not to be modified while they are
+     * compared, as is the case for every other comparison `cmp-tree` makes */
+    let (first_map, second_map) = match (unsafe { Mmap::map(&first_file) },
+        unsafe { Mmap::map(&second_file) }) {
+        (Ok(first_map), Ok(second_map)) => (first_map, second_map),
+        _ => return Err(()),
+    };
+    if !first_map.starts_with(ELF_MAGIC) || !second_map.starts_with(ELF_MAGIC) {
+        return Ok(None);
+    }
+    let (first, second) = match (ObjectFile::parse(&*first_map),
+        ObjectFile::parse(&*second_map)) {
+        (Ok(first), Ok(second)) => (first, second),
+        _ => return Ok(None),
+    };
+
+    let mut differing: Vec<String> = Vec::new();
+    if first.architecture() != second.architecture() || first.kind() != second.kind()
+        || first.is_64() != second.is_64() || first.is_little_endian() != second.is_little_endian()
+        || first.entry() != second.entry() {
+        differing.push(HEADER.to_string());
+    }
+    /* A section only one of the files has differs too, and is reported after the ones the first
+     * file has */
+    let mut names = selected_sections(&first, sections);
+    for name in selected_sections(&second, sections) {
+        if !names.contains(&name) {
+            names.push(name);
+        }
+    }
+    for name in names {
+        if !section_matches(&first, &second, &name) {
+            differing.push(name);
+        }
+    }
+    return Ok(Some(differing));
+    /* }}} */
+}
@@ -0,0 +1,212 @@
+use std::ffi::OsString;
+use std::os::unix::process::CommandExt; // For running external commands in their own process group
+use std::path::Path;
+use std::process::{Command,Stdio};
+use std::sync::{Condvar,Mutex};
+use wait_timeout::ChildExt; // For waiting on external commands with a timeout
+
+use crate::config::{Config,ExternalCommand};
+
+
+/* The placeholders that stand for the paths to the two files in the arguments of an external
+ * command */
+const FIRST_PLACEHOLDER: &str = "{first}";
+const SECOND_PLACEHOLDER: &str = "{second}";
+
+/* The number of external commands running at the time, shared between all the threads comparing
+ * files, and a condition variable to wait on for one of them to finish */
+static RUNNING: Mutex<usize> = Mutex::new(0);
+static FINISHED: Condvar = Condvar::new();
+
+
+/* A slot for one running external command. Holding one counts towards the limit on the number of
+ * external commands running at the same time, and dropping it frees the slot up again. */
+struct Slot;
+
+impl Slot {
+    /* {{{ */
+    /// Waits until fewer than `limit` external commands are running, and takes up a slot.
+    ///
+    /// #### Parameters:
+    /// * `limit` the largest number of external commands that may run at the same time, or `None`
+    ///     if it is not limited.
+    /// #### Return:
+    /// * a `Slot` that is held for as long as the external command runs.
+    fn acquire(limit: Option<usize>) -> Slot {
+        /* {{{ */
+        let mut running = RUNNING.lock().unwrap();
+        if let Some(limit) = limit {
+            while *running >= limit {
+                running = FINISHED.wait(running).unwrap();
+            }
+        }
+        *running += 1;
+        return Slot;
+        /* }}} */
+    }
+    /* }}} */
+}
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        /* {{{ */
+        *RUNNING.lock().unwrap() -= 1;
+        FINISHED.notify_one();
+        /* }}} */
+    }
+}
+
+
+/// Returns the external command two regular files are to be compared with, if any.
+///
+/// #### Parameters:
+/// * `config` a `Config` representing a configuration for executing `cmp-tree`, usually modified
+///     through command line arguments to the program.
+/// * `rel_path` the path of the files relative to the roots of their directory trees.
+/// #### Return:
+/// * the first external command whose glob matches `rel_path`, or `None` if none do.
+pub fn command_for<'a>(config: &'a Config, rel_path: &Path) -> Option<&'a ExternalCommand> {
+    /* {{{ */
+    return config.external_commands.iter().find(|command| command.glob.is_match(rel_path));
+    /* }}} */
+}
+
+
+/// Replaces the placeholders in an argument of an external command with the paths to the two
+/// files. Paths need not be valid UTF-8, so the argument is built up as an `OsString`.
+///
+/// #### Parameters:
+/// * `arg` the argument, as configured.
+/// * `first_path` the path that replaces `{first}`.
+/// * `second_path` the path that replaces `{second}`.
+/// #### Return:
+/// * the argument to pass to the external command.
+fn expand_placeholders(arg: &str, first_path: &Path, second_path: &Path) -> OsString {
+    /* {{{ */
+    let mut ret = OsString::new();
+    let mut rest = arg;
+    loop {
+        let first_pos = rest.find(FIRST_PLACEHOLDER);
+        let second_pos = rest.find(SECOND_PLACEHOLDER);
+        let (pos, placeholder, path) = match (first_pos, second_pos) {
+            (Some(f), Some(s)) if s < f => (s, SECOND_PLACEHOLDER, second_path),
+            (Some(f), _) => (f, FIRST_PLACEHOLDER, first_path),
+            (None, Some(s)) => (s, SECOND_PLACEHOLDER, second_path),
+            (None, None) => break,
+        };
+        ret.push(&rest[..pos]);
+        ret.push(path.as_os_str());
+        rest = &rest[pos + placeholder.len()..];
+    }
+    ret.push(rest);
+    return ret;
+    /* }}} */
+}
+
+
+/// Compares two regular files by running an external command on them and interpreting its exit
+/// code the way `cmp` and `diff` do: 0 means the files match, 1 means they differ and anything else
+/// means the command failed to compare them. The command is run directly, without a shell, with
+/// its standard input and output connected to `/dev/null`, so that it does not get in the way of
+/// the output of `cmp-tree`. Its standard error is left as is. If none of its arguments contain
+/// `{first}` or `{second}`, the paths to the two files are appended to them. The command runs in
+/// a process group of its own, so that a command that times out is killed along with every
+/// process it started (e.g. the commands a shell script runs).
+///
+/// #### Parameters:
+/// * `config` a `Config` representing a configuration for executing `cmp-tree`, usually modified
+///     through command line arguments to the program. Holds the timeout of the command and the
+///     limit on the number of external commands running at the same time.
+/// * `command` the external command to run.
+/// * `first_path` a file path that points to the first regular file.
+/// * `second_path` a file path that points to the second regular file.
+/// #### Return:
+/// * `Ok(true)` if the command deemed the files to match and `Ok(false)` if it deemed them to
+///     differ. `Err(reason)` if the command could not be run, timed out, was killed or exited with
+///     any other exit code, where `reason` describes what happened to it.
+pub fn compare_externally(config: &Config, command: &ExternalCommand, first_path: &Path,
+    second_path: &Path) -> Result<bool, String> {
+    /* {{{ */
+    let program = &command.argv[0];
+    let has_placeholders = command.argv[1..].iter()
+        .any(|arg| arg.contains(FIRST_PLACEHOLDER) || arg.contains(SECOND_PLACEHOLDER));
+    let mut args: Vec<OsString> = command.argv[1..].iter()
+        .map(|arg| expand_placeholders(arg, first_path, second_path)).collect();
+    if !has_placeholders {
+        args.push(first_path.as_os_str().to_os_string());
+        args.push(second_path.as_os_str().to_os_string());
+    }
+
+    let _slot = Slot::acquire(config.external_jobs);
+    let mut child = match Command::new(program).args(&args).stdin(Stdio::null())
+        .stdout(Stdio::null()).process_group(0).spawn() {
+        Ok(child) => child,
+        Err(e) => return Err(format!("`{program}` could not be run: {e}")),
+    };
+    let status = match config.external_timeout {
+        Some(timeout) => match child.wait_timeout(timeout) {
+            Ok(Some(status)) => status,
+            Ok(None) => {
+                /* The command may have finished in the meantime, so failing to kill it is fine.
+                 * Its process group has the same ID as the command itself, and lives on until the
+                 * command is waited on, so the ID can't have been reused by then */
+                unsafe {
+                    libc::killpg(child.id() as libc::pid_t, libc::SIGKILL);
+                }
+                let _ = child.wait();
+                return Err(format!("`{program}` timed out after {timeout:?}"));
+            },
+            Err(e) => return Err(format!("`{program}` could not be waited on: {e}")),
+        },
+        None => match child.wait() {
+            Ok(status) => status,
+            Err(e) => return Err(format!("`{program}` could not be waited on: {e}")),
+        },
+    };
+    match status.code() {
+        Some(0) => return Ok(true),
+        Some(1) => return Ok(false),
+        Some(code) => return Err(format!("`{program}` exited with {code}")),
+        None => return Err(format!("`{program}` was killed by a signal")),
+    }
+    /* }}} */
+}
+
+
+/* Unit tests */
+#[test]
+fn ut_expand_placeholders_001() {
+    /* {{{ */
+    let first_path = Path::new("first/a.pdf");
+    let second_path = Path::new("second/a.pdf");
+
+    assert_eq!(expand_placeholders("--old={first}", first_path, second_path),
+        OsString::from("--old=first/a.pdf"));
+    assert_eq!(expand_placeholders("{second}:{first}", first_path, second_path),
+        OsString::from("second/a.pdf:first/a.pdf"));
+    assert_eq!(expand_placeholders("{first}{first}", first_path, second_path),
+        OsString::from("first/a.pdffirst/a.pdf"));
+    assert_eq!(expand_placeholders("-q", first_path, second_path), OsString::from("-q"));
+    /* }}} */
+}
+
+#[test]
+fn ut_compare_externally_001() {
+    /* {{{ */
+    /* A command that times out is killed along with the processes it started, so the background
+     * job below never gets to create its marker file */
+    let marker = std::env::temp_dir().join(format!("cmp-tree-{}-timeout", std::process::id()));
+    let mut conf = crate::config::default_config();
+    conf.external_timeout = Some(std::time::Duration::from_millis(200));
+    let command = ExternalCommand {
+        glob: globset::Glob::new("*").unwrap().compile_matcher(),
+        argv: vec![String::from("sh"), String::from("-c"),
+            format!("(sleep 1; touch '{}') & wait", marker.display())],
+    };
+
+    let ret = compare_externally(&conf, &command, Path::new("a"), Path::new("b"));
+    assert!(ret.is_err());
+    std::thread::sleep(std::time::Duration::from_millis(1500));
+    assert!(!marker.exists());
+    /* }}} */
+}
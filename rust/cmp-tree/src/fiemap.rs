@@ -0,0 +1,237 @@
+use std::fs::File;
+use std::os::fd::AsRawFd;
+use std::os::unix::fs::MetadataExt;
+use std::path::Path;
+
+
+/* The header of the argument to the `FS_IOC_FIEMAP` ioctl, as defined in `linux/fiemap.h` */
+#[repr(C)]
+struct Fiemap {
+    fm_start: u64,
+    fm_length: u64,
+    fm_flags: u32,
+    fm_mapped_extents: u32,
+    fm_extent_count: u32,
+    fm_reserved: u32,
+}
+
+
+/* One extent returned by the `FS_IOC_FIEMAP` ioctl, as defined in `linux/fiemap.h` */
+#[derive(Clone,Copy,Debug,PartialEq,Eq)]
+#[repr(C)]
+pub struct FiemapExtent {
+    pub fe_logical: u64,
+    pub fe_physical: u64,
+    pub fe_length: u64,
+    fe_reserved64: [u64; 2],
+    pub fe_flags: u32,
+    fe_reserved: [u32; 3],
+}
+
+
+/* The argument to the `FS_IOC_FIEMAP` ioctl, with room for `EXTENTS_PER_CALL` extents */
+#[repr(C)]
+struct FiemapWithExtents {
+    header: Fiemap,
+    extents: [FiemapExtent; EXTENTS_PER_CALL],
+}
+
+
+/* `_IOWR('f', 11, struct fiemap)` */
+const FS_IOC_FIEMAP: libc::c_ulong = 0xC020660B;
+
+/* Flush dirty data to disk before mapping it, so that delayed allocations have a location */
+const FIEMAP_FLAG_SYNC: u32 = 0x1;
+
+/* The flags an extent can have, as defined in `linux/fiemap.h` */
+pub const FIEMAP_EXTENT_LAST: u32 = 0x1;
+pub const FIEMAP_EXTENT_UNKNOWN: u32 = 0x2;
+pub const FIEMAP_EXTENT_DELALLOC: u32 = 0x4;
+pub const FIEMAP_EXTENT_DATA_INLINE: u32 = 0x200;
+pub const FIEMAP_EXTENT_DATA_TAIL: u32 = 0x400;
+
+/* The number of extents asked for with every call to the `FS_IOC_FIEMAP` ioctl */
+const EXTENTS_PER_CALL: usize = 32;
+
+/* Files with more extents than this are too fragmented for checking whether their extents are
+ * shared to be any cheaper than reading them */
+const MAX_SHARED_EXTENTS: usize = 4096;
+
+
+/// Returns the extents of `file` (i.e. where the contents of the file are stored on disk) as
+/// reported by the `FS_IOC_FIEMAP` ioctl, in ascending order of their logical offsets.
+///
+/// #### Parameters:
+/// * `file` an open regular file.
+/// * `max_extents` the maximum number of extents to return. Only the first `max_extents` extents
+///     of the file are returned if it has more than that.
+/// * `sync` whether to write the file's dirty data to disk first. Otherwise, the data that has not
+///     been written yet is reported as extents with the `FIEMAP_EXTENT_DELALLOC` flag.
+/// #### Return:
+/// * the extents of `file` on success and `None` if the file system does not support
+///     `FS_IOC_FIEMAP`. Holes are not extents, so a file made entirely of holes (e.g. an empty
+///     file) has no extents.
+pub fn file_extents(file: &File, max_extents: usize, sync: bool) -> Option<Vec<FiemapExtent>> {
+    /* {{{ */
+    let empty_extent = FiemapExtent {
+        fe_logical: 0,
+        fe_physical: 0,
+        fe_length: 0,
+        fe_reserved64: [0; 2],
+        fe_flags: 0,
+        fe_reserved: [0; 3],
+    };
+    let mut ret: Vec<FiemapExtent> = Vec::new();
+    let mut start: u64 = 0;
+
+    while ret.len() < max_extents {
+        let mut arg = FiemapWithExtents {
+            header: Fiemap {
+                fm_start: start,
+                fm_length: u64::MAX - start,
+                fm_flags: if sync { FIEMAP_FLAG_SYNC } else { 0 },
+                fm_mapped_extents: 0,
+                fm_extent_count: std::cmp::min(EXTENTS_PER_CALL, max_extents - ret.len()) as u32,
+                fm_reserved: 0,
+            },
+            extents: [empty_extent; EXTENTS_PER_CALL],
+        };
+
+        /* The kernel writes at most `fm_extent_count` extents after the header, which is never
+         * more than `arg` has room for */
+        let res = unsafe {
+            libc::ioctl(file.as_raw_fd(), FS_IOC_FIEMAP, &mut arg as *mut FiemapWithExtents)
+        };
+        if res < 0 {
+            return None;
+        }
+
+        let mapped = &arg.extents[..arg.header.fm_mapped_extents as usize];
+        ret.extend_from_slice(mapped);
+        match mapped.last() {
+            Some(last) if last.fe_flags & FIEMAP_EXTENT_LAST == 0 => {
+                start = last.fe_logical + last.fe_length;
+            },
+            /* Either the last extent of the file was returned or there are no more extents */
+            _ => break,
+        }
+    }
+
+    ret.truncate(max_extents);
+    return Some(ret);
+    /* }}} */
+}
+
+
+/// Checks whether two regular files of the same size share all of their extents, i.e. whether
+/// every part of the first file is stored at the very same place on the very same device as the
+/// same part of the second file. This is the case when one file is a reflink copy of the other (as
+/// made by `cp --reflink` on Btrfs or XFS), or when both are hard links to the same file. Files
+/// that share all of their extents must be byte-for-byte identical, without either of them being
+/// read.
+///
+/// #### Parameters:
+/// * `first_path` a file path that points to the first regular file.
+/// * `second_path` a file path that points to the second regular file, which must be the same
+///     size as the first.
+/// #### Return:
+/// * `true` if the two files are known to share all of their extents and `false` if they don't,
+///     or if it could not be determined (e.g. because the file system does not support
+///     `FS_IOC_FIEMAP` or some of the data is stored inline with the metadata of the file).
+pub fn all_extents_shared(first_path: &Path, second_path: &Path) -> bool {
+    /* {{{ */
+    /* The device a file is on is looked up through the same open file its extents are, so that
+     * both always concern the same file */
+    let extents_of = |path: &Path| match File::open(path) {
+        Ok(file) => match (file.metadata(), file_extents(&file, MAX_SHARED_EXTENTS + 1, true)) {
+            (Ok(metadata), Some(extents)) => Some((metadata.dev(), extents)),
+            _ => None,
+        },
+        Err(_) => None,
+    };
+    let (first_dev, first_extents) = match extents_of(first_path) {
+        Some(dev_and_extents) => dev_and_extents,
+        None => return false,
+    };
+    /* Files made entirely of holes, and files too fragmented to be worth checking, are read */
+    if first_extents.is_empty() || first_extents.len() > MAX_SHARED_EXTENTS {
+        return false;
+    }
+    let (second_dev, second_extents) = match extents_of(second_path) {
+        Some(dev_and_extents) => dev_and_extents,
+        None => return false,
+    };
+
+    return extents_shared((first_dev, &first_extents), (second_dev, &second_extents));
+    /* }}} */
+}
+
+
+/// Intended as a helper function for `all_extents_shared()`. Checks whether two files share all of
+/// their extents, given the device each file is on and the extents of each file.
+///
+/// Physical locations are only meaningful within the device they are on, so files on different
+/// devices never share extents, even if their extents happen to be at the same offsets (e.g.
+/// because one device is a block-level copy of the other). Files on different subvolumes of the
+/// same Btrfs file system are on different devices as far as `st_dev` is concerned, so they are
+/// read instead.
+///
+/// #### Parameters:
+/// * `first` the device (`st_dev`) the first file is on and the extents of the first file.
+/// * `second` the device (`st_dev`) the second file is on and the extents of the second file.
+/// #### Return:
+/// * `true` if the two files are known to share all of their extents and `false` otherwise.
+fn extents_shared(first: (u64, &[FiemapExtent]), second: (u64, &[FiemapExtent])) -> bool {
+    /* {{{ */
+    /* Extents with any of these flags don't have a meaningful physical location (yet) */
+    const UNRELIABLE_FLAGS: u32 = FIEMAP_EXTENT_UNKNOWN | FIEMAP_EXTENT_DELALLOC
+        | FIEMAP_EXTENT_DATA_INLINE | FIEMAP_EXTENT_DATA_TAIL;
+
+    let (first_dev, first_extents) = first;
+    let (second_dev, second_extents) = second;
+    if first_dev != second_dev || first_extents.len() != second_extents.len() {
+        return false;
+    }
+
+    for (first, second) in first_extents.iter().zip(second_extents) {
+        if first.fe_flags & UNRELIABLE_FLAGS != 0 || second.fe_flags & UNRELIABLE_FLAGS != 0 {
+            return false;
+        }
+        if first.fe_logical != second.fe_logical || first.fe_physical != second.fe_physical
+            || first.fe_length != second.fe_length {
+            return false;
+        }
+    }
+
+    return true;
+    /* }}} */
+}
+
+
+/* Unit tests */
+#[test]
+fn ut_extents_shared_001() {
+    /* {{{ */
+    let extent = |logical: u64, physical: u64, flags: u32| FiemapExtent {
+        fe_logical: logical,
+        fe_physical: physical,
+        fe_length: 4096,
+        fe_reserved64: [0; 2],
+        fe_flags: flags,
+        fe_reserved: [0; 3],
+    };
+    let extents = [extent(0, 1 << 20, 0), extent(4096, 1 << 30, FIEMAP_EXTENT_LAST)];
+
+    /* The same extents on the same device are shared */
+    assert!(extents_shared((1, &extents), (1, &extents)));
+    /* Extents at the same offsets on different devices are not */
+    assert!(!extents_shared((1, &extents), (2, &extents)));
+    /* Neither are extents at different offsets, or some of the extents */
+    let moved = [extent(0, 1 << 20, 0), extent(4096, 2 << 30, FIEMAP_EXTENT_LAST)];
+    assert!(!extents_shared((1, &extents), (1, &moved)));
+    assert!(!extents_shared((1, &extents), (1, &extents[..1])));
+    /* Nor are extents that don't have a location yet */
+    let delalloc = [extent(0, 0, FIEMAP_EXTENT_DELALLOC | FIEMAP_EXTENT_LAST)];
+    assert!(!extents_shared((1, &delalloc), (1, &delalloc)));
+    /* }}} */
+}
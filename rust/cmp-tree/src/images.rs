@@ -0,0 +1,103 @@
+use image::{DynamicImage,ImageFormat,ImageReader};
+use std::path::Path;
+
+
+/* The image formats `cmp-tree` can decode images from, to compare them by their pixels */
+const IMAGE_FORMATS: &[ImageFormat] = &[ImageFormat::Bmp, ImageFormat::Gif, ImageFormat::Jpeg,
+    ImageFormat::Png, ImageFormat::WebP];
+
+
+/* How two images that both decoded compare */
+#[derive(Debug,PartialEq,Eq)]
+pub enum ImageCmp {
+    /* For when every channel of every pixel is the same in both images */
+    SamePixels,
+    /* For when no channel of any pixel differs between the images by more than the tolerance */
+    WithinTolerance,
+    /* For when the images have different dimensions. Holds the width and height of the first
+     * image, then those of the second. */
+    DimensionsMismatch((u32, u32), (u32, u32)),
+    /* For when a channel of a pixel differs between the images by more than the tolerance. Holds
+     * the coordinates (x, y) of the first such pixel, in row-major order. */
+    PixelMismatch(u32, u32),
+}
+
+
+/// Opens a file and decodes the image in it, going by the magic number it starts with rather than
+/// by the extension of its file name.
+///
+/// #### Parameters:
+/// * `path` a file path that points to a regular file.
+/// #### Return:
+/// * `Ok(Some(image))` if the file is an image in a format `cmp-tree` can decode, `Ok(None)` if it
+///     is not or fails to decode (e.g. because it is corrupt or truncated), and `Err(())` if the
+///     file could not be opened.
+fn decode(path: &Path) -> Result<Option<DynamicImage>, ()> {
+    /* {{{ */
+    let reader = match ImageReader::open(path) {
+        Ok(reader) => reader,
+        Err(_) => return Err(()),
+    };
+    let reader = match reader.with_guessed_format() {
+        Ok(reader) => reader,
+        Err(_) => return Err(()),
+    };
+    match reader.format() {
+        Some(format) if IMAGE_FORMATS.contains(&format) => (),
+        _ => return Ok(None),
+    }
+    match reader.decode() {
+        Ok(image) => return Ok(Some(image)),
+        Err(_) => return Ok(None),
+    }
+    /* }}} */
+}
+
+
+/// Compares two images by their dimensions and their pixels, so that images that were encoded
+/// differently (e.g. with different metadata chunks, compression levels or even formats) are
+/// compared by what they look like. Pixels are compared as 16 bits per channel RGBA, so images of
+/// different colour types and bit depths can be compared.
+///
+/// #### Parameters:
+/// * `first_path` a file path that points to the first regular file.
+/// * `second_path` a file path that points to the second regular file.
+/// * `tolerance` the largest difference, in 8 bit units (0 to 255), allowed between the same
+///     channel of the same pixel in the two images.
+/// #### Return:
+/// * `Ok(Some(image_cmp))` if both files are images that decode, where `image_cmp` is how they
+///     compare. `Ok(None)` if either file is not an image in a format `cmp-tree` can decode or
+///     fails to decode, and `Err(())` if either file could not be opened.
+pub fn compare_images(first_path: &Path, second_path: &Path, tolerance: u8) ->
+    Result<Option<ImageCmp>, ()> {
+    /* {{{ */
+    let (first, second) = match (decode(first_path), decode(second_path)) {
+        (Ok(Some(first)), Ok(Some(second))) => (first, second),
+        (Err(_), _) | (_, Err(_)) => return Err(()),
+        _ => return Ok(None),
+    };
+    let first_dimensions = (first.width(), first.height());
+    let second_dimensions = (second.width(), second.height());
+    if first_dimensions != second_dimensions {
+        return Ok(Some(ImageCmp::DimensionsMismatch(first_dimensions, second_dimensions)));
+    }
+
+    /* An 8 bit channel is widened to 16 bits by multiplying it by 257 (e.g. 0xFF to 0xFFFF), and
+     * so is the tolerance */
+    let tolerance = u16::from(tolerance) * 257;
+    let first = first.to_rgba16();
+    let second = second.to_rgba16();
+    let mut ret = ImageCmp::SamePixels;
+    for ((x, y, first_pixel), second_pixel) in first.enumerate_pixels().zip(second.pixels()) {
+        for (first_channel, second_channel) in first_pixel.0.iter().zip(second_pixel.0.iter()) {
+            let difference = first_channel.abs_diff(*second_channel);
+            if difference > tolerance {
+                return Ok(Some(ImageCmp::PixelMismatch(x, y)));
+            } else if difference > 0 {
+                ret = ImageCmp::WithinTolerance;
+            }
+        }
+    }
+    return Ok(Some(ret));
+    /* }}} */
+}
@@ -1,5 +1,11 @@
+use std::cmp::Reverse;
+use std::collections::{BTreeMap,BinaryHeap,HashMap,VecDeque};
 use std::path::{Path, PathBuf};
-use std::thread::{available_parallelism,scope,ScopedJoinHandle};
+use std::sync::{Condvar,Mutex,MutexGuard};
+use std::thread::{available_parallelism,panicking,scope};
+
+// Declare `src/archive.rs` as a module
+pub mod archive;
 
 // Declare `src/compare.rs` as a module
 pub mod compare;
@@ -8,257 +14,701 @@ pub mod compare;
 pub mod config;
 // Re-export `Config` and `default_config()`
 // Use statements to get rid of the `config::` prefix
-pub use config::{Config,default_config};
+pub use config::{Config,IoMode,SymlinkMode,default_config};
 
 // Declare `src/data_structures.rs` as a module
 pub mod data_structures;
 // Use statements to get rid of the `data_structures::` prefix
-use data_structures::{FileCmp,FullFileComparison};
+use data_structures::{FileCmp,FilePair,FullFileComparison};
+
+// Declare `src/decompress.rs` as a module
+pub mod decompress;
+// Declare `src/elf.rs` as a module
+pub mod elf;
+// Declare `src/external.rs` as a module
+pub mod external;
+// Declare `src/images.rs` as a module
+pub mod images;
+
+// Declare `src/disk_order.rs` as a module
+pub mod disk_order;
+
+// Declare `src/fiemap.rs` as a module. It is only available on Linux
+#[cfg(target_os = "linux")]
+pub mod fiemap;
 
 // Declare `src/printing.rs` as a module
 pub mod printing;
 
+// Declare `src/structured.rs` as a module
+pub mod structured;
+
+// Declare `src/text.rs` as a module
+pub mod text;
+
 // Declare `src/totals.rs` as a module
 pub mod totals;
 // Use statements to get rid of the `totals::` prefix
-use totals::Totals;
+use totals::default_totals;
 
+// Declare `src/uring.rs` as a module. It is only available on Linux, when built with the
+// `io-uring` feature
+#[cfg(all(feature = "io-uring", target_os = "linux"))]
+pub mod uring;
 
-/// Intended as a helper function for `files_in_tree()`. Returns an unsorted vector list of
-/// relative file paths for all files (in the broad sense of the word, including links and
-/// directories, as well as hidden files) in a directory tree rooted at the directory pointed to by
-/// the path `root` / `extension`. The file paths included in the list will all begin with
-/// `extension`, but not `root`. This function is recursive, and it is often made use of by calling
-/// it with `root` as a path to a directory that roots a directory tree and with `extension`
-/// set to an empty ("") path.
+// Declare `src/walk.rs` as a module
+pub mod walk;
+// Use statements to get rid of the `walk::` prefix
+use walk::{DirectoryLister,DiscoveredFile,MergedFile,MergedTreeWalk,PendingDirectory};
+use walk::SequentialLister;
+
+
+/// Returns the number of threads the directory tree comparison (or the comparison of a single
+/// very large file) should use given the configuration `cmp-tree` is running under.
 ///
 /// #### Parameters:
-/// * `root` the beginning of the file path to the directory for which we wish to get a list of
-///      all the files in the directory tree. It will be combined with `extension` to produce the
-///      complete path.
-/// * `extension` the end of the file path to the directory for which we wish to get a list of all
-///     the files in the directory tree. It will be combined with `root` to produce the complete
-///     path. `extension` can be an empty path.
+/// * `config` a `Config` representing a configuration for executing `cmp-tree`, usually modified
+///     through command line arguments to the program.
 /// #### Return:
-/// * an unsorted vector list of the relative file paths for all the files in the directory tree
-///     rooted at `root` / `extension`. The file paths included in the list will omit `root` from
-///     their path, but include `extension`.
-fn relative_files_in_tree(root: &Path, extension: &Path) -> Vec<PathBuf> {
+/// * the number of threads to use. This is always at least 1.
+pub(crate) fn number_of_threads(config: &Config) -> usize {
     /* {{{ */
-    let full_dir_path = root.join(extension);
-    let mut ret: Vec<PathBuf> = Vec::new();
-
-    /* Get all the files in the dir relative to the 'root' directory */
-    match std::fs::read_dir(full_dir_path) {
-        Ok(dir_entries) => {
-            for e in dir_entries {
-                match e {
-                    Ok(entry) => {
-                        if let Ok(file_type) = entry.file_type() {
-                            let rel_path: PathBuf = extension.join(entry.file_name());
-                            ret.push(rel_path);
-
-                            if file_type.is_dir() {
-                                let subdir_rel_paths = relative_files_in_tree(root,
-                                    &extension.join(entry.file_name()));
-                                /* Append all the relative paths from the sub dir to our return
-                                 * list */
-                                ret.extend(subdir_rel_paths);
-                            }
-                        } else {
-                            println!("Error getting the file type of the directory
-                                entry");
-                        }
-                    },
-                    Err(_) => {
-                        println!("Error reading one of the directory entries");
+    if config.single_threaded {
+        return 1;
+    }
+    match config.jobs {
+        Some(jobs) => return std::cmp::max(jobs, 1),
+        /* If the number of threads was not specified, use one thread per core. If we fail to find
+         * out how many cores the computer has, default to 1 thread */
+        None => match available_parallelism() {
+            Ok(cores) => return cores.get(),
+            _ => return 1,
+        },
+    }
+    /* }}} */
+}
+
+
+/* The maximum number of file pairs that can be discovered, but not yet handed over in order, at
+ * any one time during a multithreaded directory tree comparison. This is what keeps the memory
+ * used by the comparison bounded, no matter how many files the directory trees contain. */
+const MAX_COMPARISONS_IN_FLIGHT: usize = 8192;
+
+
+/* The state of the listing of a directory that was requested ahead of time */
+enum ListingState {
+    /* The directory is waiting for a thread to list it */
+    Pending(PendingDirectory),
+    /* Some thread is currently listing the directory */
+    InProgress,
+    /* The directory has been listed */
+    Done(Vec<DiscoveredFile>, Vec<PendingDirectory>),
+}
+
+
+/* A struct representing all the work that remains to be done in a multithreaded directory tree
+ * comparison. It is shared between all the threads taking part in the comparison. */
+struct SharedWork {
+    /* The listings of directories requested ahead of time, keyed by the directory tree and the
+     * relative path of the directory */
+    listings: HashMap<(usize, PathBuf), ListingState>,
+    /* The keys of the listings that are waiting for a thread to list them, in the order they were
+     * requested */
+    listing_queue: VecDeque<(usize, PathBuf)>,
+    /* The positions in the output of the file pairs that are waiting to be compared, alongside the
+     * priority of each comparison (see `comparison_priority()`). The comparisons with the highest
+     * priority are taken off the heap first */
+    pending_cmps: BinaryHeap<((u64, u64), Reverse<usize>)>,
+    /* The file pairs that are waiting to be compared, keyed by their position in the output */
+    pending_files: HashMap<usize, MergedFile>,
+    /* The file comparisons that have been performed, but not handed over yet, keyed by their
+     * position in the output. A `None` represents a comparison that failed. */
+    finished_cmps: BTreeMap<usize, Option<FullFileComparison>>,
+    /* The number of file pairs discovered so far */
+    discovered: usize,
+    /* The number of file comparisons handed over so far */
+    handed_over: usize,
+    /* Whether the directory trees have been walked in their entirety */
+    walk_finished: bool,
+    /* Whether the comparisons are no longer being handed over (for example because handing one over
+     * or a thread panicked), in which case every thread should stop as soon as possible */
+    abandoned: bool,
+    /* When the comparison should stop at the first mismatch, the mismatch that stopped it. It is
+     * handed over on its own, once every other thread has been told to stop */
+    first_mismatch: Option<FullFileComparison>,
+}
+
+
+/// Intended as a helper function for `stream_directory_tree_comparison()`. Takes the lock on the
+/// work shared between the threads of a multithreaded directory tree comparison, even if another
+/// thread panicked while holding it. A thread that panics marks the comparison as abandoned, so a
+/// poisoned lock is no reason for the thread taking it to panic too.
+///
+/// #### Parameters:
+/// * `shared_work` the work shared between all the threads.
+/// #### Return:
+/// * the guard of the lock on `shared_work`.
+fn lock_shared_work(shared_work: &Mutex<SharedWork>) -> MutexGuard<'_, SharedWork> {
+    /* {{{ */
+    match shared_work.lock() {
+        Ok(work) => return work,
+        Err(poisoned) => return poisoned.into_inner(),
+    }
+    /* }}} */
+}
+
+
+/* A guard that marks a multithreaded directory tree comparison as abandoned when it is dropped,
+ * so that no thread is left waiting for comparisons to be handed over that never will be */
+struct AbandonOnDrop<'a> {
+    shared_work: &'a Mutex<SharedWork>,
+    work_available: &'a Condvar,
+}
+
+
+impl Drop for AbandonOnDrop<'_> {
+    /* {{{ */
+    fn drop(&mut self) {
+        /* {{{ */
+        let mut work = lock_shared_work(self.shared_work);
+        work.abandoned = true;
+        self.work_available.notify_all();
+        /* }}} */
+    }
+    /* }}} */
+}
+
+
+/* A guard that marks a multithreaded directory tree comparison as abandoned when the thread
+ * holding it panics, so that no thread is left waiting on work the panicking thread was doing */
+struct AbandonOnPanic<'a> {
+    shared_work: &'a Mutex<SharedWork>,
+    work_available: &'a Condvar,
+}
+
+
+impl Drop for AbandonOnPanic<'_> {
+    /* {{{ */
+    fn drop(&mut self) {
+        /* {{{ */
+        if panicking() {
+            drop(AbandonOnDrop {
+                shared_work: self.shared_work,
+                work_available: self.work_available,
+            });
+        }
+        /* }}} */
+    }
+    /* }}} */
+}
+
+
+/* A `DirectoryLister` that lets the worker threads of a multithreaded directory tree comparison
+ * list directories ahead of time */
+struct ParallelLister<'a> {
+    config: &'a Config,
+    roots: &'a [&'a Path; 2],
+    shared_work: &'a Mutex<SharedWork>,
+    work_available: &'a Condvar,
+}
+
+
+impl DirectoryLister for ParallelLister<'_> {
+    /* {{{ */
+    fn list(&self, dir: &PendingDirectory, discovered_files: &mut Vec<DiscoveredFile>,
+        pending_dirs: &mut Vec<PendingDirectory>) {
+        /* {{{ */
+
+        let key = (dir.tree, dir.rel_path.clone());
+        let mut work = self.shared_work.lock().unwrap();
+        loop {
+            match work.listings.remove(&key) {
+                Some(ListingState::Done(mut files, mut dirs)) => {
+                    discovered_files.append(&mut files);
+                    pending_dirs.append(&mut dirs);
+                    return;
+                },
+                /* If another thread is listing the directory, wait for it to finish (unless the
+                 * comparison was abandoned, in which case it may never finish) */
+                Some(ListingState::InProgress) => {
+                    work.listings.insert(key.clone(), ListingState::InProgress);
+                    if work.abandoned {
+                        return;
                     }
-                }
+                    work = self.work_available.wait(work).unwrap();
+                },
+                /* If no other thread has started listing the directory, list it ourselves */
+                Some(ListingState::Pending(_)) | None => {
+                    drop(work);
+                    walk::list_directory(self.config, self.roots[dir.tree], dir, discovered_files,
+                        pending_dirs);
+                    return;
+                },
             }
-        },
-        Err(_) => {
-            println!("Error reading contents of the directory");
         }
+        /* }}} */
     }
 
-    return ret;
+    fn prefetch(&self, dir: &PendingDirectory) {
+        /* {{{ */
+        let key = (dir.tree, dir.rel_path.clone());
+        let mut work = self.shared_work.lock().unwrap();
+        if !work.listings.contains_key(&key) {
+            work.listings.insert(key.clone(), ListingState::Pending(dir.clone()));
+            work.listing_queue.push_back(key);
+            self.work_available.notify_all();
+        }
+        /* }}} */
+    }
     /* }}} */
 }
 
 
-/// Returns an unsorted vector list of relative file paths for all the files (in the broad sense of
-/// the word, including links and directories, as well as hidden files) in a directory tree rooted
-/// at the directory pointed to by `root`.
+/// Intended as a helper function for `stream_directory_tree_comparison()`. Returns the number of
+/// file pairs that should be compared at once given the configuration `cmp-tree` is running
+/// under. This is only ever more than 1 for I/O engines that can batch the comparison of many file
+/// pairs.
 ///
 /// #### Parameters:
-/// * `root` the file path to the directory for which we wish to get a list of all the files in the
-///     directory tree.
+/// * `config` a `Config` representing a configuration for executing `cmp-tree`, usually modified
+///     through command line arguments to the program.
 /// #### Return:
-/// * an unsorted vector list of the relative file paths for all the files in the directory tree
-///     rooted at `root`.
-fn files_in_tree(root: &Path) -> Vec<PathBuf> {
+/// * the number of file pairs to compare at once. This is always at least 1.
+fn comparison_batch_size(config: &Config) -> usize {
     /* {{{ */
-    let extension = Path::new("");
-    return relative_files_in_tree(root, extension);
+    match config.io_mode {
+        #[cfg(all(feature = "io-uring", target_os = "linux"))]
+        IoMode::Uring => return 64,
+        _ => return 1,
+    }
     /* }}} */
 }
 
 
-/// Takes two paths, each pointing to directories that root directory trees and returns a `Result`
-/// that either contains a `Vec` of `FullFileComparison`s, representing a list of comparisons
-/// between all files in the directory trees, or an empty `Err`, indicating that an error occurred
-/// in the process of comparing the two directory trees.
+/// Intended as a helper function for `stream_directory_tree_comparison()`. Returns the priority
+/// with which the file pair represented by `file` should be compared. File pairs with a higher
+/// priority are compared first (the order the comparisons are output in is not affected).
+///
+/// By default, the most expensive file pairs are compared first, so that no thread is left
+/// comparing a big file long after all the other threads have finished. If `config` asks for
+/// files to be compared in the order they are stored on disk, file pairs are instead compared in
+/// on-disk order, to keep spinning disks from seeking back and forth. A pair is ordered by the
+/// read order key (see `disk_order::read_order_key()`) of its file in the first directory tree,
+/// and pairs with the same key by the key of their file in the second directory tree. Keys of
+/// different directory trees are never compared to one another, since they need not be from the
+/// same disk (or even be the same kind of key). When the two directory trees were written in the
+/// same order (e.g. one is a copy of the other), this follows the on-disk order of both sides at
+/// once. A pair that only exists on one side has nothing to read, so it is compared first.
+///
+/// This function does I/O (it opens files to find out where they are stored), so it must not be
+/// called while holding the lock on the work shared between threads.
 ///
 /// #### Parameters:
 /// * `config` a `Config` representing a configuration for executing `cmp-tree`, usually modified
 ///     through command line arguments to the program.
-/// * `first_root` a file path that points to the root directory of the first directory tree we
-///     wish to compare.
-/// * `second_root` a file path that points to the root directory of the second directory tree we
-///     wish to compare.
+/// * `roots` the file paths to the root directories of the first and second directory trees.
+/// * `file` the file pair, as discovered by a `MergedTreeWalk` of both directory trees.
 /// #### Return:
-/// * a `Result<Vec<FullFileComparison>, ()>` that represents whether this directory tree
-///     comparison was able to be caried out successfully or not. If the `Result` turns out to be
-///     the `Vec<FullFileComparison>`, then the caller is given a sorted list of all the file
-///     comparisons that were performed during the comparison of the two directory trees. If the
-///     `Result` turns out to be an `Err`, then this function experienced some sort of error.
-fn compare_directory_trees(config: &Config, first_root: &Path, second_root: &Path) -> 
-    Result<Vec<FullFileComparison>, ()> {
+/// * the priority of the file pair, made up of a primary priority and a secondary one that only
+///     tells apart file pairs with the same primary priority.
+fn comparison_priority(config: &Config, roots: &[&Path; 2], file: &MergedFile) -> (u64, u64) {
     /* {{{ */
+    if !config.disk_order {
+        return (file.estimated_cost, 0);
+    }
+    /* The lowest keys are read first */
+    match &file.metadata[..] {
+        [Some(first_md), Some(second_md)] => {
+            let first_key = disk_order::read_order_key(&roots[0].join(&file.rel_path), first_md);
+            let second_key = disk_order::read_order_key(&roots[1].join(&file.rel_path), second_md);
+            return (u64::MAX - first_key, u64::MAX - second_key);
+        },
+        _ => return (u64::MAX, u64::MAX),
+    }
+    /* }}} */
+}
 
-    let mut ret: Vec<FullFileComparison> = Vec::new();
-    /* Get the first directory file list and the second directory file list: the list of files in
-     * each directory */
-    let first_ft: Vec<PathBuf> = files_in_tree(first_root);
-    let second_ft: Vec<PathBuf> = files_in_tree(second_root);
-
-    /* Combine all the relative paths from the first and second directory roots into one combined
-     * list of relative paths */
-    let mut combined_ft: Vec<PathBuf> = Vec::new();
-    combined_ft.extend(first_ft);
-    combined_ft.extend(second_ft);
-    /* Sort the combined file tree and remove duplicate items */
-    combined_ft.sort();
-    combined_ft.dedup();
-    /* We know our return array we be at most `combined_ft` length. It will only be shorter if
-     * errors are encountered when comparing files */
-    ret.reserve(combined_ft.len());
-
-    /* If the configuration limits the program to a single thread, perform the directory tree
-     * comparison using a single thread */
-    if config.single_threaded {
-        /* Go through all the file paths in the combined  file list, creating two full paths to the
-         * file, one rooted at `first_root`, one rooted at `second_root`, and compare them */
-        for e in &combined_ft {
-            let first_path = first_root.join(e);
-            let second_path = second_root.join(e);
-
-            let cmp_res = compare::compare_files(config, &first_path, &second_path);
-
-            if cmp_res.is_ok() {
-                ret.push(
-                    FullFileComparison {
-                        first_path: first_path,
-                        second_path: second_path,
-                        partial_cmp: cmp_res.unwrap(),
-                    }
-                );
-            }
+
+/// Intended as a helper function for `stream_directory_tree_comparison()`. Compares the
+/// corresponding files represented by every element of `files`, reusing the metadata the
+/// directory walk read for them.
+///
+/// #### Parameters:
+/// * `config` a `Config` representing a configuration for executing `cmp-tree`, usually modified
+///     through command line arguments to the program.
+/// * `roots` the file paths to the root directories of the first and second directory trees.
+/// * `files` the file pairs to compare, as discovered by a `MergedTreeWalk` of both directory
+///     trees.
+/// #### Return:
+/// * the `FullFileComparison` of every file pair in the same order as `files`, or `None` for the
+///     file pairs that could not be compared.
+fn compare_merged_files(config: &Config, roots: &[&Path; 2], files: Vec<MergedFile>) ->
+    Vec<Option<FullFileComparison>> {
+    /* {{{ */
+    /* Create two full paths to every file, one rooted at the first root, one rooted at the second
+     * root, and compare them */
+    let pairs: Vec<FilePair> = files.into_iter().map(|file| {
+        let mut metadata = file.metadata.into_iter();
+        return FilePair {
+            first_path: roots[0].join(&file.rel_path),
+            first_link_metadata: metadata.next().flatten(),
+            second_path: roots[1].join(&file.rel_path),
+            second_link_metadata: metadata.next().flatten(),
+        };
+    }).collect();
+
+    let cmp_results = compare::compare_file_pairs(config, roots[0], roots[1], &pairs);
+    return pairs.into_iter().zip(cmp_results).map(|(pair, cmp_res)| {
+        match cmp_res {
+            Ok(partial_cmp) => return Some(FullFileComparison {
+                first_path: pair.first_path,
+                second_path: pair.second_path,
+                partial_cmp: partial_cmp,
+            }),
+            Err(_) => return None,
         }
+    }).collect();
+    /* }}} */
+}
 
-        return Ok(ret);
+
+/// Intended as a helper function for `stream_directory_tree_comparison()`. Walks the directory
+/// trees in sorted order, queueing up a comparison for every file pair it discovers. Blocks
+/// whenever too many comparisons are in flight.
+///
+/// #### Parameters:
+/// * `config` a `Config` representing a configuration for executing `cmp-tree`, usually modified
+///     through command line arguments to the program.
+/// * `roots` the file paths to the root directories of the first and second directory trees.
+/// * `shared_work` the work that remains to be done, shared between all the threads.
+/// * `work_available` a `Condvar` used to wake up threads waiting for more work.
+fn directory_tree_walker(config: &Config, roots: &[&Path; 2], shared_work: &Mutex<SharedWork>,
+    work_available: &Condvar) {
+    /* {{{ */
+    let lister = ParallelLister {
+        config: config,
+        roots: roots,
+        shared_work: shared_work,
+        work_available: work_available,
+    };
+    let walk = MergedTreeWalk::new(&lister, Vec::from([
+        walk::root_directory(config, 0, roots[0]),
+        walk::root_directory(config, 1, roots[1]),
+    ]));
+
+    for f in walk {
+        /* Working out the priority can mean opening the files, so do it before taking the lock */
+        let priority = comparison_priority(config, roots, &f);
+        let mut work = shared_work.lock().unwrap();
+        while work.discovered - work.handed_over >= MAX_COMPARISONS_IN_FLIGHT && !work.abandoned {
+            work = work_available.wait(work).unwrap();
+        }
+        if work.abandoned {
+            return;
+        }
+        let position = work.discovered;
+        work.pending_cmps.push((priority, Reverse(position)));
+        work.pending_files.insert(position, f);
+        work.discovered += 1;
+        work_available.notify_all();
     }
 
-    /* If we make it here that means the program has not been limited to a single thread */
+    let mut work = shared_work.lock().unwrap();
+    work.walk_finished = true;
+    work_available.notify_all();
+    /* }}} */
+}
 
-    /* Find out how many cores the computer has. If we fail to get that info, default to 1 thread
-     * */
-    let num_threads: usize = match available_parallelism() {
-        Ok(cores) => cores.get(),
-        _ => 1,
-    };
-    /* Calculate how many file pairs each thread needs to compare. Perform a ceiled division
-     * through manual math to make sure every element is a member of some chunk */
-    let chunk_size: usize =
-        (combined_ft.len() + num_threads - 1) / num_threads;
-
-    scope(|s| {
-        let mut thread_handles: Vec<ScopedJoinHandle<'_, Vec<FullFileComparison>>> = Vec::new();
-        thread_handles.reserve(num_threads);
-
-        for chunk in combined_ft.chunks(chunk_size) {
-            thread_handles.push(s.spawn(move || -> Vec<FullFileComparison> {
-                let mut ret_vec: Vec<FullFileComparison> = Vec::new();
-                ret_vec.reserve(chunk.len());
-
-                for file_pair in chunk {
-                    /* Go through all the file pairs assigned to this thread, creating two full
-                     * paths to the file, one rooted at `first_root`, one rooted at `second_root`,
-                     * and compare them */
-                    let first_file = first_root.join(file_pair);
-                    let second_file = second_root.join(file_pair);
-
-                    let cmp_res = compare::compare_files(config, &first_file, &second_file);
-
-                    if cmp_res.is_ok() {
-                        ret_vec.push(
-                            FullFileComparison {
-                                first_path: first_file,
-                                second_path: second_file,
-                                partial_cmp: cmp_res.unwrap(),
-                            }
-                        );
-                    }
-                }
 
-                return ret_vec;
-            }));
+/// Intended as a helper function for `stream_directory_tree_comparison()`. Does the same as
+/// `compare_merged_files()`, except that the file pairs are compared from the highest to the
+/// lowest priority (see `comparison_priority()`) rather than in the order they are given in.
+///
+/// #### Parameters:
+/// * `config` a `Config` representing a configuration for executing `cmp-tree`, usually modified
+///     through command line arguments to the program.
+/// * `roots` the file paths to the root directories of the first and second directory trees.
+/// * `files` the file pairs to compare.
+/// #### Return:
+/// * the comparison of every file pair, in the same order as `files`.
+fn compare_merged_files_by_priority(config: &Config, roots: &[&Path; 2], files: Vec<MergedFile>)
+    -> Vec<Option<FullFileComparison>> {
+    /* {{{ */
+    let mut prioritized: Vec<((u64, u64), Reverse<usize>, MergedFile)> = files.into_iter()
+        .enumerate().map(|(i, f)| (comparison_priority(config, roots, &f), Reverse(i), f))
+        .collect();
+    prioritized.sort_unstable_by_key(|(priority, position, _)| Reverse((*priority, *position)));
+
+    let positions: Vec<usize> = prioritized.iter().map(|(_, Reverse(i), _)| *i).collect();
+    let cmps = compare_merged_files(config, roots,
+        prioritized.into_iter().map(|(_, _, f)| f).collect());
+
+    let mut ret: Vec<Option<FullFileComparison>> = Vec::new();
+    ret.resize_with(positions.len(), || None);
+    for (position, cmp) in positions.into_iter().zip(cmps) {
+        ret[position] = cmp;
+    }
+    return ret;
+    /* }}} */
+}
+
+
+/// Intended as a helper function for `stream_directory_tree_comparison()`. Performs work for a
+/// multithreaded directory tree comparison until there is none left. Any number of threads can run
+/// this function at once, sharing the work between them.
+///
+/// There are two kinds of work: listing directories ahead of the walk and comparing file pairs.
+/// Listing directories takes priority, since it is what keeps the walk from stalling. File pairs
+/// are compared from the most expensive to the least expensive, so that no thread is left
+/// comparing a big file long after all the other threads have finished.
+///
+/// #### Parameters:
+/// * `config` a `Config` representing a configuration for executing `cmp-tree`, usually modified
+///     through command line arguments to the program.
+/// * `roots` the file paths to the root directories of the first and second directory trees.
+/// * `shared_work` the work that remains to be done, shared between all the threads.
+/// * `work_available` a `Condvar` used to wake up threads waiting for more work.
+fn directory_tree_comparison_worker(config: &Config, roots: &[&Path; 2],
+    shared_work: &Mutex<SharedWork>, work_available: &Condvar) {
+    /* {{{ */
+    let batch_size = comparison_batch_size(config);
+    let mut work = shared_work.lock().unwrap();
+    loop {
+        if work.abandoned {
+            break;
         }
-        /* Join all threads in order of creation */
-        for handle in thread_handles.into_iter() {
-            match handle.join() {
-                /* If the thread succeeded, go through its `ret_vec` and copy all its contents
-                 * `ret` */
-                Ok(ret_list) => {
-                    ret.extend(ret_list);
+
+        /* 1. If there is a directory waiting to be listed, list it */
+        if let Some(key) = work.listing_queue.pop_front() {
+            /* The walk may have gotten to the directory first and listed it itself */
+            let dir = match work.listings.remove(&key) {
+                Some(ListingState::Pending(dir)) => dir,
+                Some(state) => {
+                    work.listings.insert(key, state);
+                    continue;
                 },
-                _ => (),
+                None => continue,
+            };
+            work.listings.insert(key.clone(), ListingState::InProgress);
+            drop(work);
+
+            let mut discovered_files: Vec<DiscoveredFile> = Vec::new();
+            let mut discovered_dirs: Vec<PendingDirectory> = Vec::new();
+            walk::list_directory(config, roots[dir.tree], &dir, &mut discovered_files,
+                &mut discovered_dirs);
+
+            work = shared_work.lock().unwrap();
+            work.listings.insert(key, ListingState::Done(discovered_files, discovered_dirs));
+            work_available.notify_all();
+            continue;
+        }
+
+        /* 2. If there are file pairs waiting to be compared, compare (a batch of) them */
+        if !work.pending_cmps.is_empty() {
+            let mut positions: Vec<usize> = Vec::new();
+            let mut files: Vec<MergedFile> = Vec::new();
+            while positions.len() < batch_size {
+                match work.pending_cmps.pop() {
+                    Some((_, Reverse(position))) => {
+                        files.push(work.pending_files.remove(&position).unwrap());
+                        positions.push(position);
+                    },
+                    None => break,
+                }
+            }
+            drop(work);
+
+            let full_cmps = compare_merged_files(config, roots, files);
+
+            work = shared_work.lock().unwrap();
+            for (position, full_cmp) in positions.into_iter().zip(full_cmps) {
+                /* If the comparison should stop at the first mismatch, tell every thread to stop
+                 * (the first thread to find a mismatch wins) */
+                match full_cmp {
+                    Some(cmp) if config.quick && !cmp.partial_cmp.file_cmp.is_match() => {
+                        if !work.abandoned {
+                            work.first_mismatch = Some(cmp);
+                            work.abandoned = true;
+                        }
+                    },
+                    _ => {
+                        work.finished_cmps.insert(position, full_cmp);
+                    },
+                }
             }
+            work_available.notify_all();
+            continue;
         }
-    });
 
-    return Ok(ret);
+        /* 3. If the walk is over and there is no work waiting to be done, we're done. Otherwise,
+         * wait for more work */
+        if work.walk_finished {
+            break;
+        }
+        work = work_available.wait(work).unwrap();
+    }
     /* }}} */
 }
 
 
-
-
-/// Takes a `Vec` of `FullFileComparison`s representing a directory tree comparison and returns a a
-/// boolean representing whether or not the file comparison list received as input contains any
-/// mismatches or not.
+/// Takes two paths, each pointing to directories that root directory trees, compares every file
+/// in the directory trees and hands each `FullFileComparison` over to `sink` as soon as it (and
+/// every comparison that comes before it) is ready. The comparisons are handed over in the sorted
+/// order of the file paths they concern.
+///
+/// The directory trees are walked directory by directory and merged as they go, so the memory
+/// used by the comparison is bounded by the number of entries in a directory rather than the
+/// number of files in the directory trees.
+///
+/// If `config` asks for the comparison to stop at the first mismatch, the first mismatch any
+/// thread finds is handed over last, and every thread stops taking on more work. With several
+/// threads, it is not necessarily the first mismatch in the sorted order of the file paths.
 ///
 /// #### Parameters:
-/// * `directory_tree_comparison` a `Vec` of `FullFileComparison`s. Typically, this parameter is
-///     the unwrapped result of a call to `compare_directory_trees()`.
+/// * `config` a `Config` representing a configuration for executing `cmp-tree`, usually modified
+///     through command line arguments to the program.
+/// * `first_root` a file path that points to the root directory of the first directory tree we
+///     wish to compare.
+/// * `second_root` a file path that points to the root directory of the second directory tree we
+///     wish to compare.
+/// * `sink` a closure that is called once for every file comparison that was performed.
 /// #### Return:
-/// * a `bool` that will be `true` if there WERE any mismatches in the directory tree comparison
-///     and `false` if the directory tree comparison found the two directory trees to be identical.
-fn directory_tree_comparison_contains_mismatch(
-    directory_tree_comparison: &Vec<FullFileComparison>) -> Result<bool, ()> {
+/// * a `Result<(), ()>` that represents whether this directory tree comparison was able to be
+///     caried out successfully or not.
+fn stream_directory_tree_comparison(config: &Config, first_root: &Path, second_root: &Path,
+    sink: &mut dyn FnMut(FullFileComparison)) -> Result<(), ()> {
     /* {{{ */
-
-    /* For every comparison in the list... */
-    for e in directory_tree_comparison {
-        /* If the comparison found a mismatch of any kind between the two files, return early */
-        match e.partial_cmp.file_cmp {
-            FileCmp::Match => (),
-            _ => return Ok(true),
+    let roots: [&Path; 2] = [first_root, second_root];
+    let num_threads = number_of_threads(config);
+
+    /* If the configuration limits the program to a single thread, walk the directory trees and
+     * compare every file pair on this thread, one after the other */
+    if num_threads == 1 {
+        let lister = SequentialLister {
+            config: config,
+            roots: &roots,
+        };
+        let walk = MergedTreeWalk::new(&lister, Vec::from([
+            walk::root_directory(config, 0, first_root),
+            walk::root_directory(config, 1, second_root),
+        ]));
+        /* When comparing in on-disk order, as many file pairs as the multithreaded comparison
+         * keeps in flight are reordered at once */
+        let batch_size = if config.disk_order {
+            MAX_COMPARISONS_IN_FLIGHT
+        } else {
+            comparison_batch_size(config)
+        };
+        let mut batch: Vec<MergedFile> = Vec::new();
+        let mut walk = walk.peekable();
+        while let Some(f) = walk.next() {
+            batch.push(f);
+            if batch.len() == batch_size || walk.peek().is_none() {
+                let batch = std::mem::take(&mut batch);
+                let full_cmps = if config.disk_order {
+                    compare_merged_files_by_priority(config, &roots, batch)
+                } else {
+                    compare_merged_files(config, &roots, batch)
+                };
+                for full_cmp in full_cmps.into_iter().flatten() {
+                    let is_match = full_cmp.partial_cmp.file_cmp.is_match();
+                    sink(full_cmp);
+                    /* If the comparison should stop at the first mismatch, there is no point in
+                     * walking the directory trees any further */
+                    if config.quick && !is_match {
+                        return Ok(());
+                    }
+                }
+            }
         }
+        return Ok(());
     }
-    /* If we make it here, that means no mismatches of any kind were found in the file comparison
-     * list. */
-    return Ok(false);
+
+    /* Otherwise, one thread walks the directory trees, `num_threads` threads list directories and
+     * compare file pairs, and this thread hands the comparisons over in order */
+    let shared_work = Mutex::new(SharedWork {
+        listings: HashMap::new(),
+        listing_queue: VecDeque::new(),
+        pending_cmps: BinaryHeap::new(),
+        pending_files: HashMap::new(),
+        finished_cmps: BTreeMap::new(),
+        discovered: 0,
+        handed_over: 0,
+        walk_finished: false,
+        abandoned: false,
+        first_mismatch: None,
+    });
+    let work_available = Condvar::new();
+
+    return scope(|s| {
+        let roots = &roots;
+        let shared_work = &shared_work;
+        let work_available = &work_available;
+
+        /* A thread that panics abandons the comparison, so that the other threads stop rather
+         * than wait forever for the comparisons it was meant to finish */
+        let mut threads = Vec::from([s.spawn(move || {
+            let _guard = AbandonOnPanic {
+                shared_work: shared_work,
+                work_available: work_available,
+            };
+            directory_tree_walker(config, roots, shared_work, work_available);
+        })]);
+        for _ in 0..num_threads {
+            threads.push(s.spawn(move || {
+                let _guard = AbandonOnPanic {
+                    shared_work: shared_work,
+                    work_available: work_available,
+                };
+                directory_tree_comparison_worker(config, roots, shared_work, work_available);
+            }));
+        }
+
+        let guard = AbandonOnDrop {
+            shared_work: shared_work,
+            work_available: work_available,
+        };
+        let mut work = lock_shared_work(shared_work);
+        let first_mismatch = loop {
+            /* If a mismatch stopped the comparison early, it is the last comparison handed over */
+            if work.abandoned {
+                break work.first_mismatch.take();
+            }
+            let position = work.handed_over;
+            if let Some(full_cmp) = work.finished_cmps.remove(&position) {
+                work.handed_over += 1;
+                /* Handing over a comparison may free up room for the walk to continue */
+                work_available.notify_all();
+                drop(work);
+                if let Some(full_cmp) = full_cmp {
+                    sink(full_cmp);
+                }
+                work = lock_shared_work(shared_work);
+                continue;
+            }
+            if work.walk_finished && work.handed_over == work.discovered {
+                break None;
+            }
+            work = match work_available.wait(work) {
+                Ok(work) => work,
+                Err(poisoned) => poisoned.into_inner(),
+            };
+        };
+        drop(work);
+        drop(guard);
+        if let Some(full_cmp) = first_mismatch {
+            sink(full_cmp);
+        }
+
+        /* If any thread panicked, some of the comparisons were never handed over */
+        let mut ret: Result<(), ()> = Ok(());
+        for thread in threads {
+            if thread.join().is_err() {
+                ret = Err(());
+            }
+        }
+        return ret;
+    });
     /* }}} */
 }
 
@@ -271,51 +721,89 @@ fn directory_tree_comparison_contains_mismatch(
 /// * `config` a `Config` representing a configuration for executing `cmp-tree`, usually modified
 ///     through command line arguments to the program.
 /// * `first_dir` a file path that points to the root directory of the first directory tree we
-///     wish to compare. This function assumes that this path points to a directory and that the
-///     directory exists.
+///     wish to compare. This function assumes that this path points to a directory, a tar or zip
+///     archive or a directory inside one (see `archive::split_root()`), and that it exists.
 /// * `second_dir` a file path that points to the root directory of the second directory tree we
-///     wish to compare. This function assumes that this path points to a directory and that the
-///     directory exists.
+///     wish to compare, under the same assumptions as `first_dir`.
 /// #### Return:
 /// * an `i32` that represents how execution of the directory tree comparison went. If there was an
-///     error during execution, 2 is returned. If the comparison proceeded without error, but
-///     mismatches between files were found, 1 is returned. If the comparison proceeeded without
-///     error and no mismatches were found, 0 is returned.
+///     error during execution (e.g. an external command failed to compare two files, or `config`
+///     asks for something files in archives cannot do, see `archive::unsupported_flags()`), 2 is
+///     returned. If the comparison proceeded without error, but mismatches between files were
+///     found, 1 is returned. If the comparison proceeeded without error and no mismatches were
+///     found, 0 is returned.
 pub fn cmp_tree(config: &Config, first_dir: &Path, second_dir: &Path) -> i32 {
     /* {{{ */
-    /* Perform the comparison between the two directory trees */
-    let directory_tree_comparison_res = compare_directory_trees(&config, first_dir, second_dir);
+    let mut totals_count = default_totals();
+    let mut mismatch_occurred = false;
+    let mut external_failure_occurred = false;
+
+    /* Perform the comparison between the two directory trees, printing the appropriate output
+     * (provided silent mode is off) as each file comparison comes in */
+    let mut handle = |full_cmp: &FullFileComparison| {
+        if !config.silent {
+            printing::print_one_comparison(config, full_cmp);
+        }
+        /* Keep track of whether any mismatches occurred (this is needed to determine the exit
+         * code of this program) */
+        if !full_cmp.partial_cmp.file_cmp.is_match() {
+            mismatch_occurred = true;
+        }
+        /* An external command that failed to compare two files is an error, not a mismatch */
+        if let FileCmp::SubstanceExternalCommandFailure(_) = full_cmp.partial_cmp.file_cmp {
+            external_failure_occurred = true;
+        }
+        totals_count.update(&full_cmp.partial_cmp);
+    };
+    let mut sink = |full_cmp: FullFileComparison| {
+        handle(&full_cmp);
+        /* The files in two archives that differ follow the archives themselves. The archives are
+         * already known to differ, so an archive that cannot be read only leaves out its files */
+        let _ = archive::recurse_into_archives(config, &full_cmp, &mut |member_cmp| {
+            handle(&member_cmp);
+        });
+    };
+    /* Archives have no directories to walk, so they are enumerated in their entirety instead.
+     * Files in archives are only compared byte-for-byte, so refuse to pretend otherwise */
+    let is_archive = |root: &Path| archive::split_root(root).is_some();
+    let has_archive_root = is_archive(first_dir) || is_archive(second_dir);
+    if has_archive_root {
+        let unsupported = archive::unsupported_flags(config);
+        if !unsupported.is_empty() {
+            println!("ERROR: {} cannot be used when either directory tree is an archive",
+                unsupported.join(", "));
+            return 2;
+        }
+        if !config.silent {
+            for flag in archive::ignored_flags(config) {
+                println!("WARNING: {flag} has no effect when either directory tree is an archive");
+            }
+        }
+    }
+    let directory_tree_comparison_res = match has_archive_root {
+        true => archive::stream_archive_comparison(config, first_dir, second_dir, &mut sink),
+        false => stream_directory_tree_comparison(config, first_dir, second_dir, &mut sink),
+    };
     if let Err(_) = directory_tree_comparison_res {
         println!("ERROR: Failed to compare the directory trees");
         return 2;
     }
 
-    /* If we make it to this point, this means our directory tree comparison succeeded. Unwrap
-     * safely */
-    let directory_tree_comparison = directory_tree_comparison_res.unwrap();
-
-    /* Check if any mismatches occurred (this is needed to determine the exit code of this program
-    * */
-    let mismatch_occurred =
-        directory_tree_comparison_contains_mismatch(&directory_tree_comparison);
-    /* Print the appropriate output, provided silent mode is off */
-    if !config.silent {
-        printing::print_output(&config, &directory_tree_comparison);
-    }
     if config.totals {
-        let totals_count = Totals::calculate_from(&directory_tree_comparison);
         printing::print_totals(&totals_count);
     }
 
-    /* If a mismatch occurred during the comparison, exit with exit code 1. If there were no
-     * mismatches, and the directory trees are identical, exit with exit code 0. If there was an
-     * error in assessing whether there was any mismatch in the directory tree comparison, exit
-     * with exit code 2. */
-    match mismatch_occurred {
-        Ok(true) => return 1,
-        Ok(false) => return 0,
-        Err(_) => return 2,
+    /* If an external command failed to compare two files, exit with exit code 2, as the directory
+     * trees could not be compared in their entirety. Otherwise, if a mismatch occurred during the
+     * comparison, exit with exit code 1. If there were no mismatches, and the directory trees are
+     * identical, exit with exit code 0. */
+    if external_failure_occurred {
+        return 2;
+    }
+    if mismatch_occurred {
+        return 1;
     }
+    return 0;
     /* }}} */
 }
 
@@ -1,4 +1,4 @@
-use clap::{command, Arg}; // For parsing commandline args.
+use clap::{command, value_parser, Arg, ArgAction}; // For parsing commandline args.
 use std::path::Path;
 use std::process::exit; // For exiting with an exit code on failure. Not idiomatic.
 
@@ -7,10 +7,135 @@ use std::process::exit; // For exiting with an exit code on failure. Not idiomat
 // ??
 
 // Use statements to get rid of the `cmp_tree::` prefix (keeping the `config::` prefix!)
+use cmp_tree::archive;
 use cmp_tree::config;
 
 
+/// Parses a size given on the command line, such as the argument to `--buffer-size`. Sizes are
+/// given in bytes, optionally followed by one of the binary suffixes `K`, `M`, `G` or `T` (e.g.
+/// `64K` is 65536 bytes).
+///
+/// #### Parameters:
+/// * `arg` the command line argument to parse.
+/// #### Return:
+/// * the size in bytes on success, and an error message describing the problem on failure.
+fn parse_size(arg: &str) -> Result<u64, String> {
+    /* {{{ */
+    let (digits, multiplier): (&str, u64) = match arg.char_indices().last() {
+        Some((i, 'K')) | Some((i, 'k')) => (&arg[..i], 1 << 10),
+        Some((i, 'M')) | Some((i, 'm')) => (&arg[..i], 1 << 20),
+        Some((i, 'G')) | Some((i, 'g')) => (&arg[..i], 1 << 30),
+        Some((i, 'T')) | Some((i, 't')) => (&arg[..i], 1 << 40),
+        _ => (arg, 1),
+    };
+    match digits.parse::<u64>() {
+        Ok(n) if n > 0 => match n.checked_mul(multiplier) {
+            Some(size) => return Ok(size),
+            None => return Err(String::from("size is too large")),
+        },
+        _ => return Err(String::from("expected a positive number of bytes, optionally followed \
+            by K, M, G or T")),
+    }
+    /* }}} */
+}
+
+
+/// Parses a glob given on the command line, such as the argument to `--structured`. Globs are
+/// matched against file paths relative to the roots of the directory trees, and their `*` also
+/// matches `/`, so `*.json` matches JSON files in any directory.
+///
+/// #### Parameters:
+/// * `arg` the command line argument to parse.
+/// #### Return:
+/// * the compiled glob on success, and an error message describing the problem on failure.
+fn parse_glob(arg: &str) -> Result<globset::GlobMatcher, String> {
+    /* {{{ */
+    match globset::Glob::new(arg) {
+        Ok(glob) => return Ok(glob.compile_matcher()),
+        Err(e) => return Err(format!("invalid glob: {e}")),
+    }
+    /* }}} */
+}
+
+
+/// Parses a mask given on the command line, such as the argument to `--mask`. Masks are given as a
+/// glob (see `parse_glob()`) and a regex separated by the first `=` (e.g.
+/// `*.html=[0-9]{4}-[0-9]{2}-[0-9]{2}`).
+///
+/// #### Parameters:
+/// * `arg` the command line argument to parse.
+/// * `whole_line` whether the mask masks whole lines rather than the spans its regex matches.
+/// #### Return:
+/// * the mask on success, and an error message describing the problem on failure.
+fn parse_mask(arg: &str, whole_line: bool) -> Result<config::Mask, String> {
+    /* {{{ */
+    let (glob, regex) = match arg.split_once('=') {
+        Some((glob, regex)) => (glob, regex),
+        None => return Err(String::from("expected a glob and a regex separated by '='")),
+    };
+    match (parse_glob(glob), regex::bytes::Regex::new(regex)) {
+        (Ok(glob), Ok(regex)) => return Ok(config::Mask { glob, regex, whole_line }),
+        (Err(e), _) => return Err(e),
+        (_, Err(e)) => return Err(format!("invalid regex: {e}")),
+    }
+    /* }}} */
+}
+
+
+/// Parses an external command given on the command line, as the argument to `--external`.
+/// External commands are given as a glob (see `parse_glob()`) and a command separated by the
+/// first `=` (e.g. `*.pdf=pdftotext-diff.sh {first} {second}`). The command is split into a
+/// program and its arguments the way a POSIX shell would split it, quotes included, but is not
+/// otherwise interpreted by a shell.
+///
+/// #### Parameters:
+/// * `arg` the command line argument to parse.
+/// #### Return:
+/// * the external command on success, and an error message describing the problem on failure.
+fn parse_external(arg: &str) -> Result<config::ExternalCommand, String> {
+    /* {{{ */
+    let (glob, command) = match arg.split_once('=') {
+        Some((glob, command)) => (glob, command),
+        None => return Err(String::from("expected a glob and a command separated by '='")),
+    };
+    match (parse_glob(glob), shlex::split(command)) {
+        (Err(e), _) => return Err(e),
+        (_, None) => return Err(String::from("invalid command: unbalanced quotes")),
+        (_, Some(argv)) if argv.is_empty() => {
+            return Err(String::from("expected a command after '='"));
+        },
+        (Ok(glob), Some(argv)) => return Ok(config::ExternalCommand { glob, argv }),
+    }
+    /* }}} */
+}
+
+
+/// Checks whether a root given on the command line exists. A root can be a directory, an archive
+/// or a directory inside an archive (e.g. `release.tar.gz!/release-1.0`), in which case only the
+/// archive has to exist for now.
+///
+/// #### Parameters:
+/// * `root` the root, as given on the command line.
+/// #### Return:
+/// * whether the root exists, or an error if that could not be determined.
+fn root_exists(root: &Path) -> std::io::Result<bool> {
+    /* {{{ */
+    match archive::split_root(root) {
+        Some(_) => return Ok(true),
+        None => return root.try_exists(),
+    }
+    /* }}} */
+}
+
+
 fn main() {
+    /* The io_uring I/O engine is only available on Linux, when built with the `io-uring` feature */
+    let io_modes: &[&str] = if cfg!(all(feature = "io-uring", target_os = "linux")) {
+        &["read", "mmap", "uring"]
+    } else {
+        &["read", "mmap"]
+    };
+
     let match_result = command!()
         .arg(
             Arg::new("first_root_dir").required(true).index(1)
@@ -18,21 +143,133 @@ fn main() {
         .arg(
             Arg::new("second_root_dir").required(true).index(2)
         )
+        .arg(
+            Arg::new("buffer_size").short('b').long("buffer-size").num_args(1)
+                .value_parser(parse_size)
+        )
         .arg(
             Arg::new("date").short('d').long("date").num_args(0)
         )
+        .arg(
+            Arg::new("decompress").long("decompress").num_args(0)
+        )
+        .arg(
+            Arg::new("disk_order").long("disk-order").num_args(0)
+        )
+        .arg(
+            Arg::new("elf").long("elf").num_args(0)
+        )
+        .arg(
+            Arg::new("elf_sections").long("elf-sections").num_args(1).action(ArgAction::Append)
+                .value_delimiter(',')
+        )
+        .arg(
+            Arg::new("external").long("external").num_args(1).action(ArgAction::Append)
+                .value_parser(parse_external)
+        )
+        .arg(
+            Arg::new("external_jobs").long("external-jobs").num_args(1)
+                .value_parser(value_parser!(u64).range(1..))
+        )
+        .arg(
+            Arg::new("external_timeout").long("external-timeout").num_args(1)
+                .value_parser(value_parser!(u64).range(1..))
+        )
+        .arg(
+            Arg::new("ignore_all_space").long("ignore-all-space").num_args(0)
+        )
+        .arg(
+            Arg::new("ignore_blank_lines").long("ignore-blank-lines").num_args(0)
+        )
+        .arg(
+            Arg::new("ignore_bom").long("ignore-bom").num_args(0)
+        )
+        .arg(
+            Arg::new("ignore_trailing_newline").long("ignore-trailing-newline").num_args(0)
+        )
+        .arg(
+            Arg::new("ignore_trailing_space").long("ignore-trailing-space").num_args(0)
+        )
+        .arg(
+            Arg::new("images").long("images").num_args(0)
+        )
+        .arg(
+            Arg::new("image_tolerance").long("image-tolerance").num_args(1)
+                .value_parser(value_parser!(u8))
+        )
+        .arg(
+            Arg::new("io").long("io").num_args(1).value_parser(io_modes.to_vec())
+        )
+        .arg(
+            Arg::new("jobs").short('j').long("jobs").num_args(1)
+                .value_parser(value_parser!(u64).range(1..))
+        )
+        .arg(
+            Arg::new("follow_symlinks").short('L').long("follow-symlinks").num_args(0)
+        )
+        .arg(
+            Arg::new("mask").long("mask").num_args(1).action(ArgAction::Append)
+                .value_parser(|arg: &str| parse_mask(arg, false))
+        )
+        .arg(
+            Arg::new("mask_lines").long("mask-lines").num_args(1).action(ArgAction::Append)
+                .value_parser(|arg: &str| parse_mask(arg, true))
+        )
         .arg(
             Arg::new("matches").short('m').long("matches").num_args(0)
         )
+        .arg(
+            Arg::new("modes").long("modes").num_args(0)
+        )
+        .arg(
+            Arg::new("normalize_link_targets").long("normalize-link-targets").num_args(0)
+        )
         .arg(
             Arg::new("pretty").short('p').long("pretty").num_args(0)
         )
+        .arg(
+            Arg::new("sample").long("sample").num_args(1)
+                .value_parser(value_parser!(u64).range(1..))
+        )
+        .arg(
+            Arg::new("sample_seed").long("sample-seed").num_args(1)
+                .value_parser(value_parser!(u64))
+        )
+        .arg(
+            Arg::new("quick").short('q').long("quick").num_args(0)
+        )
+        .arg(
+            Arg::new("recurse_archives").long("recurse-archives").num_args(0)
+        )
         .arg(
             Arg::new("silent").short('s').long("silent").num_args(0)
         )
+        .arg(
+            Arg::new("shallow").long("shallow").num_args(0)
+        )
+        .arg(
+            Arg::new("shared_extents").long("shared-extents").num_args(0)
+        )
+        .arg(
+            Arg::new("strict_symlinks").long("strict-symlinks").num_args(0)
+        )
+        .arg(
+            Arg::new("sparseness").long("sparseness").num_args(0)
+        )
+        .arg(
+            Arg::new("split_large_files").long("split-large-files").num_args(1)
+                .value_parser(parse_size)
+        )
         .arg(
             Arg::new("single_threaded").short('S').long("single-threaded").num_args(0)
         )
+        .arg(
+            Arg::new("text").long("text").num_args(0)
+        )
+        .arg(
+            Arg::new("structured").long("structured").num_args(1).action(ArgAction::Append)
+                .value_parser(parse_glob)
+        )
         .arg(
             Arg::new("totals").short('t').long("totals").num_args(0)
         ).get_matches();
@@ -54,7 +291,7 @@ fn main() {
 
     // If either of the given directories don't exist, or errors occur when the program tries
     // to access them, exit the program early
-    match first_dir.try_exists() {
+    match root_exists(first_dir) {
         Ok(true) => (),
         Ok(false) => {
             println!("ERROR: the first directory tree does not exist or could not be accessed.");
@@ -65,7 +302,7 @@ fn main() {
             exit(2)
         },
     }
-    match second_dir.try_exists() {
+    match root_exists(second_dir) {
         Ok(true) => (),
         Ok(false) => {
             println!("ERROR: the second directory tree does not exist or could not be accessed.");
@@ -83,10 +320,80 @@ fn main() {
     /* Modify the config as the commandline flags/argument require */
     if match_result.get_flag("matches") { conf.matches = true; }
     if match_result.get_flag("date") { conf.compare_modification_times = true; }
+    if match_result.get_flag("modes") { conf.compare_modes = true; }
+    if match_result.get_flag("sparseness") { conf.compare_sparseness = true; }
+    if match_result.get_flag("decompress") { conf.decompress = true; }
+    if match_result.get_flag("disk_order") { conf.disk_order = true; }
+    if match_result.get_flag("elf") { conf.elf = true; }
+    if match_result.get_flag("images") { conf.images = true; }
+    if match_result.get_flag("normalize_link_targets") { conf.normalize_link_targets = true; }
     if match_result.get_flag("pretty") { conf.pretty = true; }
+    if match_result.get_flag("quick") { conf.quick = true; }
+    if match_result.get_flag("recurse_archives") { conf.recurse_archives = true; }
+    if match_result.get_flag("shallow") { conf.shallow = true; }
+    if match_result.get_flag("shared_extents") { conf.shared_extents = true; }
     if match_result.get_flag("silent") { conf.silent = true; }
     if match_result.get_flag("single_threaded") { conf.single_threaded = true; }
+    if let Some(jobs) = match_result.get_one::<u64>("jobs") { conf.jobs = Some(*jobs as usize); }
+    if let Some(commands) = match_result.get_many::<config::ExternalCommand>("external") {
+        conf.external_commands.extend(commands.cloned());
+    }
+    if let Some(jobs) = match_result.get_one::<u64>("external_jobs") {
+        conf.external_jobs = Some(*jobs as usize);
+    }
+    if let Some(secs) = match_result.get_one::<u64>("external_timeout") {
+        conf.external_timeout = Some(std::time::Duration::from_secs(*secs));
+    }
+    if let Some(io) = match_result.get_one::<String>("io") {
+        match io.as_str() {
+            "mmap" => conf.io_mode = config::IoMode::Mmap,
+            #[cfg(all(feature = "io-uring", target_os = "linux"))]
+            "uring" => conf.io_mode = config::IoMode::Uring,
+            _ => conf.io_mode = config::IoMode::Read,
+        }
+    }
+    if let Some(size) = match_result.get_one::<u64>("buffer_size") {
+        conf.buffer_size = *size as usize;
+    }
+    if let Some(sample_count) = match_result.get_one::<u64>("sample") {
+        conf.sample_blocks = Some(*sample_count);
+    }
+    if let Some(seed) = match_result.get_one::<u64>("sample_seed") { conf.sample_seed = *seed; }
+    if let Some(size) = match_result.get_one::<u64>("split_large_files") {
+        conf.large_file_threshold = Some(*size);
+    }
+    if match_result.get_flag("follow_symlinks") {
+        conf.symlink_mode = config::SymlinkMode::Follow;
+    }
+    if match_result.get_flag("strict_symlinks") {
+        conf.symlink_mode = config::SymlinkMode::FollowAndCompare;
+    }
     if match_result.get_flag("totals") { conf.totals = true; }
+    if match_result.get_flag("text") { conf.text = true; }
+    /* Ignoring parts of text files is enough for them to be compared as text, without `--text` */
+    if match_result.get_flag("ignore_bom") { conf.ignore_bom = true; }
+    if match_result.get_flag("ignore_trailing_newline") { conf.ignore_trailing_newline = true; }
+    if match_result.get_flag("ignore_trailing_space") { conf.ignore_trailing_space = true; }
+    if match_result.get_flag("ignore_all_space") { conf.ignore_all_space = true; }
+    if match_result.get_flag("ignore_blank_lines") { conf.ignore_blank_lines = true; }
+    /* Selecting ELF sections to compare only makes sense when comparing ELF files */
+    if let Some(sections) = match_result.get_many::<String>("elf_sections") {
+        conf.elf = true;
+        conf.elf_sections.extend(sections.cloned());
+    }
+    /* A tolerance only makes sense when comparing images by their pixels */
+    if let Some(tolerance) = match_result.get_one::<u8>("image_tolerance") {
+        conf.images = true;
+        conf.image_tolerance = *tolerance;
+    }
+    if let Some(globs) = match_result.get_many::<globset::GlobMatcher>("structured") {
+        conf.structured.extend(globs.cloned());
+    }
+    for id in ["mask", "mask_lines"] {
+        if let Some(masks) = match_result.get_many::<config::Mask>(id) {
+            conf.masks.extend(masks.cloned());
+        }
+    }
 
     /* Call the god function */
     let exit_code: i32 = cmp_tree::cmp_tree(&conf, first_dir, second_dir);
@@ -2,6 +2,7 @@
 use crate::config::Config;
 
 // Use statements to get rid of the `data_structures::` prefix
+use crate::data_structures::Equivalence;
 use crate::data_structures::FileCmp;
 use crate::data_structures::FullFileComparison;
 use crate::totals::Totals;
@@ -14,7 +15,6 @@ const BOLD: &str = "\x1B[1m";
 const NORMAL: &str = "\x1B[0m";
 const RED: &str = "\x1B[31m";
 const GREEN: &str = "\x1B[32m";
-#[allow(dead_code)]
 const YELLOW: &str = "\x1B[33m";
 #[allow(dead_code)]
 const BLUE: &str = "\x1B[34m";
@@ -36,7 +36,7 @@ const WHITE: &str = "\x1B[37m";
 ///     were compared.
 pub fn print_one_comparison(config: &Config, full_comp: &FullFileComparison) {
     /* {{{ */
-    match full_comp.partial_cmp.file_cmp {
+    match &full_comp.partial_cmp.file_cmp {
         FileCmp::ExistenceNeitherFile => {
             if config.pretty { print!("{BOLD}{RED}"); }
             println!("Neither {:?} nor {:?} exist", full_comp.first_path, full_comp.second_path);
@@ -60,11 +60,53 @@ pub fn print_one_comparison(config: &Config, full_comp: &FullFileComparison) {
                 full_comp.second_path);
             if config.pretty { print!("{NORMAL}"); }
         },
+        FileCmp::FileTypeDanglingSoftLinkOnlyFirstFile => {
+            if config.pretty { print!("{BOLD}{RED}"); }
+            println!("{:?} is a dangling soft link, but {:?} is not", full_comp.first_path,
+                full_comp.second_path);
+            if config.pretty { print!("{NORMAL}"); }
+        },
+        FileCmp::FileTypeDanglingSoftLinkOnlySecondFile => {
+            if config.pretty { print!("{BOLD}{RED}"); }
+            println!("{:?} is not a dangling soft link, but {:?} is", full_comp.first_path,
+                full_comp.second_path);
+            if config.pretty { print!("{NORMAL}"); }
+        },
         FileCmp::SubstanceRegFileContentMismatch => {
             if config.pretty { print!("{BOLD}{RED}"); }
             println!("{:?} differs from {:?}", full_comp.first_path, full_comp.second_path);
             if config.pretty { print!("{NORMAL}"); }
         },
+        FileCmp::SubstanceStructuredDataMismatch(key_path) => {
+            if config.pretty { print!("{BOLD}{RED}"); }
+            println!("{:?} differs from {:?} (first at {})", full_comp.first_path,
+                full_comp.second_path, key_path);
+            if config.pretty { print!("{NORMAL}"); }
+        },
+        FileCmp::SubstanceElfSectionMismatch(sections) => {
+            if config.pretty { print!("{BOLD}{RED}"); }
+            println!("{:?} differs from {:?} (in {})", full_comp.first_path,
+                full_comp.second_path, sections.join(", "));
+            if config.pretty { print!("{NORMAL}"); }
+        },
+        FileCmp::SubstanceImageDimensionsMismatch(first_dims, second_dims) => {
+            if config.pretty { print!("{BOLD}{RED}"); }
+            println!("{:?} differs from {:?} (dimensions {}x{} and {}x{})", full_comp.first_path,
+                full_comp.second_path, first_dims.0, first_dims.1, second_dims.0, second_dims.1);
+            if config.pretty { print!("{NORMAL}"); }
+        },
+        FileCmp::SubstanceImagePixelMismatch(x, y) => {
+            if config.pretty { print!("{BOLD}{RED}"); }
+            println!("{:?} differs from {:?} (first at pixel ({}, {}))", full_comp.first_path,
+                full_comp.second_path, x, y);
+            if config.pretty { print!("{NORMAL}"); }
+        },
+        FileCmp::SubstanceExternalCommandFailure(reason) => {
+            if config.pretty { print!("{BOLD}{RED}"); }
+            println!("{:?} could not be compared to {:?} ({})", full_comp.first_path,
+                full_comp.second_path, reason);
+            if config.pretty { print!("{NORMAL}"); }
+        },
         FileCmp::SubstanceSoftLinkLinkMismatch => {
             if config.pretty { print!("{BOLD}{RED}"); }
             println!("{:?} has a different link path than {:?}", full_comp.first_path,
@@ -77,6 +119,18 @@ pub fn print_one_comparison(config: &Config, full_comp: &FullFileComparison) {
                 full_comp.second_path);
             if config.pretty { print!("{NORMAL}"); }
         },
+        FileCmp::MetadataModeMismatch => {
+            if config.pretty { print!("{BOLD}{RED}"); }
+            println!("{:?} has different permissions to {:?}", full_comp.first_path,
+                full_comp.second_path);
+            if config.pretty { print!("{NORMAL}"); }
+        },
+        FileCmp::MetadataSparsenessMismatch => {
+            if config.pretty { print!("{BOLD}{RED}"); }
+            println!("{:?} has different sparseness to {:?}", full_comp.first_path,
+                full_comp.second_path);
+            if config.pretty { print!("{NORMAL}"); }
+        },
         FileCmp::Match => {
             if config.matches {
                 if config.pretty { print!("{BOLD}{GREEN}"); }
@@ -84,6 +138,48 @@ pub fn print_one_comparison(config: &Config, full_comp: &FullFileComparison) {
                 if config.pretty { print!("{NORMAL}"); }
             }
         },
+        FileCmp::MatchUnverified => {
+            if config.matches {
+                if config.pretty { print!("{BOLD}{GREEN}"); }
+                println!("{:?} == {:?} (same size and modification time, content not verified)",
+                    full_comp.first_path, full_comp.second_path);
+                if config.pretty { print!("{NORMAL}"); }
+            }
+        },
+        FileCmp::MatchSampled => {
+            if config.matches {
+                if config.pretty { print!("{BOLD}{GREEN}"); }
+                println!("{:?} == {:?} (sampled blocks match, content not fully verified)",
+                    full_comp.first_path, full_comp.second_path);
+                if config.pretty { print!("{NORMAL}"); }
+            }
+        },
+        /* Equivalences are not identical, so they are printed even without `config.matches` */
+        FileCmp::Equivalent(equivalence) => {
+            let how = match equivalence {
+                Equivalence::Text => "as text",
+                Equivalence::Whitespace => "ignoring whitespace",
+                Equivalence::Masked => "ignoring masked content",
+                Equivalence::Semantic => "as data",
+                Equivalence::Decompressed => "once decompressed",
+                Equivalence::ElfSections => "in the sections compared",
+                Equivalence::Pixels => "same pixels",
+                Equivalence::PixelsWithinTolerance => "pixels within tolerance",
+                Equivalence::External => "according to an external command",
+            };
+            if config.pretty { print!("{BOLD}{YELLOW}"); }
+            println!("{:?} is equivalent to {:?} ({})", full_comp.first_path,
+                full_comp.second_path, how);
+            if config.pretty { print!("{NORMAL}"); }
+        },
+        FileCmp::MatchSharedExtents(bytes) => {
+            if config.matches {
+                if config.pretty { print!("{BOLD}{GREEN}"); }
+                println!("{:?} == {:?} (shared extents, {} bytes not read)", full_comp.first_path,
+                    full_comp.second_path, bytes);
+                if config.pretty { print!("{NORMAL}"); }
+            }
+        },
     }
     /* }}} */
 }
@@ -97,14 +193,15 @@ pub fn print_one_comparison(config: &Config, full_comp: &FullFileComparison) {
 /// * `config` a `Config` representing a configuration for executing `cmp-tree`, usually modified
 ///     through command line arguments to the program.
 /// * `directory_tree_comparison` a `Result` possibly containing a `Vec` of `FullFileComparison`s.
-///     Typically, this parameter is the result of a call to `compare_directory_trees()`.
+///     Typically, this parameter holds every comparison that
+///     `stream_directory_tree_comparison()` handed over.
 pub fn print_output(config: &Config, directory_tree_comparison: &Vec<FullFileComparison>) {
     /* {{{ */
 
     for e in directory_tree_comparison {
         /* Print what needs to be printed for the current comparison. This function call may very
          * well print nothing */
-        print_one_comparison(&config, &e);
+        print_one_comparison(config, e);
     }
     /* }}} */
 }
@@ -129,5 +226,21 @@ pub fn print_totals(totals_count: &Totals) {
     println!("Soft link matches: {0}/{1}",
         totals_count.softlink_matches,
         totals_count.max_softlink_matches);
+    /* Only mention unverified matches and shared extents when there were any */
+    if totals_count.unverified_file_matches > 0 {
+        println!("File matches with unverified content: {0}",
+            totals_count.unverified_file_matches);
+    }
+    if totals_count.equivalent_files > 0 {
+        println!("Files equivalent without being identical: {0}", totals_count.equivalent_files);
+    }
+    if totals_count.sampled_file_matches > 0 {
+        println!("File matches from sampled blocks: {0}", totals_count.sampled_file_matches);
+    }
+    if totals_count.shared_extent_matches > 0 {
+        println!("File matches from shared extents: {0} ({1} bytes not read)",
+            totals_count.shared_extent_matches,
+            totals_count.shared_extent_bytes);
+    }
     /* }}} */
 }
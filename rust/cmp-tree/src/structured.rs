@@ -0,0 +1,149 @@
+use serde_json::Value;
+use std::path::Path;
+
+
+/* The structured data formats `cmp-tree` can parse files as, to compare them as data */
+#[derive(Debug,PartialEq,Eq,Clone,Copy)]
+pub enum Format {
+    Json,
+    Yaml,
+    Toml,
+}
+
+
+/// Returns the structured data format of a file, going by the extension of its file name.
+///
+/// #### Parameters:
+/// * `path` a file path that points to the file.
+/// #### Return:
+/// * the format of the file, or `None` if its extension is not that of a structured data format
+///     `cmp-tree` can parse.
+pub fn format_of(path: &Path) -> Option<Format> {
+    /* {{{ */
+    let extension = match path.extension().and_then(|e| e.to_str()) {
+        Some(extension) => extension.to_ascii_lowercase(),
+        None => return None,
+    };
+    match extension.as_str() {
+        "json" => return Some(Format::Json),
+        "yaml" | "yml" => return Some(Format::Yaml),
+        "toml" => return Some(Format::Toml),
+        _ => return None,
+    }
+    /* }}} */
+}
+
+
+/// Parses the contents of a structured data file into a tree of values. Every format is parsed
+/// into the same kind of tree, in which the keys of a mapping are unordered.
+///
+/// #### Parameters:
+/// * `format` the format of the file.
+/// * `contents` the contents of the file.
+/// #### Return:
+/// * the data in the file, or `None` if the file is not valid in its format (or, for YAML, has keys
+///     that are not strings).
+pub fn parse(format: Format, contents: &[u8]) -> Option<Value> {
+    /* {{{ */
+    match format {
+        Format::Json => return serde_json::from_slice(contents).ok(),
+        Format::Yaml => return serde_yaml::from_slice(contents).ok(),
+        Format::Toml => match std::str::from_utf8(contents) {
+            Ok(contents) => return toml::from_str(contents).ok(),
+            Err(_) => return None,
+        },
+    }
+    /* }}} */
+}
+
+
+/// Appends a key to a key path, in dot notation if the key is a plain identifier and in bracket
+/// notation otherwise (e.g. `$.servers["eu-west.1"].port`).
+///
+/// #### Parameters:
+/// * `path` the key path of the mapping the key belongs to.
+/// * `key` the key to append.
+/// #### Return:
+/// * the key path of the value the key maps to.
+fn key_path(path: &str, key: &str) -> String {
+    /* {{{ */
+    let is_identifier = !key.is_empty()
+        && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    match is_identifier {
+        true => return format!("{path}.{key}"),
+        false => return format!("{path}[{}]", Value::String(key.to_string())),
+    }
+    /* }}} */
+}
+
+
+/// A helper function for `first_difference()` that walks two trees of values in step.
+///
+/// #### Parameters:
+/// * `first` the first value.
+/// * `second` the second value.
+/// * `path` the key path of the two values.
+/// #### Return:
+/// * the key path of the first place the two values differ, or `None` if they are equal.
+fn first_difference_at(first: &Value, second: &Value, path: &str) -> Option<String> {
+    /* {{{ */
+    match (first, second) {
+        (Value::Object(first_map), Value::Object(second_map)) => {
+            /* Keys are visited in sorted order, so the same difference is always reported first */
+            let mut keys: Vec<&String> = first_map.keys().chain(second_map.keys()).collect();
+            keys.sort_unstable();
+            keys.dedup();
+            for key in keys {
+                let key_path = key_path(path, key);
+                match (first_map.get(key), second_map.get(key)) {
+                    (Some(first_value), Some(second_value)) => {
+                        match first_difference_at(first_value, second_value, &key_path) {
+                            Some(difference) => return Some(difference),
+                            None => (),
+                        }
+                    },
+                    /* A key only one of the mappings has */
+                    _ => return Some(key_path),
+                }
+            }
+            return None;
+        },
+        (Value::Array(first_array), Value::Array(second_array)) => {
+            for i in 0..std::cmp::max(first_array.len(), second_array.len()) {
+                let index_path = format!("{path}[{i}]");
+                match (first_array.get(i), second_array.get(i)) {
+                    (Some(first_value), Some(second_value)) => {
+                        match first_difference_at(first_value, second_value, &index_path) {
+                            Some(difference) => return Some(difference),
+                            None => (),
+                        }
+                    },
+                    /* An element only the longer of the arrays has */
+                    _ => return Some(index_path),
+                }
+            }
+            return None;
+        },
+        _ => match first == second {
+            true => return None,
+            false => return Some(path.to_string()),
+        },
+    }
+    /* }}} */
+}
+
+
+/// Compares two trees of values and returns the key path of the first place they differ, if any.
+/// Key paths start at the root of the data, `$`, and name keys in dot notation and array elements
+/// by their index (e.g. `$.dependencies.serde.features[1]`).
+///
+/// #### Parameters:
+/// * `first` the data in the first file.
+/// * `second` the data in the second file.
+/// #### Return:
+/// * the key path of the first place the two trees of values differ, or `None` if they are equal.
+pub fn first_difference(first: &Value, second: &Value) -> Option<String> {
+    /* {{{ */
+    return first_difference_at(first, second, "$");
+    /* }}} */
+}
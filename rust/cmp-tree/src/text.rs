@@ -0,0 +1,168 @@
+// Use statements to get rid of the `config::` prefix
+use crate::config::{Config,Mask};
+
+
+/* Files bigger than this are never compared as text, since comparing files as text means reading
+ * them into memory in their entirety */
+pub const MAX_TEXT_FILE_SIZE: u64 = 64 << 20;
+
+/* The number of bytes at the start of a file that are looked at to decide whether it is text */
+const TEXT_DETECTION_LEN: usize = 8000;
+
+/* The byte order mark UTF-8 text sometimes starts with */
+const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+
+
+/// Decides whether the contents of a file look like text. Like Git does, a file is deemed text
+/// when there is no NUL byte in the first few thousand bytes of it.
+///
+/// #### Parameters:
+/// * `contents` the contents of the file.
+/// #### Return:
+/// * `true` if the file looks like text and `false` if it looks binary.
+pub fn is_text(contents: &[u8]) -> bool {
+    /* {{{ */
+    let len = std::cmp::min(contents.len(), TEXT_DETECTION_LEN);
+    return !contents[..len].contains(&0);
+    /* }}} */
+}
+
+
+/// Normalizes the contents of a text file so that text files that only differ in ways `config`
+/// deems insignificant normalize to the same bytes. Line endings are only normalized (CRLF becomes
+/// LF) if `config.text` is set, and a UTF-8 byte order mark and a trailing newline are only
+/// dropped if `config` asks for it. Ignoring some other part of text files is enough for them to
+/// be compared as text (see `ignores_anything()`), but not for their line endings to be ignored.
+///
+/// #### Parameters:
+/// * `config` a `Config` representing a configuration for executing `cmp-tree`, usually modified
+///     through command line arguments to the program.
+/// * `contents` the contents of a text file.
+/// #### Return:
+/// * the normalized contents of the text file.
+pub fn normalize(config: &Config, contents: &[u8]) -> Vec<u8> {
+    /* {{{ */
+    let mut contents = contents;
+    if config.ignore_bom {
+        contents = contents.strip_prefix(UTF8_BOM).unwrap_or(contents);
+    }
+
+    let mut ret: Vec<u8> = Vec::with_capacity(contents.len());
+    let mut i = 0;
+    while i < contents.len() {
+        /* A CR is only dropped when it is part of a CRLF, lone CRs are left as they are */
+        if config.text && contents[i] == b'\r' && contents.get(i + 1) == Some(&b'\n') {
+            i += 1;
+            continue;
+        }
+        ret.push(contents[i]);
+        i += 1;
+    }
+
+    if config.ignore_trailing_newline && ret.last() == Some(&b'\n') {
+        ret.pop();
+    }
+
+    return ret;
+    /* }}} */
+}
+
+
+/// Returns whether some part of text files (whitespace, a byte order mark or a trailing newline)
+/// is to be ignored. Ignoring any of them only makes sense when comparing text files as text, so
+/// asking for it is enough for them to be compared as text, whether or not `config.text` is set.
+///
+/// #### Parameters:
+/// * `config` a `Config` representing a configuration for executing `cmp-tree`, usually modified
+///     through command line arguments to the program.
+/// #### Return:
+/// * `true` if `config` asks for some part of text files to be ignored and `false` otherwise.
+pub fn ignores_anything(config: &Config) -> bool {
+    /* {{{ */
+    return ignores_whitespace(config) || config.ignore_bom || config.ignore_trailing_newline;
+    /* }}} */
+}
+
+
+/// Returns whether the whitespace of text files is to be ignored in some way.
+///
+/// #### Parameters:
+/// * `config` a `Config` representing a configuration for executing `cmp-tree`, usually modified
+///     through command line arguments to the program.
+/// #### Return:
+/// * `true` if `config` asks for some whitespace to be ignored and `false` otherwise.
+pub fn ignores_whitespace(config: &Config) -> bool {
+    /* {{{ */
+    return config.ignore_trailing_space || config.ignore_all_space || config.ignore_blank_lines;
+    /* }}} */
+}
+
+
+/// Normalizes the whitespace of a text file that has already been normalized by `normalize()`, so
+/// that text files that only differ in the whitespace `config` asks to ignore normalize to the
+/// same bytes. Works like the `-Z`, `-w` and `-B` options of `diff`: trailing whitespace, all
+/// whitespace and blank lines are dropped, respectively.
+///
+/// #### Parameters:
+/// * `config` a `Config` representing a configuration for executing `cmp-tree`, usually modified
+///     through command line arguments to the program.
+/// * `contents` the normalized contents of a text file.
+/// #### Return:
+/// * the contents of the text file with its whitespace normalized.
+pub fn normalize_whitespace(config: &Config, contents: &[u8]) -> Vec<u8> {
+    /* {{{ */
+    let is_space = |c: &u8| matches!(*c, b' ' | b'\t' | b'\r' | 0x0B | 0x0C);
+    let mut ret: Vec<u8> = Vec::with_capacity(contents.len());
+
+    for line in contents.split(|c| *c == b'\n') {
+        let mut line: Vec<u8> = line.to_vec();
+        if config.ignore_all_space {
+            line.retain(|c| !is_space(c));
+        } else if config.ignore_trailing_space {
+            while line.last().is_some_and(is_space) {
+                line.pop();
+            }
+        }
+        /* A line that only had whitespace in it is blank once its whitespace is dropped */
+        if config.ignore_blank_lines && line.iter().all(is_space) {
+            continue;
+        }
+        ret.extend_from_slice(&line);
+        ret.push(b'\n');
+    }
+
+    return ret;
+    /* }}} */
+}
+
+
+/// Masks the content matched by `masks` out of a text file, so that text files that only differ
+/// in content expected to differ (e.g. timestamps or host names embedded by a build) mask to the
+/// same bytes. A mask that applies to whole lines empties every line its regex matches, while any
+/// other mask removes the spans of text its regex matches. Lines are masked one at a time, so a
+/// match never spans several lines.
+///
+/// #### Parameters:
+/// * `masks` the masks that apply to the text file.
+/// * `contents` the normalized contents of a text file.
+/// #### Return:
+/// * the contents of the text file with the content matched by `masks` masked out.
+pub fn apply_masks(masks: &[&Mask], contents: &[u8]) -> Vec<u8> {
+    /* {{{ */
+    let mut ret: Vec<u8> = Vec::with_capacity(contents.len());
+
+    for line in contents.split(|c| *c == b'\n') {
+        let masks_line = masks.iter().any(|mask| mask.whole_line && mask.regex.is_match(line));
+        if !masks_line {
+            let mut line: Vec<u8> = line.to_vec();
+            for mask in masks.iter().filter(|mask| !mask.whole_line) {
+                line = mask.regex.replace_all(&line, &b""[..]).into_owned();
+            }
+            ret.extend_from_slice(&line);
+        }
+        ret.push(b'\n');
+    }
+
+    return ret;
+    /* }}} */
+}
@@ -13,6 +13,20 @@ pub struct Totals {
     pub file_matches: u128,
     pub dir_matches: u128,
     pub softlink_matches: u128,
+    /* The number of regular file matches that were established from their size and modification
+     * time alone, without comparing their contents. They are not counted as regular file matches,
+     * since those are byte-for-byte matches */
+    pub unverified_file_matches: u128,
+    /* The number of regular files that are equivalent without being byte-for-byte identical. They
+     * are not counted as regular file matches */
+    pub equivalent_files: u128,
+    /* The number of regular file matches that were established from sampled blocks alone. These
+     * are not counted as byte-for-byte regular file matches */
+    pub sampled_file_matches: u128,
+    /* The number of regular file matches that were established from shared extents, and the
+     * number of bytes that did not need to be read because of it */
+    pub shared_extent_matches: u128,
+    pub shared_extent_bytes: u128,
 }
 
 
@@ -46,7 +60,7 @@ impl Totals {
     /// * `p_cmp` a `PartialFileComparison` containing only the necessary the information about the
     ///     2 files that were compared.
     // TODO: this function is difficult to read and long. See you if you can fix it sometime
-    fn update(&mut self, p_cmp: &PartialFileComparison) {
+    pub fn update(&mut self, p_cmp: &PartialFileComparison) {
         /* {{{ */
         /* First we determine how the given `PartialFileComparison` should affect the max file,
          * directory, etc. match counts in the `Totals` struct */
@@ -122,6 +136,14 @@ impl Totals {
                 SimpleFileType::Directory => self.dir_matches += 1,
                 SimpleFileType::SoftLink => self.softlink_matches += 1,
             },
+            FileCmp::MatchUnverified => self.unverified_file_matches += 1,
+            FileCmp::Equivalent(_) => self.equivalent_files += 1,
+            FileCmp::MatchSampled => self.sampled_file_matches += 1,
+            FileCmp::MatchSharedExtents(bytes) => {
+                self.file_matches += 1;
+                self.shared_extent_matches += 1;
+                self.shared_extent_bytes += *bytes as u128;
+            },
             /* If the file comparison is anything but a match, do nothing to the totals */
             _ => (),
         }
@@ -142,5 +164,10 @@ pub fn default_totals() -> Totals {
         file_matches: 0,
         dir_matches: 0,
         softlink_matches: 0,
+        unverified_file_matches: 0,
+        sampled_file_matches: 0,
+        equivalent_files: 0,
+        shared_extent_matches: 0,
+        shared_extent_bytes: 0,
     };
 }
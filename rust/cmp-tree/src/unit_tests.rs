@@ -6,6 +6,40 @@ mod unit_tests {
     use crate::data_structures::FullFileComparison;
     use crate::data_structures::PartialFileComparison;
     use crate::data_structures::SimpleFileType;
+    // Use statements to get rid of the `walk::` prefix
+    use crate::walk::files_in_tree;
+
+    /// Takes two paths, each pointing to directories that root directory trees and returns a
+    /// `Result` that either contains a `Vec` of `FullFileComparison`s, representing a list of
+    /// comparisons between all files in the directory trees, or an empty `Err`, indicating that an
+    /// error occurred in the process of comparing the two directory trees. Collects what
+    /// `stream_directory_tree_comparison()` hands over, so that the tests can look at every
+    /// comparison at once.
+    ///
+    /// #### Parameters:
+    /// * `config` a `Config` representing a configuration for executing `cmp-tree`, usually
+    ///     modified through command line arguments to the program.
+    /// * `first_root` a file path that points to the root directory of the first directory tree
+    ///     we wish to compare.
+    /// * `second_root` a file path that points to the root directory of the second directory tree
+    ///     we wish to compare.
+    /// #### Return:
+    /// * a `Result<Vec<FullFileComparison>, ()>` that represents whether this directory tree
+    ///     comparison was able to be caried out successfully or not. If the `Result` turns out to
+    ///     be the `Vec<FullFileComparison>`, then the caller is given a list of all the file
+    ///     comparisons that were performed during the comparison of the two directory trees, in
+    ///     the order they were handed over in.
+    fn compare_directory_trees(config: &Config, first_root: &Path, second_root: &Path) ->
+        Result<Vec<FullFileComparison>, ()> {
+        /* {{{ */
+        let mut ret: Vec<FullFileComparison> = Vec::new();
+        match stream_directory_tree_comparison(config, first_root, second_root,
+            &mut |full_cmp| ret.push(full_cmp)) {
+            Ok(()) => return Ok(ret),
+            Err(_) => return Err(()),
+        }
+        /* }}} */
+    }
 
     #[test]
     fn ut_files_in_tree_001() {
@@ -17,7 +51,7 @@ mod unit_tests {
         ]);
         expected_ret.sort();
 
-        let mut ret = files_in_tree(&root_dir);
+        let mut ret = files_in_tree(&default_config(), root_dir);
         ret.sort();
         assert_eq!(ret, expected_ret);
         /* }}} */
@@ -36,7 +70,7 @@ mod unit_tests {
         ]);
         expected_ret.sort();
 
-        let mut ret = files_in_tree(&root_dir);
+        let mut ret = files_in_tree(&default_config(), root_dir);
         ret.sort();
         assert_eq!(ret, expected_ret);
         /* }}} */
@@ -67,7 +101,7 @@ mod unit_tests {
         ]);
         expected_ret.sort();
 
-        let mut ret = files_in_tree(&root_dir);
+        let mut ret = files_in_tree(&default_config(), root_dir);
         ret.sort();
         assert_eq!(ret, expected_ret);
         /* }}} */
@@ -101,12 +135,62 @@ mod unit_tests {
         ]);
         expected_ret.sort();
 
-        let mut ret = files_in_tree(&root_dir);
+        let mut ret = files_in_tree(&default_config(), root_dir);
+        ret.sort();
+        assert_eq!(ret, expected_ret);
+        /* }}} */
+    }
+
+    #[test]
+    fn ut_files_in_tree_005() {
+        /* {{{ */
+        let mut conf = default_config();
+        conf.symlink_mode = SymlinkMode::Follow;
+        /* Both `root` and `adirectory/parent` resolve to the root of the directory tree. Neither
+         * should be descended into when following soft links */
+        let root_dir = Path::new("../../tests/021/first");
+        let mut expected_ret = Vec::from([
+            Path::new("adirectory"),
+            Path::new("adirectory/file.txt"),
+            Path::new("adirectory/parent"),
+            Path::new("dangling"),
+            Path::new("root"),
+        ]);
+        expected_ret.sort();
+
+        let mut ret = files_in_tree(&conf, root_dir);
         ret.sort();
         assert_eq!(ret, expected_ret);
         /* }}} */
     }
 
+    #[test]
+    fn ut_merged_tree_walk_001() {
+        /* {{{ */
+        /* Walking two directory trees at once should visit every file that exists in either
+         * directory tree exactly once, in sorted order */
+        let conf = default_config();
+        let first_dir = Path::new("../../tests/007/first");
+        let second_dir = Path::new("../../tests/007/second");
+        let mut expected_ret = files_in_tree(&conf, first_dir);
+        expected_ret.extend(files_in_tree(&conf, second_dir));
+        expected_ret.sort();
+        expected_ret.dedup();
+
+        let roots: [&Path; 2] = [first_dir, second_dir];
+        let lister = SequentialLister {
+            config: &conf,
+            roots: &roots,
+        };
+        let walk = MergedTreeWalk::new(&lister, Vec::from([
+            walk::root_directory(&conf, 0, first_dir),
+            walk::root_directory(&conf, 1, second_dir),
+        ]));
+        let ret: Vec<PathBuf> = walk.map(|f| f.rel_path).collect();
+        assert_eq!(ret, expected_ret);
+        /* }}} */
+    }
+
     #[test]
     fn ut_compare_directory_trees_001() {
         /* {{{ */
@@ -121,8 +205,8 @@ mod unit_tests {
                     first_ft: Some(SimpleFileType::RegFile),
                     second_ft: Some(SimpleFileType::RegFile),
                 },
-                first_path: PathBuf::from(first_dir.join("cmp_man_pages.txt")),
-                second_path: PathBuf::from(second_dir.join("cmp_man_pages.txt")),
+                first_path: first_dir.join("cmp_man_pages.txt"),
+                second_path: second_dir.join("cmp_man_pages.txt"),
             },
             FullFileComparison {
                 partial_cmp: PartialFileComparison {
@@ -130,18 +214,18 @@ mod unit_tests {
                     first_ft: Some(SimpleFileType::RegFile),
                     second_ft: Some(SimpleFileType::RegFile),
                 },
-                first_path: PathBuf::from(first_dir.join("Lorem.txt")),
-                second_path: PathBuf::from(second_dir.join("Lorem.txt")),
+                first_path: first_dir.join("Lorem.txt"),
+                second_path: second_dir.join("Lorem.txt"),
             },
         ]);
         expected_ret_content.sort();
 
-        match compare_directory_trees(&conf, &first_dir, &second_dir) {
+        match compare_directory_trees(&conf, first_dir, second_dir) {
             Ok(mut ret_content) => {
                 ret_content.sort();
                 assert_eq!(ret_content, expected_ret_content);
             },
-            Err(_) => assert!(false),
+            Err(_) => panic!(),
         }
         /* }}} */
     }
@@ -160,8 +244,8 @@ mod unit_tests {
                     first_ft: Some(SimpleFileType::RegFile),
                     second_ft: Some(SimpleFileType::RegFile),
                 },
-                first_path: PathBuf::from(first_dir.join("cmp_man_pages.txt")),
-                second_path: PathBuf::from(second_dir.join("cmp_man_pages.txt")),
+                first_path: first_dir.join("cmp_man_pages.txt"),
+                second_path: second_dir.join("cmp_man_pages.txt"),
             },
             FullFileComparison {
                 partial_cmp: PartialFileComparison {
@@ -169,18 +253,18 @@ mod unit_tests {
                     first_ft: Some(SimpleFileType::RegFile),
                     second_ft: Some(SimpleFileType::RegFile),
                 },
-                first_path: PathBuf::from(first_dir.join("Lorem.txt")),
-                second_path: PathBuf::from(second_dir.join("Lorem.txt")),
+                first_path: first_dir.join("Lorem.txt"),
+                second_path: second_dir.join("Lorem.txt"),
             },
         ]);
         expected_ret_content.sort();
 
-        match compare_directory_trees(&conf, &first_dir, &second_dir) {
+        match compare_directory_trees(&conf, first_dir, second_dir) {
             Ok(mut ret_content) => {
                 ret_content.sort();
                 assert_eq!(ret_content, expected_ret_content);
             },
-            Err(_) => assert!(false),
+            Err(_) => panic!(),
         }
         /* }}} */
     }
@@ -199,8 +283,8 @@ mod unit_tests {
                     first_ft: Some(SimpleFileType::RegFile),
                     second_ft: Some(SimpleFileType::RegFile),
                 },
-                first_path: PathBuf::from(first_dir.join("cmp_man_pages.txt")),
-                second_path: PathBuf::from(second_dir.join("cmp_man_pages.txt")),
+                first_path: first_dir.join("cmp_man_pages.txt"),
+                second_path: second_dir.join("cmp_man_pages.txt"),
             },
             FullFileComparison {
                 partial_cmp: PartialFileComparison {
@@ -208,8 +292,8 @@ mod unit_tests {
                     first_ft: Some(SimpleFileType::RegFile),
                     second_ft: Some(SimpleFileType::RegFile),
                 },
-                first_path: PathBuf::from(first_dir.join("Lorem.txt")),
-                second_path: PathBuf::from(second_dir.join("Lorem.txt")),
+                first_path: first_dir.join("Lorem.txt"),
+                second_path: second_dir.join("Lorem.txt"),
             },
             FullFileComparison {
                 partial_cmp: PartialFileComparison {
@@ -217,8 +301,8 @@ mod unit_tests {
                     first_ft: Some(SimpleFileType::Directory),
                     second_ft: Some(SimpleFileType::Directory),
                 },
-                first_path: PathBuf::from(first_dir.join("subdir")),
-                second_path: PathBuf::from(second_dir.join("subdir")),
+                first_path: first_dir.join("subdir"),
+                second_path: second_dir.join("subdir"),
             },
             FullFileComparison {
                 partial_cmp: PartialFileComparison {
@@ -226,8 +310,8 @@ mod unit_tests {
                     first_ft: Some(SimpleFileType::RegFile),
                     second_ft: Some(SimpleFileType::RegFile),
                 },
-                first_path: PathBuf::from(first_dir.join("subdir/linear_gradient.png")),
-                second_path: PathBuf::from(second_dir.join("subdir/linear_gradient.png")),
+                first_path: first_dir.join("subdir/linear_gradient.png"),
+                second_path: second_dir.join("subdir/linear_gradient.png"),
             },
             FullFileComparison {
                 partial_cmp: PartialFileComparison {
@@ -235,18 +319,18 @@ mod unit_tests {
                     first_ft: Some(SimpleFileType::RegFile),
                     second_ft: Some(SimpleFileType::RegFile),
                 },
-                first_path: PathBuf::from(first_dir.join("subdir/rose.png")),
-                second_path: PathBuf::from(second_dir.join("subdir/rose.png")),
+                first_path: first_dir.join("subdir/rose.png"),
+                second_path: second_dir.join("subdir/rose.png"),
             },
         ]);
         expected_ret_content.sort();
 
-        match compare_directory_trees(&conf, &first_dir, &second_dir) {
+        match compare_directory_trees(&conf, first_dir, second_dir) {
             Ok(mut ret_content) => {
                 ret_content.sort();
                 assert_eq!(ret_content, expected_ret_content);
             },
-            Err(_) => assert!(false),
+            Err(_) => panic!(),
         }
         /* }}} */
     }
@@ -265,8 +349,8 @@ mod unit_tests {
                     first_ft: Some(SimpleFileType::RegFile),
                     second_ft: Some(SimpleFileType::RegFile),
                 },
-                first_path: PathBuf::from(first_dir.join("cmp_man_pages.txt")),
-                second_path: PathBuf::from(second_dir.join("cmp_man_pages.txt")),
+                first_path: first_dir.join("cmp_man_pages.txt"),
+                second_path: second_dir.join("cmp_man_pages.txt"),
             },
             FullFileComparison {
                 partial_cmp: PartialFileComparison {
@@ -274,8 +358,8 @@ mod unit_tests {
                     first_ft: Some(SimpleFileType::RegFile),
                     second_ft: Some(SimpleFileType::RegFile),
                 },
-                first_path: PathBuf::from(first_dir.join("Lorem.txt")),
-                second_path: PathBuf::from(second_dir.join("Lorem.txt")),
+                first_path: first_dir.join("Lorem.txt"),
+                second_path: second_dir.join("Lorem.txt"),
             },
             FullFileComparison {
                 partial_cmp: PartialFileComparison {
@@ -283,8 +367,8 @@ mod unit_tests {
                     first_ft: Some(SimpleFileType::Directory),
                     second_ft: Some(SimpleFileType::Directory),
                 },
-                first_path: PathBuf::from(first_dir.join("subdir")),
-                second_path: PathBuf::from(second_dir.join("subdir")),
+                first_path: first_dir.join("subdir"),
+                second_path: second_dir.join("subdir"),
             },
             FullFileComparison {
                 partial_cmp: PartialFileComparison {
@@ -292,8 +376,8 @@ mod unit_tests {
                     first_ft: Some(SimpleFileType::RegFile),
                     second_ft: Some(SimpleFileType::RegFile),
                 },
-                first_path: PathBuf::from(first_dir.join("subdir/linear_gradient.png")),
-                second_path: PathBuf::from(second_dir.join("subdir/linear_gradient.png")),
+                first_path: first_dir.join("subdir/linear_gradient.png"),
+                second_path: second_dir.join("subdir/linear_gradient.png"),
             },
             FullFileComparison {
                 partial_cmp: PartialFileComparison {
@@ -301,18 +385,18 @@ mod unit_tests {
                     first_ft: Some(SimpleFileType::RegFile),
                     second_ft: Some(SimpleFileType::RegFile),
                 },
-                first_path: PathBuf::from(first_dir.join("subdir/rose.png")),
-                second_path: PathBuf::from(second_dir.join("subdir/rose.png")),
+                first_path: first_dir.join("subdir/rose.png"),
+                second_path: second_dir.join("subdir/rose.png"),
             },
         ]);
         expected_ret_content.sort();
 
-        match compare_directory_trees(&conf, &first_dir, &second_dir) {
+        match compare_directory_trees(&conf, first_dir, second_dir) {
             Ok(mut ret_content) => {
                 ret_content.sort();
                 assert_eq!(ret_content, expected_ret_content);
             },
-            Err(_) => assert!(false),
+            Err(_) => panic!(),
         }
         /* }}} */
     }
@@ -331,8 +415,8 @@ mod unit_tests {
                     first_ft: Some(SimpleFileType::RegFile),
                     second_ft: Some(SimpleFileType::RegFile),
                 },
-                first_path: PathBuf::from(first_dir.join("linear_gradient.png")),
-                second_path: PathBuf::from(second_dir.join("linear_gradient.png")),
+                first_path: first_dir.join("linear_gradient.png"),
+                second_path: second_dir.join("linear_gradient.png"),
             },
             FullFileComparison {
                 partial_cmp: PartialFileComparison {
@@ -340,8 +424,8 @@ mod unit_tests {
                     first_ft: Some(SimpleFileType::RegFile),
                     second_ft: Some(SimpleFileType::RegFile),
                 },
-                first_path: PathBuf::from(first_dir.join("Lorem.txt")),
-                second_path: PathBuf::from(second_dir.join("Lorem.txt")),
+                first_path: first_dir.join("Lorem.txt"),
+                second_path: second_dir.join("Lorem.txt"),
             },
             FullFileComparison {
                 partial_cmp: PartialFileComparison {
@@ -349,18 +433,18 @@ mod unit_tests {
                     first_ft: Some(SimpleFileType::RegFile),
                     second_ft: Some(SimpleFileType::RegFile),
                 },
-                first_path: PathBuf::from(first_dir.join("rose.png")),
-                second_path: PathBuf::from(second_dir.join("rose.png")),
+                first_path: first_dir.join("rose.png"),
+                second_path: second_dir.join("rose.png"),
             },
         ]);
         expected_ret_content.sort();
 
-        match compare_directory_trees(&conf, &first_dir, &second_dir) {
+        match compare_directory_trees(&conf, first_dir, second_dir) {
             Ok(mut ret_content) => {
                 ret_content.sort();
                 assert_eq!(ret_content, expected_ret_content);
             },
-            Err(_) => assert!(false),
+            Err(_) => panic!(),
         }
         /* }}} */
     }
@@ -379,8 +463,8 @@ mod unit_tests {
                     first_ft: Some(SimpleFileType::Directory),
                     second_ft: Some(SimpleFileType::Directory)
                 },
-                first_path: PathBuf::from(first_dir.join("a")),
-                second_path: PathBuf::from(second_dir.join("a")),
+                first_path: first_dir.join("a"),
+                second_path: second_dir.join("a"),
             },
             FullFileComparison {
                 partial_cmp: PartialFileComparison {
@@ -388,8 +472,8 @@ mod unit_tests {
                     first_ft: Some(SimpleFileType::Directory),
                     second_ft: Some(SimpleFileType::Directory)
                 },
-                first_path: PathBuf::from(first_dir.join("a/i")),
-                second_path: PathBuf::from(second_dir.join("a/i")),
+                first_path: first_dir.join("a/i"),
+                second_path: second_dir.join("a/i"),
             },
             FullFileComparison {
                 partial_cmp: PartialFileComparison {
@@ -397,8 +481,8 @@ mod unit_tests {
                     first_ft: Some(SimpleFileType::Directory),
                     second_ft: Some(SimpleFileType::Directory)
                 },
-                first_path: PathBuf::from(first_dir.join("b")),
-                second_path: PathBuf::from(second_dir.join("b")),
+                first_path: first_dir.join("b"),
+                second_path: second_dir.join("b"),
             },
             FullFileComparison {
                 partial_cmp: PartialFileComparison {
@@ -406,8 +490,8 @@ mod unit_tests {
                     first_ft: Some(SimpleFileType::Directory),
                     second_ft: Some(SimpleFileType::Directory)
                 },
-                first_path: PathBuf::from(first_dir.join("b/i")),
-                second_path: PathBuf::from(second_dir.join("b/i")),
+                first_path: first_dir.join("b/i"),
+                second_path: second_dir.join("b/i"),
             },
             FullFileComparison {
                 partial_cmp: PartialFileComparison {
@@ -415,8 +499,8 @@ mod unit_tests {
                     first_ft: Some(SimpleFileType::Directory),
                     second_ft: Some(SimpleFileType::Directory)
                 },
-                first_path: PathBuf::from(first_dir.join("b/i/1")),
-                second_path: PathBuf::from(second_dir.join("b/i/1")),
+                first_path: first_dir.join("b/i/1"),
+                second_path: second_dir.join("b/i/1"),
             },
             FullFileComparison {
                 partial_cmp: PartialFileComparison {
@@ -424,8 +508,8 @@ mod unit_tests {
                     first_ft: Some(SimpleFileType::Directory),
                     second_ft: Some(SimpleFileType::Directory)
                 },
-                first_path: PathBuf::from(first_dir.join("b/ii")),
-                second_path: PathBuf::from(second_dir.join("b/ii")),
+                first_path: first_dir.join("b/ii"),
+                second_path: second_dir.join("b/ii"),
             },
             FullFileComparison {
                 partial_cmp: PartialFileComparison {
@@ -433,8 +517,8 @@ mod unit_tests {
                     first_ft: Some(SimpleFileType::Directory),
                     second_ft: Some(SimpleFileType::Directory)
                 },
-                first_path: PathBuf::from(first_dir.join("b/ii/2")),
-                second_path: PathBuf::from(second_dir.join("b/ii/2")),
+                first_path: first_dir.join("b/ii/2"),
+                second_path: second_dir.join("b/ii/2"),
             },
             FullFileComparison {
                 partial_cmp: PartialFileComparison {
@@ -442,8 +526,8 @@ mod unit_tests {
                     first_ft: Some(SimpleFileType::Directory),
                     second_ft: Some(SimpleFileType::Directory)
                 },
-                first_path: PathBuf::from(first_dir.join("c")),
-                second_path: PathBuf::from(second_dir.join("c")),
+                first_path: first_dir.join("c"),
+                second_path: second_dir.join("c"),
             },
             FullFileComparison {
                 partial_cmp: PartialFileComparison {
@@ -451,8 +535,8 @@ mod unit_tests {
                     first_ft: Some(SimpleFileType::Directory),
                     second_ft: Some(SimpleFileType::Directory)
                 },
-                first_path: PathBuf::from(first_dir.join("c/i")),
-                second_path: PathBuf::from(second_dir.join("c/i")),
+                first_path: first_dir.join("c/i"),
+                second_path: second_dir.join("c/i"),
             },
             FullFileComparison {
                 partial_cmp: PartialFileComparison {
@@ -460,8 +544,8 @@ mod unit_tests {
                     first_ft: Some(SimpleFileType::Directory),
                     second_ft: Some(SimpleFileType::Directory)
                 },
-                first_path: PathBuf::from(first_dir.join("c/i/1")),
-                second_path: PathBuf::from(second_dir.join("c/i/1")),
+                first_path: first_dir.join("c/i/1"),
+                second_path: second_dir.join("c/i/1"),
             },
             FullFileComparison {
                 partial_cmp: PartialFileComparison {
@@ -469,8 +553,8 @@ mod unit_tests {
                     first_ft: Some(SimpleFileType::Directory),
                     second_ft: Some(SimpleFileType::Directory)
                 },
-                first_path: PathBuf::from(first_dir.join("c/i/1/a")),
-                second_path: PathBuf::from(second_dir.join("c/i/1/a")),
+                first_path: first_dir.join("c/i/1/a"),
+                second_path: second_dir.join("c/i/1/a"),
             },
             FullFileComparison {
                 partial_cmp: PartialFileComparison {
@@ -478,8 +562,8 @@ mod unit_tests {
                     first_ft: Some(SimpleFileType::Directory),
                     second_ft: Some(SimpleFileType::Directory)
                 },
-                first_path: PathBuf::from(first_dir.join("c/ii")),
-                second_path: PathBuf::from(second_dir.join("c/ii")),
+                first_path: first_dir.join("c/ii"),
+                second_path: second_dir.join("c/ii"),
             },
             FullFileComparison {
                 partial_cmp: PartialFileComparison {
@@ -487,8 +571,8 @@ mod unit_tests {
                     first_ft: Some(SimpleFileType::Directory),
                     second_ft: Some(SimpleFileType::Directory)
                 },
-                first_path: PathBuf::from(first_dir.join("c/ii/2")),
-                second_path: PathBuf::from(second_dir.join("c/ii/2")),
+                first_path: first_dir.join("c/ii/2"),
+                second_path: second_dir.join("c/ii/2"),
             },
             FullFileComparison {
                 partial_cmp: PartialFileComparison {
@@ -496,8 +580,8 @@ mod unit_tests {
                     first_ft: Some(SimpleFileType::Directory),
                     second_ft: Some(SimpleFileType::Directory)
                 },
-                first_path: PathBuf::from(first_dir.join("c/ii/2/b")),
-                second_path: PathBuf::from(second_dir.join("c/ii/2/b")),
+                first_path: first_dir.join("c/ii/2/b"),
+                second_path: second_dir.join("c/ii/2/b"),
             },
             FullFileComparison {
                 partial_cmp: PartialFileComparison {
@@ -505,8 +589,8 @@ mod unit_tests {
                     first_ft: Some(SimpleFileType::Directory),
                     second_ft: Some(SimpleFileType::Directory)
                 },
-                first_path: PathBuf::from(first_dir.join("c/iii")),
-                second_path: PathBuf::from(second_dir.join("c/iii")),
+                first_path: first_dir.join("c/iii"),
+                second_path: second_dir.join("c/iii"),
             },
             FullFileComparison {
                 partial_cmp: PartialFileComparison {
@@ -514,8 +598,8 @@ mod unit_tests {
                     first_ft: Some(SimpleFileType::Directory),
                     second_ft: Some(SimpleFileType::Directory)
                 },
-                first_path: PathBuf::from(first_dir.join("c/iii/3")),
-                second_path: PathBuf::from(second_dir.join("c/iii/3")),
+                first_path: first_dir.join("c/iii/3"),
+                second_path: second_dir.join("c/iii/3"),
             },
             FullFileComparison {
                 partial_cmp: PartialFileComparison {
@@ -523,18 +607,18 @@ mod unit_tests {
                     first_ft: Some(SimpleFileType::Directory),
                     second_ft: Some(SimpleFileType::Directory)
                 },
-                first_path: PathBuf::from(first_dir.join("c/iii/3/c")),
-                second_path: PathBuf::from(second_dir.join("c/iii/3/c")),
+                first_path: first_dir.join("c/iii/3/c"),
+                second_path: second_dir.join("c/iii/3/c"),
             },
         ]);
         expected_ret_content.sort();
 
-        match compare_directory_trees(&conf, &first_dir, &second_dir) {
+        match compare_directory_trees(&conf, first_dir, second_dir) {
             Ok(mut ret_content) => {
                 ret_content.sort();
                 assert_eq!(ret_content, expected_ret_content);
             },
-            Err(_) => assert!(false),
+            Err(_) => panic!(),
         }
         /* }}} */
     }
@@ -553,8 +637,8 @@ mod unit_tests {
                     first_ft: Some(SimpleFileType::Directory),
                     second_ft: Some(SimpleFileType::Directory)
                 },
-                first_path: PathBuf::from(first_dir.join("a")),
-                second_path: PathBuf::from(second_dir.join("a")),
+                first_path: first_dir.join("a"),
+                second_path: second_dir.join("a"),
             },
             FullFileComparison {
                 partial_cmp: PartialFileComparison {
@@ -562,8 +646,8 @@ mod unit_tests {
                     first_ft: Some(SimpleFileType::Directory),
                     second_ft: Some(SimpleFileType::Directory)
                 },
-                first_path: PathBuf::from(first_dir.join("a/i")),
-                second_path: PathBuf::from(second_dir.join("a/i")),
+                first_path: first_dir.join("a/i"),
+                second_path: second_dir.join("a/i"),
             },
             FullFileComparison {
                 partial_cmp: PartialFileComparison {
@@ -571,8 +655,8 @@ mod unit_tests {
                     first_ft: Some(SimpleFileType::Directory),
                     second_ft: Some(SimpleFileType::Directory)
                 },
-                first_path: PathBuf::from(first_dir.join("b")),
-                second_path: PathBuf::from(second_dir.join("b")),
+                first_path: first_dir.join("b"),
+                second_path: second_dir.join("b"),
             },
             FullFileComparison {
                 partial_cmp: PartialFileComparison {
@@ -580,8 +664,8 @@ mod unit_tests {
                     first_ft: Some(SimpleFileType::Directory),
                     second_ft: Some(SimpleFileType::Directory)
                 },
-                first_path: PathBuf::from(first_dir.join("b/i")),
-                second_path: PathBuf::from(second_dir.join("b/i")),
+                first_path: first_dir.join("b/i"),
+                second_path: second_dir.join("b/i"),
             },
             FullFileComparison {
                 partial_cmp: PartialFileComparison {
@@ -589,8 +673,8 @@ mod unit_tests {
                     first_ft: Some(SimpleFileType::Directory),
                     second_ft: Some(SimpleFileType::Directory)
                 },
-                first_path: PathBuf::from(first_dir.join("b/i/1")),
-                second_path: PathBuf::from(second_dir.join("b/i/1")),
+                first_path: first_dir.join("b/i/1"),
+                second_path: second_dir.join("b/i/1"),
             },
             FullFileComparison {
                 partial_cmp: PartialFileComparison {
@@ -598,8 +682,8 @@ mod unit_tests {
                     first_ft: Some(SimpleFileType::Directory),
                     second_ft: Some(SimpleFileType::Directory)
                 },
-                first_path: PathBuf::from(first_dir.join("b/ii")),
-                second_path: PathBuf::from(second_dir.join("b/ii")),
+                first_path: first_dir.join("b/ii"),
+                second_path: second_dir.join("b/ii"),
             },
             FullFileComparison {
                 partial_cmp: PartialFileComparison {
@@ -607,8 +691,8 @@ mod unit_tests {
                     first_ft: Some(SimpleFileType::Directory),
                     second_ft: Some(SimpleFileType::Directory)
                 },
-                first_path: PathBuf::from(first_dir.join("b/ii/2")),
-                second_path: PathBuf::from(second_dir.join("b/ii/2")),
+                first_path: first_dir.join("b/ii/2"),
+                second_path: second_dir.join("b/ii/2"),
             },
             FullFileComparison {
                 partial_cmp: PartialFileComparison {
@@ -616,8 +700,8 @@ mod unit_tests {
                     first_ft: Some(SimpleFileType::Directory),
                     second_ft: None
                 },
-                first_path: PathBuf::from(first_dir.join("c")),
-                second_path: PathBuf::from(second_dir.join("c")),
+                first_path: first_dir.join("c"),
+                second_path: second_dir.join("c"),
             },
             FullFileComparison {
                 partial_cmp: PartialFileComparison {
@@ -625,8 +709,8 @@ mod unit_tests {
                     first_ft: Some(SimpleFileType::Directory),
                     second_ft: None
                 },
-                first_path: PathBuf::from(first_dir.join("c/i")),
-                second_path: PathBuf::from(second_dir.join("c/i")),
+                first_path: first_dir.join("c/i"),
+                second_path: second_dir.join("c/i"),
             },
             FullFileComparison {
                 partial_cmp: PartialFileComparison {
@@ -634,8 +718,8 @@ mod unit_tests {
                     first_ft: Some(SimpleFileType::Directory),
                     second_ft: None
                 },
-                first_path: PathBuf::from(first_dir.join("c/i/1")),
-                second_path: PathBuf::from(second_dir.join("c/i/1")),
+                first_path: first_dir.join("c/i/1"),
+                second_path: second_dir.join("c/i/1"),
             },
             FullFileComparison {
                 partial_cmp: PartialFileComparison {
@@ -643,8 +727,8 @@ mod unit_tests {
                     first_ft: Some(SimpleFileType::Directory),
                     second_ft: None
                 },
-                first_path: PathBuf::from(first_dir.join("c/i/1/a")),
-                second_path: PathBuf::from(second_dir.join("c/i/1/a")),
+                first_path: first_dir.join("c/i/1/a"),
+                second_path: second_dir.join("c/i/1/a"),
             },
             FullFileComparison {
                 partial_cmp: PartialFileComparison {
@@ -652,8 +736,8 @@ mod unit_tests {
                     first_ft: Some(SimpleFileType::Directory),
                     second_ft: None
                 },
-                first_path: PathBuf::from(first_dir.join("c/ii")),
-                second_path: PathBuf::from(second_dir.join("c/ii")),
+                first_path: first_dir.join("c/ii"),
+                second_path: second_dir.join("c/ii"),
             },
             FullFileComparison {
                 partial_cmp: PartialFileComparison {
@@ -661,8 +745,8 @@ mod unit_tests {
                     first_ft: Some(SimpleFileType::Directory),
                     second_ft: None
                 },
-                first_path: PathBuf::from(first_dir.join("c/ii/2")),
-                second_path: PathBuf::from(second_dir.join("c/ii/2")),
+                first_path: first_dir.join("c/ii/2"),
+                second_path: second_dir.join("c/ii/2"),
             },
             FullFileComparison {
                 partial_cmp: PartialFileComparison {
@@ -670,8 +754,8 @@ mod unit_tests {
                     first_ft: Some(SimpleFileType::Directory),
                     second_ft: None
                 },
-                first_path: PathBuf::from(first_dir.join("c/ii/2/b")),
-                second_path: PathBuf::from(second_dir.join("c/ii/2/b")),
+                first_path: first_dir.join("c/ii/2/b"),
+                second_path: second_dir.join("c/ii/2/b"),
             },
             FullFileComparison {
                 partial_cmp: PartialFileComparison {
@@ -679,8 +763,8 @@ mod unit_tests {
                     first_ft: Some(SimpleFileType::Directory),
                     second_ft: None
                 },
-                first_path: PathBuf::from(first_dir.join("c/iii")),
-                second_path: PathBuf::from(second_dir.join("c/iii")),
+                first_path: first_dir.join("c/iii"),
+                second_path: second_dir.join("c/iii"),
             },
             FullFileComparison {
                 partial_cmp: PartialFileComparison {
@@ -688,8 +772,8 @@ mod unit_tests {
                     first_ft: Some(SimpleFileType::Directory),
                     second_ft: None
                 },
-                first_path: PathBuf::from(first_dir.join("c/iii/3")),
-                second_path: PathBuf::from(second_dir.join("c/iii/3")),
+                first_path: first_dir.join("c/iii/3"),
+                second_path: second_dir.join("c/iii/3"),
             },
             FullFileComparison {
                 partial_cmp: PartialFileComparison {
@@ -697,8 +781,8 @@ mod unit_tests {
                     first_ft: Some(SimpleFileType::Directory),
                     second_ft: None
                 },
-                first_path: PathBuf::from(first_dir.join("c/iii/3/c")),
-                second_path: PathBuf::from(second_dir.join("c/iii/3/c")),
+                first_path: first_dir.join("c/iii/3/c"),
+                second_path: second_dir.join("c/iii/3/c"),
             },
             FullFileComparison {
                 partial_cmp: PartialFileComparison {
@@ -706,8 +790,8 @@ mod unit_tests {
                     first_ft: None,
                     second_ft: Some(SimpleFileType::Directory),
                 },
-                first_path: PathBuf::from(first_dir.join("d")),
-                second_path: PathBuf::from(second_dir.join("d")),
+                first_path: first_dir.join("d"),
+                second_path: second_dir.join("d"),
             },
             FullFileComparison {
                 partial_cmp: PartialFileComparison {
@@ -715,8 +799,8 @@ mod unit_tests {
                     first_ft: None,
                     second_ft: Some(SimpleFileType::Directory),
                 },
-                first_path: PathBuf::from(first_dir.join("d/i")),
-                second_path: PathBuf::from(second_dir.join("d/i")),
+                first_path: first_dir.join("d/i"),
+                second_path: second_dir.join("d/i"),
             },
             FullFileComparison {
                 partial_cmp: PartialFileComparison {
@@ -724,8 +808,8 @@ mod unit_tests {
                     first_ft: None,
                     second_ft: Some(SimpleFileType::Directory),
                 },
-                first_path: PathBuf::from(first_dir.join("d/i/1")),
-                second_path: PathBuf::from(second_dir.join("d/i/1")),
+                first_path: first_dir.join("d/i/1"),
+                second_path: second_dir.join("d/i/1"),
             },
             FullFileComparison {
                 partial_cmp: PartialFileComparison {
@@ -733,8 +817,8 @@ mod unit_tests {
                     first_ft: None,
                     second_ft: Some(SimpleFileType::Directory),
                 },
-                first_path: PathBuf::from(first_dir.join("d/i/1/a")),
-                second_path: PathBuf::from(second_dir.join("d/i/1/a")),
+                first_path: first_dir.join("d/i/1/a"),
+                second_path: second_dir.join("d/i/1/a"),
             },
             FullFileComparison {
                 partial_cmp: PartialFileComparison {
@@ -742,8 +826,8 @@ mod unit_tests {
                     first_ft: None,
                     second_ft: Some(SimpleFileType::Directory),
                 },
-                first_path: PathBuf::from(first_dir.join("d/ii")),
-                second_path: PathBuf::from(second_dir.join("d/ii")),
+                first_path: first_dir.join("d/ii"),
+                second_path: second_dir.join("d/ii"),
             },
             FullFileComparison {
                 partial_cmp: PartialFileComparison {
@@ -751,8 +835,8 @@ mod unit_tests {
                     first_ft: None,
                     second_ft: Some(SimpleFileType::Directory),
                 },
-                first_path: PathBuf::from(first_dir.join("d/ii/2")),
-                second_path: PathBuf::from(second_dir.join("d/ii/2")),
+                first_path: first_dir.join("d/ii/2"),
+                second_path: second_dir.join("d/ii/2"),
             },
             FullFileComparison {
                 partial_cmp: PartialFileComparison {
@@ -760,8 +844,8 @@ mod unit_tests {
                     first_ft: None,
                     second_ft: Some(SimpleFileType::Directory),
                 },
-                first_path: PathBuf::from(first_dir.join("d/ii/2/b")),
-                second_path: PathBuf::from(second_dir.join("d/ii/2/b")),
+                first_path: first_dir.join("d/ii/2/b"),
+                second_path: second_dir.join("d/ii/2/b"),
             },
             FullFileComparison {
                 partial_cmp: PartialFileComparison {
@@ -769,8 +853,8 @@ mod unit_tests {
                     first_ft: None,
                     second_ft: Some(SimpleFileType::Directory),
                 },
-                first_path: PathBuf::from(first_dir.join("d/iii")),
-                second_path: PathBuf::from(second_dir.join("d/iii")),
+                first_path: first_dir.join("d/iii"),
+                second_path: second_dir.join("d/iii"),
             },
             FullFileComparison {
                 partial_cmp: PartialFileComparison {
@@ -778,8 +862,8 @@ mod unit_tests {
                     first_ft: None,
                     second_ft: Some(SimpleFileType::Directory),
                 },
-                first_path: PathBuf::from(first_dir.join("d/iii/3")),
-                second_path: PathBuf::from(second_dir.join("d/iii/3")),
+                first_path: first_dir.join("d/iii/3"),
+                second_path: second_dir.join("d/iii/3"),
             },
             FullFileComparison {
                 partial_cmp: PartialFileComparison {
@@ -787,8 +871,8 @@ mod unit_tests {
                     first_ft: None,
                     second_ft: Some(SimpleFileType::Directory),
                 },
-                first_path: PathBuf::from(first_dir.join("d/iii/3/c")),
-                second_path: PathBuf::from(second_dir.join("d/iii/3/c")),
+                first_path: first_dir.join("d/iii/3/c"),
+                second_path: second_dir.join("d/iii/3/c"),
             },
             FullFileComparison {
                 partial_cmp: PartialFileComparison {
@@ -796,8 +880,8 @@ mod unit_tests {
                     first_ft: None,
                     second_ft: Some(SimpleFileType::Directory),
                 },
-                first_path: PathBuf::from(first_dir.join("d/iv")),
-                second_path: PathBuf::from(second_dir.join("d/iv")),
+                first_path: first_dir.join("d/iv"),
+                second_path: second_dir.join("d/iv"),
             },
             FullFileComparison {
                 partial_cmp: PartialFileComparison {
@@ -805,8 +889,8 @@ mod unit_tests {
                     first_ft: None,
                     second_ft: Some(SimpleFileType::Directory),
                 },
-                first_path: PathBuf::from(first_dir.join("d/iv/4")),
-                second_path: PathBuf::from(second_dir.join("d/iv/4")),
+                first_path: first_dir.join("d/iv/4"),
+                second_path: second_dir.join("d/iv/4"),
             },
             FullFileComparison {
                 partial_cmp: PartialFileComparison {
@@ -814,19 +898,19 @@ mod unit_tests {
                     first_ft: None,
                     second_ft: Some(SimpleFileType::Directory),
                 },
-                first_path: PathBuf::from(first_dir.join("d/iv/4/d")),
-                second_path: PathBuf::from(second_dir.join("d/iv/4/d")),
+                first_path: first_dir.join("d/iv/4/d"),
+                second_path: second_dir.join("d/iv/4/d"),
             },
         ]);
         expected_ret_content.sort();
 
-        match compare_directory_trees(&conf, &first_dir, &second_dir) {
+        match compare_directory_trees(&conf, first_dir, second_dir) {
             Ok(mut ret_content) => {
                 ret_content.sort();
                 println!(" ret: {:#?}\n exp: {:#?}", ret_content, expected_ret_content);
                 assert_eq!(ret_content, expected_ret_content);
             },
-            Err(_) => assert!(false),
+            Err(_) => panic!(),
         }
         /* }}} */
     }
@@ -845,8 +929,8 @@ mod unit_tests {
                     first_ft: Some(SimpleFileType::Directory),
                     second_ft: Some(SimpleFileType::Directory),
                 },
-                first_path: PathBuf::from(first_dir.join("adirectory")),
-                second_path: PathBuf::from(second_dir.join("adirectory")),
+                first_path: first_dir.join("adirectory"),
+                second_path: second_dir.join("adirectory"),
             },
             FullFileComparison {
                 partial_cmp: PartialFileComparison {
@@ -854,8 +938,8 @@ mod unit_tests {
                     first_ft: Some(SimpleFileType::Directory),
                     second_ft: Some(SimpleFileType::Directory),
                 },
-                first_path: PathBuf::from(first_dir.join("adirectory/dir")),
-                second_path: PathBuf::from(second_dir.join("adirectory/dir")),
+                first_path: first_dir.join("adirectory/dir"),
+                second_path: second_dir.join("adirectory/dir"),
             },
             FullFileComparison {
                 partial_cmp: PartialFileComparison {
@@ -863,8 +947,8 @@ mod unit_tests {
                     first_ft: Some(SimpleFileType::RegFile),
                     second_ft: Some(SimpleFileType::RegFile),
                 },
-                first_path: PathBuf::from(first_dir.join("adirectory/file.txt")),
-                second_path: PathBuf::from(second_dir.join("adirectory/file.txt")),
+                first_path: first_dir.join("adirectory/file.txt"),
+                second_path: second_dir.join("adirectory/file.txt"),
             },
             FullFileComparison {
                 partial_cmp: PartialFileComparison {
@@ -872,18 +956,18 @@ mod unit_tests {
                     first_ft: Some(SimpleFileType::SoftLink),
                     second_ft: Some(SimpleFileType::SoftLink),
                 },
-                first_path: PathBuf::from(first_dir.join("link")),
-                second_path: PathBuf::from(second_dir.join("link")),
+                first_path: first_dir.join("link"),
+                second_path: second_dir.join("link"),
             },
         ]);
         expected_ret_content.sort();
 
-        match compare_directory_trees(&conf, &first_dir, &second_dir) {
+        match compare_directory_trees(&conf, first_dir, second_dir) {
             Ok(mut ret_content) => {
                 ret_content.sort();
                 assert_eq!(ret_content, expected_ret_content);
             },
-            Err(_) => assert!(false),
+            Err(_) => panic!(),
         }
         /* }}} */
     }
@@ -902,19 +986,211 @@ mod unit_tests {
                     first_ft: Some(SimpleFileType::SoftLink),
                     second_ft: Some(SimpleFileType::SoftLink),
                 },
-                first_path: PathBuf::from(first_dir.join("link")),
-                second_path: PathBuf::from(second_dir.join("link")),
+                first_path: first_dir.join("link"),
+                second_path: second_dir.join("link"),
             },
         ]);
         expected_ret_content.sort();
 
-        match compare_directory_trees(&conf, &first_dir, &second_dir) {
+        match compare_directory_trees(&conf, first_dir, second_dir) {
             Ok(mut ret_content) => {
                 ret_content.sort();
                 assert_eq!(ret_content, expected_ret_content);
             },
-            Err(_) => assert!(false),
+            Err(_) => panic!(),
         }
         /* }}} */
     }
+
+    #[test]
+    fn ut_compare_directory_trees_011() {
+        /* {{{ */
+        let mut conf = default_config();
+        conf.symlink_mode = SymlinkMode::Follow;
+        let first_dir = Path::new("../../tests/022/first");
+        let second_dir = Path::new("../../tests/022/second");
+        /* `expected_ret` would be `Ok(expected_ret_content)` */
+        let mut expected_ret_content = Vec::from([
+            FullFileComparison {
+                partial_cmp: PartialFileComparison {
+                    file_cmp: FileCmp::Match,
+                    first_ft: Some(SimpleFileType::RegFile),
+                    second_ft: Some(SimpleFileType::RegFile),
+                },
+                first_path: first_dir.join("file.txt"),
+                second_path: second_dir.join("file.txt"),
+            },
+            FullFileComparison {
+                partial_cmp: PartialFileComparison {
+                    file_cmp: FileCmp::FileTypeDanglingSoftLinkOnlySecondFile,
+                    first_ft: Some(SimpleFileType::RegFile),
+                    second_ft: Some(SimpleFileType::SoftLink),
+                },
+                first_path: first_dir.join("link"),
+                second_path: second_dir.join("link"),
+            },
+        ]);
+        expected_ret_content.sort();
+
+        match compare_directory_trees(&conf, first_dir, second_dir) {
+            Ok(mut ret_content) => {
+                ret_content.sort();
+                assert_eq!(ret_content, expected_ret_content);
+            },
+            Err(_) => panic!(),
+        }
+        /* }}} */
+    }
+
+    #[test]
+    fn ut_compare_directory_trees_012() {
+        /* {{{ */
+        /* No matter how many threads perform the comparison, the file comparisons should come back
+         * in the sorted order of their file paths */
+        let mut single_threaded_conf = default_config();
+        single_threaded_conf.single_threaded = true;
+        let mut multi_threaded_conf = default_config();
+        multi_threaded_conf.jobs = Some(3);
+        let first_dir = Path::new("../../tests/007/first");
+        let second_dir = Path::new("../../tests/007/second");
+
+        let expected_ret = compare_directory_trees(&single_threaded_conf, first_dir, second_dir);
+        let ret = compare_directory_trees(&multi_threaded_conf, first_dir, second_dir);
+        match (ret, expected_ret) {
+            (Ok(ret_content), Ok(expected_ret_content)) => {
+                assert_eq!(ret_content, expected_ret_content);
+            },
+            _ => panic!(),
+        }
+        /* }}} */
+    }
+
+    #[test]
+    fn ut_compare_directory_trees_013() {
+        /* {{{ */
+        /* Comparing files in the order they are stored on disk should not change the output,
+         * whether the comparison is single-threaded or multithreaded */
+        let conf = default_config();
+        let mut single_threaded_conf = default_config();
+        single_threaded_conf.disk_order = true;
+        single_threaded_conf.single_threaded = true;
+        let mut multi_threaded_conf = default_config();
+        multi_threaded_conf.disk_order = true;
+        multi_threaded_conf.jobs = Some(3);
+        let first_dir = Path::new("../../tests/007/first");
+        let second_dir = Path::new("../../tests/007/second");
+
+        let expected_ret = compare_directory_trees(&conf, first_dir, second_dir);
+        let single_threaded_ret =
+            compare_directory_trees(&single_threaded_conf, first_dir, second_dir);
+        let multi_threaded_ret =
+            compare_directory_trees(&multi_threaded_conf, first_dir, second_dir);
+        match (single_threaded_ret, multi_threaded_ret, expected_ret) {
+            (Ok(single_threaded_content), Ok(multi_threaded_content), Ok(expected_content)) => {
+                assert_eq!(single_threaded_content, expected_content);
+                assert_eq!(multi_threaded_content, expected_content);
+            },
+            _ => panic!(),
+        }
+        /* }}} */
+    }
+
+    #[test]
+    fn ut_compare_directory_trees_014() {
+        /* {{{ */
+        /* When stopping at the first mismatch, the mismatch should be the last comparison handed
+         * over, whether the comparison is single-threaded or multithreaded */
+        let mut single_threaded_conf = default_config();
+        single_threaded_conf.quick = true;
+        single_threaded_conf.single_threaded = true;
+        let mut multi_threaded_conf = default_config();
+        multi_threaded_conf.quick = true;
+        multi_threaded_conf.jobs = Some(3);
+        let first_dir = Path::new("../../tests/007/first");
+        let second_dir = Path::new("../../tests/007/second");
+
+        for conf in [single_threaded_conf, multi_threaded_conf] {
+            match compare_directory_trees(&conf, first_dir, second_dir) {
+                Ok(ret_content) => {
+                    let (last, rest) = ret_content.split_last().unwrap();
+                    assert!(!last.partial_cmp.file_cmp.is_match());
+                    assert!(rest.iter().all(|cmp| cmp.partial_cmp.file_cmp.is_match()));
+                },
+                Err(_) => panic!(),
+            }
+        }
+        /* }}} */
+    }
+
+    #[test]
+    fn ut_comparison_priority_001() {
+        /* {{{ */
+        /* File pairs are ordered by the first side, then the second side, when comparing files in
+         * the order they are stored on disk, unless they only exist on one side */
+        let mut conf = default_config();
+        let roots: [&Path; 2] = [Path::new("../../tests/001/first"),
+            Path::new("../../tests/001/second")];
+        let rel_path = PathBuf::from("Lorem.txt");
+        let metadata: Vec<std::fs::Metadata> = roots.iter()
+            .map(|root| std::fs::symlink_metadata(root.join(&rel_path)).unwrap()).collect();
+        let keys: Vec<u64> = roots.iter().zip(&metadata)
+            .map(|(root, md)| crate::disk_order::read_order_key(&root.join(&rel_path), md))
+            .collect();
+        let both_sides = MergedFile {
+            rel_path: rel_path.clone(),
+            estimated_cost: 7,
+            metadata: vec![Some(metadata[0].clone()), Some(metadata[1].clone())],
+        };
+        let second_side_only = MergedFile {
+            rel_path: rel_path.clone(),
+            estimated_cost: 7,
+            metadata: vec![None, Some(metadata[1].clone())],
+        };
+
+        assert_eq!(comparison_priority(&conf, &roots, &both_sides), (7, 0));
+        conf.disk_order = true;
+        assert_eq!(comparison_priority(&conf, &roots, &both_sides),
+            (u64::MAX - keys[0], u64::MAX - keys[1]));
+        assert_eq!(comparison_priority(&conf, &roots, &second_side_only), (u64::MAX, u64::MAX));
+        /* }}} */
+    }
+
+    #[test]
+    fn ut_totals_calculate_from_001() {
+        /* {{{ */
+        /* Matches whose contents were not compared in their entirety are not counted as
+         * byte-for-byte matches */
+        let reg_file_cmp = |file_cmp: FileCmp| FullFileComparison {
+            partial_cmp: PartialFileComparison {
+                file_cmp: file_cmp,
+                first_ft: Some(SimpleFileType::RegFile),
+                second_ft: Some(SimpleFileType::RegFile),
+            },
+            first_path: PathBuf::from("first/file"),
+            second_path: PathBuf::from("second/file"),
+        };
+        let totals = totals::Totals::calculate_from(&vec![
+            reg_file_cmp(FileCmp::Match),
+            reg_file_cmp(FileCmp::MatchUnverified),
+            reg_file_cmp(FileCmp::MatchSampled),
+        ]);
+
+        assert_eq!(totals.max_file_matches, 3);
+        assert_eq!(totals.file_matches, 1);
+        assert_eq!(totals.unverified_file_matches, 1);
+        assert_eq!(totals.sampled_file_matches, 1);
+        /* }}} */
+    }
+
+    #[test]
+    fn ut_number_of_threads_001() {
+        /* {{{ */
+        let mut conf = default_config();
+        conf.jobs = Some(5);
+        assert_eq!(number_of_threads(&conf), 5);
+        /* Limiting the program to a single thread takes precedence over the number of jobs */
+        conf.single_threaded = true;
+        assert_eq!(number_of_threads(&conf), 1);
+        /* }}} */
+    }
 }
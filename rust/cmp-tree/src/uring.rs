@@ -0,0 +1,199 @@
+use std::cell::RefCell;
+use std::ffi::CString;
+use std::fs::File;
+use std::io::ErrorKind;
+use std::os::fd::{AsRawFd,FromRawFd};
+use std::os::unix::ffi::OsStrExt; // For turning paths into C strings
+use std::path::Path;
+
+use io_uring::{IoUring,opcode,squeue,types};
+
+
+// Use statements to get rid of the `data_structures::` prefix
+use crate::data_structures::FileCmp;
+
+
+/* The number of entries in the submission queue of each thread's ring. Batches bigger than this
+ * are submitted in several rounds. */
+const RING_ENTRIES: u32 = 256;
+
+
+thread_local! {
+    /* Every thread that compares files gets its own ring, created the first time it is needed.
+     * `None` if the ring could not be created (e.g. because the kernel does not support io_uring
+     * or it has been disabled). */
+    static RING: RefCell<Option<IoUring>> = RefCell::new(IoUring::new(RING_ENTRIES).ok());
+}
+
+
+/// Submits every entry in `entries` to `ring` and waits for all of them to complete, returning the
+/// result of every entry in the same order as `entries`. The `user_data` of every entry must be
+/// its index in `entries`.
+///
+/// Waiting is retried when it is interrupted, so that no entry is left in flight. If waiting fails
+/// for any other reason, the kernel may still be using the paths and buffers of the entries that
+/// did not complete, and `ring` may still hold entries that were never submitted. The caller must
+/// then keep those paths and buffers alive for good and stop using `ring`.
+///
+/// #### Parameters:
+/// * `ring` the ring to submit the entries to.
+/// * `entries` the submission queue entries to submit.
+/// #### Return:
+/// * the results of the entries (negative `errno` values on failure) on success. Otherwise, an
+///     `Err` holding the results of the entries that completed, with `None` for every entry that
+///     may still be in flight.
+fn submit_all(ring: &mut IoUring, entries: &[squeue::Entry]) ->
+    Result<Vec<i32>, Vec<Option<i32>>> {
+    /* {{{ */
+    let mut results: Vec<Option<i32>> = vec![None; entries.len()];
+
+    for batch in entries.chunks(RING_ENTRIES as usize) {
+        /* The buffers and paths the entries point to outlive this function, so pushing the
+         * entries is safe. Nothing is pushed if the submission queue is full, and every entry
+         * pushed before this batch has completed */
+        match unsafe { ring.submission().push_multiple(batch) } {
+            Ok(_) => (),
+            Err(_) => return Err(results),
+        }
+        let mut completed = 0;
+        while completed < batch.len() {
+            match ring.submit_and_wait(batch.len() - completed) {
+                Ok(_) => (),
+                Err(e) if e.kind() == ErrorKind::Interrupted => (),
+                Err(_) => return Err(results),
+            }
+            for cqe in ring.completion() {
+                results[cqe.user_data() as usize] = Some(cqe.result());
+                completed += 1;
+            }
+        }
+    }
+
+    return Ok(results.into_iter().flatten().collect());
+    /* }}} */
+}
+
+
+/// Compares the contents of several pairs of regular files at once using io_uring. The files of
+/// every pair are opened with one batch of submissions and read in their entirety with a second
+/// one, so the number of system calls no longer grows with the number of files. This is meant for
+/// small files, where the cost of a comparison is dominated by system call latency; every file is
+/// read into memory in one go.
+///
+/// #### Parameters:
+/// * `pairs` the pairs of regular files to compare, alongside the size both files of the pair are
+///     expected to have.
+/// #### Return:
+/// * `None` if io_uring is not available on this thread (or stops being usable partway through),
+///     in which case the caller should compare the files some other way. Otherwise, the result of
+///     every comparison in the same order as `pairs`. A comparison that could not be completed
+///     with io_uring (e.g. because a file changed size since its size was read) is `None`, and
+///     should also be done some other way.
+pub fn compare_regular_file_pairs(pairs: &[(&Path, &Path, u64)]) ->
+    Option<Vec<Option<Result<FileCmp, ()>>>> {
+    /* {{{ */
+    return RING.with(|ring_cell| {
+        let mut ring_ref = ring_cell.borrow_mut();
+        let ring = match ring_ref.as_mut() {
+            Some(ring) => ring,
+            None => return None,
+        };
+        let mut ret: Vec<Option<Result<FileCmp, ()>>> = Vec::new();
+        ret.resize_with(pairs.len(), || None);
+
+        /* 1. Open both files of every pair */
+        let mut paths: Vec<CString> = Vec::new();
+        for (first_path, second_path, _) in pairs {
+            for path in [first_path, second_path] {
+                match CString::new(path.as_os_str().as_bytes()) {
+                    Ok(p) => paths.push(p),
+                    Err(_) => return None,
+                }
+            }
+        }
+        let open_entries: Vec<squeue::Entry> = paths.iter().enumerate().map(|(i, path)| {
+            opcode::OpenAt::new(types::Fd(libc::AT_FDCWD), path.as_ptr())
+                .flags(libc::O_RDONLY | libc::O_CLOEXEC)
+                .build()
+                .user_data(i as u64)
+        }).collect();
+        /* Wrap every file descriptor in a `File` so that it gets closed no matter what */
+        let to_file = |fd: i32| {
+            if fd >= 0 {
+                return Some(unsafe { File::from_raw_fd(fd) });
+            }
+            return None;
+        };
+        let files: Vec<Option<File>> = match submit_all(ring, &open_entries) {
+            Ok(results) => results.into_iter().map(to_file).collect(),
+            /* Close the files that were opened, and keep the paths of the files that may still be
+             * being opened alive for good. The ring is dropped so that it never submits them */
+            Err(results) => {
+                for fd in results.into_iter().flatten() {
+                    drop(to_file(fd));
+                }
+                std::mem::forget(paths);
+                *ring_ref = None;
+                return None;
+            },
+        };
+
+        /* 2. Read both files of every pair that could be opened. One byte more than the expected
+         * size is requested so that files that grew in the meantime can be detected */
+        let mut buffers: Vec<Vec<u8>> = Vec::new();
+        let mut read_entries: Vec<squeue::Entry> = Vec::new();
+        let mut read_pairs: Vec<usize> = Vec::new();
+        for (i, (_, _, size)) in pairs.iter().enumerate() {
+            match (&files[2 * i], &files[2 * i + 1]) {
+                (Some(first_file), Some(second_file)) => {
+                    let len = (*size + 1) as usize;
+                    for file in [first_file, second_file] {
+                        let mut buf: Vec<u8> = vec![0; len];
+                        read_entries.push(
+                            opcode::Read::new(types::Fd(file.as_raw_fd()), buf.as_mut_ptr(),
+                                len as u32)
+                                .build()
+                                .user_data(read_entries.len() as u64)
+                        );
+                        buffers.push(buf);
+                    }
+                    read_pairs.push(i);
+                },
+                _ => ret[i] = Some(Err(())),
+            }
+        }
+        let read_results = match submit_all(ring, &read_entries) {
+            Ok(results) => results,
+            /* Keep the buffers that may still be being read into alive for good. The ring is
+             * dropped so that it never submits any more reads into them */
+            Err(_) => {
+                std::mem::forget(buffers);
+                *ring_ref = None;
+                return None;
+            },
+        };
+
+        /* 3. Compare what was read */
+        for (j, &i) in read_pairs.iter().enumerate() {
+            let size = pairs[i].2 as i32;
+            let (first_read, second_read) = (read_results[2 * j], read_results[2 * j + 1]);
+            if first_read < 0 || second_read < 0 {
+                ret[i] = Some(Err(()));
+            /* If a file grew or shrank, or a read came back short, the files weren't read in their
+             * entirety */
+            } else if first_read != size || second_read != size {
+                ret[i] = None;
+            } else {
+                let len = size as usize;
+                if buffers[2 * j][..len] == buffers[2 * j + 1][..len] {
+                    ret[i] = Some(Ok(FileCmp::Match));
+                } else {
+                    ret[i] = Some(Ok(FileCmp::SubstanceRegFileContentMismatch));
+                }
+            }
+        }
+
+        return Some(ret);
+    });
+    /* }}} */
+}
@@ -0,0 +1,452 @@
+use std::collections::BTreeMap;
+use std::fs::Metadata;
+use std::os::unix::fs::MetadataExt; // For getting the device and inode numbers of directories
+use std::path::{Path, PathBuf};
+
+
+// Use statements to get rid of the `config::` prefix
+use crate::config::{Config,SymlinkMode};
+
+
+/* A struct representing a directory that has been discovered while walking a directory tree, but
+ * whose contents have not been listed yet */
+#[derive(Clone)]
+pub struct PendingDirectory {
+    /* Which of the directory trees being walked this directory belongs to. Typically 0 for the
+     * first directory tree and 1 for the second directory tree. */
+    pub tree: usize,
+    /* The path of the directory relative to the root of its directory tree */
+    pub rel_path: PathBuf,
+    /* The (device, inode) pairs of every directory between the root of the directory tree and
+     * this directory (inclusive). Only used, and only kept up to date, when following soft links.
+     * */
+    pub ancestors: Vec<(u64, u64)>,
+}
+
+
+/* A struct representing a file (in the broad sense) that has been discovered while walking a
+ * directory tree */
+pub struct DiscoveredFile {
+    /* The path of the file relative to the root of its directory tree */
+    pub rel_path: PathBuf,
+    /* A rough estimate of how expensive it will be to compare this file to its corresponding file.
+     * Only meaningful relative to other estimates. */
+    pub estimated_cost: u64,
+    /* The metadata of the file itself (soft links are not followed), as read while listing its
+     * directory. `None` if it could not be read. */
+    pub metadata: Option<Metadata>,
+}
+
+
+/* A struct representing a file (in the broad sense) that has been discovered while walking several
+ * directory trees at once. The file may exist in any number of the directory trees. */
+pub struct MergedFile {
+    /* The path of the file relative to the roots of the directory trees */
+    pub rel_path: PathBuf,
+    /* The largest estimated cost of all the corresponding files */
+    pub estimated_cost: u64,
+    /* The metadata of the corresponding file in each directory tree (soft links are not followed),
+     * indexed by `PendingDirectory.tree`. `None` if the file does not exist in that directory
+     * tree (or its metadata could not be read). */
+    pub metadata: Vec<Option<Metadata>>,
+}
+
+
+/// Returns a `PendingDirectory` representing the root directory of a directory tree, ready to be
+/// listed by `list_directory()`.
+///
+/// #### Parameters:
+/// * `config` a `Config` representing a configuration for executing `cmp-tree`, usually modified
+///     through command line arguments to the program.
+/// * `tree` which of the directory trees being walked `root` is the root of.
+/// * `root` the file path to the root directory of the directory tree.
+/// #### Return:
+/// * a `PendingDirectory` representing the root directory.
+pub fn root_directory(config: &Config, tree: usize, root: &Path) -> PendingDirectory {
+    /* {{{ */
+    let mut ancestors: Vec<(u64, u64)> = Vec::new();
+    /* When following soft links, the root itself is the first directory a soft link could loop
+     * back to */
+    if config.symlink_mode != SymlinkMode::Compare {
+        if let Ok(md) = root.metadata() {
+            ancestors.push((md.dev(), md.ino()));
+        }
+    }
+
+    return PendingDirectory {
+        tree: tree,
+        rel_path: PathBuf::new(),
+        ancestors: ancestors,
+    };
+    /* }}} */
+}
+
+
+/// Lists the contents of the directory represented by `dir`. Every file (in the broad sense of the
+/// word, including links and directories, as well as hidden files) in the directory is added to
+/// `discovered_files`, and every subdirectory that needs to be descended into is added to
+/// `pending_dirs`. This function does not recurse; walking an entire directory tree is done by
+/// repeatedly calling this function on the directories added to `pending_dirs`, which is what
+/// allows several threads to walk the same directory tree at once.
+///
+/// If `config` says soft links are to be followed, soft links that resolve to directories are
+/// descended into as if they were directories themselves. To avoid looping forever on soft links
+/// that point back up the directory tree, a soft link is never descended into if it resolves to
+/// one of the ancestors of `dir`.
+///
+/// #### Parameters:
+/// * `config` a `Config` representing a configuration for executing `cmp-tree`, usually modified
+///     through command line arguments to the program.
+/// * `root` the file path to the root directory of the directory tree `dir` belongs to.
+/// * `dir` the directory whose contents we wish to list.
+/// * `discovered_files` the list every file in the directory will be added to. The relative paths
+///     of the files will omit `root`.
+/// * `pending_dirs` the list every subdirectory that needs to be descended into will be added to.
+pub fn list_directory(config: &Config, root: &Path, dir: &PendingDirectory,
+    discovered_files: &mut Vec<DiscoveredFile>, pending_dirs: &mut Vec<PendingDirectory>) {
+    /* {{{ */
+    let full_dir_path = root.join(&dir.rel_path);
+
+    /* Get all the files in the dir relative to the 'root' directory */
+    match std::fs::read_dir(full_dir_path) {
+        Ok(dir_entries) => {
+            for e in dir_entries {
+                match e {
+                    Ok(entry) => {
+                        if let Ok(file_type) = entry.file_type() {
+                            let rel_path: PathBuf = dir.rel_path.join(entry.file_name());
+                            /* `DirEntry.metadata()` does not follow soft links and is the only
+                             * time the file is stat'ed. The metadata is handed over to the file
+                             * comparison so that it doesn't have to stat the file again */
+                            let metadata = match entry.metadata() {
+                                Ok(md) => Some(md),
+                                Err(_) => None,
+                            };
+
+                            /* Only regular files are expensive to compare, and a regular file is
+                             * as expensive to compare as it is big */
+                            let mut estimated_cost: u64 = 0;
+                            if file_type.is_file() {
+                                if let Some(md) = &metadata {
+                                    estimated_cost = md.len();
+                                }
+                            }
+
+                            if file_type.is_dir() && config.symlink_mode == SymlinkMode::Compare {
+                                pending_dirs.push(PendingDirectory {
+                                    tree: dir.tree,
+                                    rel_path: rel_path.clone(),
+                                    ancestors: Vec::new(),
+                                });
+                            } else if config.symlink_mode != SymlinkMode::Compare
+                                && (file_type.is_dir() || file_type.is_symlink()) {
+                                /* If we make it here, we are following soft links. Directories
+                                 * are their own targets, so only soft links need another stat.
+                                 * Dangling soft links have nothing to descend into, so skip them
+                                 * */
+                                let target_md_res = match (file_type.is_dir(), &metadata) {
+                                    (true, Some(md)) => Ok(md.clone()),
+                                    _ => entry.path().metadata(),
+                                };
+                                if let Ok(target_md) = target_md_res {
+                                    let id = (target_md.dev(), target_md.ino());
+                                    /* A soft link that resolves to a regular file is as expensive
+                                     * to compare as that regular file */
+                                    if target_md.is_file() {
+                                        estimated_cost = target_md.len();
+                                    /* Only descend into the directory if it isn't one of the
+                                     * directories we are already in the middle of walking.
+                                     * Otherwise we would loop forever */
+                                    } else if target_md.is_dir() && !dir.ancestors.contains(&id) {
+                                        let mut ancestors = dir.ancestors.clone();
+                                        ancestors.push(id);
+                                        pending_dirs.push(PendingDirectory {
+                                            tree: dir.tree,
+                                            rel_path: rel_path.clone(),
+                                            ancestors: ancestors,
+                                        });
+                                    }
+                                }
+                            }
+
+                            discovered_files.push(DiscoveredFile {
+                                rel_path: rel_path,
+                                estimated_cost: estimated_cost,
+                                metadata: metadata,
+                            });
+                        } else {
+                            println!("Error getting the file type of the directory
+                                entry");
+                        }
+                    },
+                    Err(_) => {
+                        println!("Error reading one of the directory entries");
+                    }
+                }
+            }
+        },
+        Err(_) => {
+            println!("Error reading contents of the directory");
+        }
+    }
+    /* }}} */
+}
+
+
+/// Returns an unsorted vector list of relative file paths for all the files (in the broad sense of
+/// the word, including links and directories, as well as hidden files) in a directory tree rooted
+/// at the directory pointed to by `root`.
+///
+/// #### Parameters:
+/// * `config` a `Config` representing a configuration for executing `cmp-tree`, usually modified
+///     through command line arguments to the program.
+/// * `root` the file path to the directory for which we wish to get a list of all the files in the
+///     directory tree.
+/// #### Return:
+/// * an unsorted vector list of the relative file paths for all the files in the directory tree
+///     rooted at `root`.
+pub fn files_in_tree(config: &Config, root: &Path) -> Vec<PathBuf> {
+    /* {{{ */
+    let mut discovered_files: Vec<DiscoveredFile> = Vec::new();
+    let mut pending_dirs: Vec<PendingDirectory> = Vec::from([root_directory(config, 0, root)]);
+
+    while let Some(dir) = pending_dirs.pop() {
+        list_directory(config, root, &dir, &mut discovered_files, &mut pending_dirs);
+    }
+
+    return discovered_files.into_iter().map(|f| f.rel_path).collect();
+    /* }}} */
+}
+
+
+/* A trait for anything that can list the contents of directories for a `MergedTreeWalk`. Listing
+ * is separated from walking so that directories can be listed ahead of time, possibly by other
+ * threads, while the walk itself proceeds in order. */
+pub trait DirectoryLister {
+    /// Lists the contents of the directory represented by `dir` the same way `list_directory()`
+    /// does, adding every file in the directory to `discovered_files` and every subdirectory that
+    /// needs to be descended into to `pending_dirs`.
+    fn list(&self, dir: &PendingDirectory, discovered_files: &mut Vec<DiscoveredFile>,
+        pending_dirs: &mut Vec<PendingDirectory>);
+
+    /// Lets the lister know that `dir` will be listed soon, giving it the chance to list it ahead
+    /// of time. By default, this does nothing.
+    fn prefetch(&self, _dir: &PendingDirectory) {}
+}
+
+
+/* A `DirectoryLister` that simply lists directories as they are needed, on the calling thread */
+pub struct SequentialLister<'a> {
+    pub config: &'a Config,
+    /* The file paths to the root directories of the directory trees being walked, indexed by
+     * `PendingDirectory.tree` */
+    pub roots: &'a [&'a Path],
+}
+
+
+impl DirectoryLister for SequentialLister<'_> {
+    /* {{{ */
+    fn list(&self, dir: &PendingDirectory, discovered_files: &mut Vec<DiscoveredFile>,
+        pending_dirs: &mut Vec<PendingDirectory>) {
+        /* {{{ */
+
+        list_directory(self.config, self.roots[dir.tree], dir, discovered_files, pending_dirs);
+        /* }}} */
+    }
+    /* }}} */
+}
+
+
+/* A struct representing one entry of a directory in a merged walk of several directory trees. The
+ * file it represents may exist in any number of the directory trees. */
+struct MergedEntry {
+    file: MergedFile,
+    /* The corresponding directories that need to be descended into, at most one per directory
+     * tree */
+    subdirs: Vec<PendingDirectory>,
+}
+
+
+/* A struct representing a directory that a `MergedTreeWalk` is in the middle of walking */
+struct MergedFrame {
+    /* The entries of the directory that have not been visited yet, in reverse sorted order so that
+     * the next entry can be popped off the end */
+    entries: Vec<MergedEntry>,
+    /* Every entry at an index greater than or equal to this one has already been considered for
+     * prefetching */
+    prefetch_cursor: usize,
+    /* The number of entries that have been prefetched, but not visited yet */
+    prefetched: usize,
+}
+
+
+/* The number of subdirectories of a given directory that `MergedTreeWalk` will ask its lister to
+ * list ahead of time */
+const PREFETCH_COUNT: usize = 4;
+
+
+/* An `Iterator` that walks several directory trees at once, yielding every file (in the broad
+ * sense) that exists in any of the directory trees exactly once, in sorted order.
+ * The directory trees are walked one directory at a time: the contents of corresponding
+ * directories are listed, merged and sorted, and every subdirectory is walked right after it is
+ * yielded. Unlike collecting and sorting every relative path of the directory trees, this means
+ * memory use only grows with the number of entries in a directory (times the depth of the
+ * directory trees), not with the number of files in the directory trees.
+ *
+ * The sorted order is the same one a sorted `Vec<PathBuf>` has: a directory is immediately
+ * followed by its contents, and sibling files are sorted by name. */
+pub struct MergedTreeWalk<'a, L: DirectoryLister> {
+    lister: &'a L,
+    /* The number of directory trees being walked */
+    tree_count: usize,
+    /* The directories the walk is in the middle of walking, from the roots down */
+    stack: Vec<MergedFrame>,
+}
+
+
+impl<'a, L: DirectoryLister> MergedTreeWalk<'a, L> {
+    /* {{{ */
+    /// Creates a `MergedTreeWalk` that walks the directory trees rooted at `roots`.
+    ///
+    /// #### Parameters:
+    /// * `lister` the `DirectoryLister` that will be used to list the contents of directories.
+    /// * `roots` the root directories of the directory trees to walk, typically created through
+    ///     `root_directory()`.
+    /// #### Return:
+    /// * a `MergedTreeWalk` that has not yielded anything yet.
+    pub fn new(lister: &'a L, roots: Vec<PendingDirectory>) -> MergedTreeWalk<'a, L> {
+        /* {{{ */
+        let mut ret = MergedTreeWalk {
+            lister: lister,
+            tree_count: roots.len(),
+            stack: Vec::new(),
+        };
+        ret.descend(roots);
+        return ret;
+        /* }}} */
+    }
+
+    /// Lists the contents of the corresponding directories in `dirs`, merges them and pushes the
+    /// result on top of the stack so that the walk continues inside the directories.
+    ///
+    /// #### Parameters:
+    /// * `dirs` the corresponding directories to descend into, at most one per directory tree.
+    fn descend(&mut self, dirs: Vec<PendingDirectory>) {
+        /* {{{ */
+        /* Let the lister get started on all but the first directory while we list the first one
+         * ourselves, so the directory trees can be listed at the same time */
+        for dir in dirs.iter().skip(1) {
+            self.lister.prefetch(dir);
+        }
+
+        /* The relative paths of the contents of corresponding directories are identical, so
+         * merging the contents is just a matter of merging entries with identical paths. A
+         * `BTreeMap` takes care of the sorting for us. */
+        let mut merged: BTreeMap<PathBuf, MergedEntry> = BTreeMap::new();
+        let mut discovered_files: Vec<DiscoveredFile> = Vec::new();
+        let mut discovered_dirs: Vec<PendingDirectory> = Vec::new();
+        for dir in &dirs {
+            self.lister.list(dir, &mut discovered_files, &mut discovered_dirs);
+            for f in discovered_files.drain(..) {
+                match merged.get_mut(&f.rel_path) {
+                    Some(entry) => {
+                        entry.file.estimated_cost =
+                            std::cmp::max(entry.file.estimated_cost, f.estimated_cost);
+                        entry.file.metadata[dir.tree] = f.metadata;
+                    },
+                    None => {
+                        let mut metadata: Vec<Option<Metadata>> = vec![None; self.tree_count];
+                        metadata[dir.tree] = f.metadata;
+                        merged.insert(f.rel_path.clone(), MergedEntry {
+                            file: MergedFile {
+                                rel_path: f.rel_path,
+                                estimated_cost: f.estimated_cost,
+                                metadata: metadata,
+                            },
+                            subdirs: Vec::new(),
+                        });
+                    },
+                }
+            }
+        }
+        for d in discovered_dirs {
+            if let Some(entry) = merged.get_mut(&d.rel_path) {
+                entry.subdirs.push(d);
+            }
+        }
+
+        let entries: Vec<MergedEntry> = merged.into_values().rev().collect();
+        let mut frame = MergedFrame {
+            prefetch_cursor: entries.len(),
+            entries: entries,
+            prefetched: 0,
+        };
+        self.prefetch_ahead(&mut frame);
+        self.stack.push(frame);
+        /* }}} */
+    }
+
+    /// Asks the lister to list the next few subdirectories of `frame` ahead of time, so that they
+    /// are (hopefully) already listed by the time the walk reaches them.
+    ///
+    /// #### Parameters:
+    /// * `frame` the directory whose subdirectories we want listed ahead of time.
+    fn prefetch_ahead(&self, frame: &mut MergedFrame) {
+        /* {{{ */
+        while frame.prefetched < PREFETCH_COUNT && frame.prefetch_cursor > 0 {
+            frame.prefetch_cursor -= 1;
+            let entry = &frame.entries[frame.prefetch_cursor];
+            if !entry.subdirs.is_empty() {
+                for dir in &entry.subdirs {
+                    self.lister.prefetch(dir);
+                }
+                frame.prefetched += 1;
+            }
+        }
+        /* }}} */
+    }
+    /* }}} */
+}
+
+
+impl<L: DirectoryLister> Iterator for MergedTreeWalk<'_, L> {
+    /* {{{ */
+    type Item = MergedFile;
+
+    fn next(&mut self) -> Option<MergedFile> {
+        /* {{{ */
+        /* Once every directory has been left, the walk is over */
+        while let Some(frame) = self.stack.last_mut() {
+            let entry = match frame.entries.pop() {
+                Some(entry) => entry,
+                /* If we have visited every entry of the directory, continue with its parent */
+                None => {
+                    self.stack.pop();
+                    continue;
+                },
+            };
+
+            /* Keep the prefetching bookkeeping up to date now that the entry has been visited */
+            if frame.prefetch_cursor <= frame.entries.len() {
+                if !entry.subdirs.is_empty() {
+                    frame.prefetched -= 1;
+                }
+            } else {
+                frame.prefetch_cursor = frame.entries.len();
+            }
+
+            let ret = entry.file;
+            /* A directory is immediately followed by its contents */
+            if !entry.subdirs.is_empty() {
+                let mut frame = self.stack.pop().unwrap();
+                self.prefetch_ahead(&mut frame);
+                self.stack.push(frame);
+                self.descend(entry.subdirs);
+            }
+            return Some(ret);
+        }
+        return None;
+        /* }}} */
+    }
+    /* }}} */
+}
@@ -33,7 +33,7 @@ mod integration_tests {
         let first_dir = Path::new("../../tests/007/first");
         let second_dir = Path::new("../../tests/007/second");
 
-        let exit_code = cmp_tree::cmp_tree(&conf, &first_dir, &second_dir);
+        let exit_code = cmp_tree::cmp_tree(&conf, first_dir, second_dir);
         assert_eq!(exit_code, 1);
         /* }}} */
     }
@@ -45,7 +45,7 @@ mod integration_tests {
         let first_dir = Path::new("../../tests/015/first");
         let second_dir = Path::new("../../tests/015/second");
 
-        let exit_code = cmp_tree::cmp_tree(&conf, &first_dir, &second_dir);
+        let exit_code = cmp_tree::cmp_tree(&conf, first_dir, second_dir);
         assert_eq!(exit_code, 1);
         /* }}} */
     }
@@ -57,11 +57,23 @@ mod integration_tests {
         let first_dir = Path::new("../../tests/016/first");
         let second_dir = Path::new("../../tests/016/second");
 
-        let exit_code = cmp_tree::cmp_tree(&conf, &first_dir, &second_dir);
+        let exit_code = cmp_tree::cmp_tree(&conf, first_dir, second_dir);
         assert_eq!(exit_code, 1);
         /* }}} */
     }
 
+    #[test]
+    fn it_file_type_003_identical_1l_fifos() {
+        /* {{{ */
+        let conf = cmp_tree::default_config();
+        let first_dir = Path::new("../../tests/038/first");
+        let second_dir = Path::new("../../tests/038/second");
+
+        let exit_code = cmp_tree::cmp_tree(&conf, first_dir, second_dir);
+        assert_eq!(exit_code, 0);
+        /* }}} */
+    }
+
     #[test]
     fn it_substance_001_identical_1l_multiple_regular_files() {
         /* {{{ */
@@ -69,7 +81,7 @@ mod integration_tests {
         let first_dir = Path::new("../../tests/001/first");
         let second_dir = Path::new("../../tests/001/second");
 
-        let exit_code = cmp_tree::cmp_tree(&conf, &first_dir, &second_dir);
+        let exit_code = cmp_tree::cmp_tree(&conf, first_dir, second_dir);
         assert_eq!(exit_code, 0);
         /* }}} */
     }
@@ -81,7 +93,7 @@ mod integration_tests {
         let first_dir = Path::new("../../tests/002/first");
         let second_dir = Path::new("../../tests/002/second");
 
-        let exit_code = cmp_tree::cmp_tree(&conf, &first_dir, &second_dir);
+        let exit_code = cmp_tree::cmp_tree(&conf, first_dir, second_dir);
         assert_eq!(exit_code, 1);
         /* }}} */
     }
@@ -93,7 +105,7 @@ mod integration_tests {
         let first_dir = Path::new("../../tests/003/first");
         let second_dir = Path::new("../../tests/003/second");
 
-        let exit_code = cmp_tree::cmp_tree(&conf, &first_dir, &second_dir);
+        let exit_code = cmp_tree::cmp_tree(&conf, first_dir, second_dir);
         assert_eq!(exit_code, 0);
         /* }}} */
     }
@@ -105,7 +117,7 @@ mod integration_tests {
         let first_dir = Path::new("../../tests/004/first");
         let second_dir = Path::new("../../tests/004/second");
 
-        let exit_code = cmp_tree::cmp_tree(&conf, &first_dir, &second_dir);
+        let exit_code = cmp_tree::cmp_tree(&conf, first_dir, second_dir);
         assert_eq!(exit_code, 1);
         /* }}} */
     }
@@ -117,7 +129,7 @@ mod integration_tests {
         let first_dir = Path::new("../../tests/005/first");
         let second_dir = Path::new("../../tests/005/second");
 
-        let exit_code = cmp_tree::cmp_tree(&conf, &first_dir, &second_dir);
+        let exit_code = cmp_tree::cmp_tree(&conf, first_dir, second_dir);
         assert_eq!(exit_code, 1);
         /* }}} */
     }
@@ -129,7 +141,7 @@ mod integration_tests {
         let first_dir = Path::new("../../tests/006/first");
         let second_dir = Path::new("../../tests/006/second");
 
-        let exit_code = cmp_tree::cmp_tree(&conf, &first_dir, &second_dir);
+        let exit_code = cmp_tree::cmp_tree(&conf, first_dir, second_dir);
         assert_eq!(exit_code, 0);
         /* }}} */
     }
@@ -141,7 +153,7 @@ mod integration_tests {
         let first_dir = Path::new("../../tests/008/first");
         let second_dir = Path::new("../../tests/008/second");
 
-        let exit_code = cmp_tree::cmp_tree(&conf, &first_dir, &second_dir);
+        let exit_code = cmp_tree::cmp_tree(&conf, first_dir, second_dir);
         assert_eq!(exit_code, 0);
         /* }}} */
     }
@@ -153,7 +165,7 @@ mod integration_tests {
         let first_dir = Path::new("../../tests/009/first");
         let second_dir = Path::new("../../tests/009/second");
 
-        let exit_code = cmp_tree::cmp_tree(&conf, &first_dir, &second_dir);
+        let exit_code = cmp_tree::cmp_tree(&conf, first_dir, second_dir);
         assert_eq!(exit_code, 1);
         /* }}} */
     }
@@ -165,7 +177,7 @@ mod integration_tests {
         let first_dir = Path::new("../../tests/010/first");
         let second_dir = Path::new("../../tests/010/second");
 
-        let exit_code = cmp_tree::cmp_tree(&conf, &first_dir, &second_dir);
+        let exit_code = cmp_tree::cmp_tree(&conf, first_dir, second_dir);
         assert_eq!(exit_code, 1);
         /* }}} */
     }
@@ -177,7 +189,7 @@ mod integration_tests {
         let first_dir = Path::new("../../tests/013/first");
         let second_dir = Path::new("../../tests/013/second");
 
-        let exit_code = cmp_tree::cmp_tree(&conf, &first_dir, &second_dir);
+        let exit_code = cmp_tree::cmp_tree(&conf, first_dir, second_dir);
         assert_eq!(exit_code, 1);
         /* }}} */
     }
@@ -189,7 +201,7 @@ mod integration_tests {
         let first_dir = Path::new("../../tests/017/first");
         let second_dir = Path::new("../../tests/017/second");
 
-        let exit_code = cmp_tree::cmp_tree(&conf, &first_dir, &second_dir);
+        let exit_code = cmp_tree::cmp_tree(&conf, first_dir, second_dir);
         assert_eq!(exit_code, 0);
         /* }}} */
     }
@@ -201,7 +213,7 @@ mod integration_tests {
         let first_dir = Path::new("../../tests/018/first");
         let second_dir = Path::new("../../tests/018/second");
 
-        let exit_code = cmp_tree::cmp_tree(&conf, &first_dir, &second_dir);
+        let exit_code = cmp_tree::cmp_tree(&conf, first_dir, second_dir);
         assert_eq!(exit_code, 1);
         /* }}} */
     }
@@ -213,7 +225,7 @@ mod integration_tests {
         let first_dir = Path::new("../../tests/019/first");
         let second_dir = Path::new("../../tests/019/second");
 
-        let exit_code = cmp_tree::cmp_tree(&conf, &first_dir, &second_dir);
+        let exit_code = cmp_tree::cmp_tree(&conf, first_dir, second_dir);
         assert_eq!(exit_code, 1);
         /* }}} */
     }
@@ -225,7 +237,7 @@ mod integration_tests {
         let first_dir = Path::new("../../tests/020/first");
         let second_dir = Path::new("../../tests/020/second");
 
-        let exit_code = cmp_tree::cmp_tree(&conf, &first_dir, &second_dir);
+        let exit_code = cmp_tree::cmp_tree(&conf, first_dir, second_dir);
         assert_eq!(exit_code, 1);
         /* }}} */
     }
@@ -240,7 +252,7 @@ mod integration_tests {
         let first_dir = Path::new("../../tests/001/first");
         let second_dir = Path::new("../../tests/001/second");
 
-        let exit_code = cmp_tree::cmp_tree(&conf, &first_dir, &second_dir);
+        let exit_code = cmp_tree::cmp_tree(&conf, first_dir, second_dir);
         assert_eq!(exit_code, 0);
         /* }}} */
     }
@@ -255,7 +267,7 @@ mod integration_tests {
         let first_dir = Path::new("../../tests/003/first");
         let second_dir = Path::new("../../tests/003/second");
 
-        let exit_code = cmp_tree::cmp_tree(&conf, &first_dir, &second_dir);
+        let exit_code = cmp_tree::cmp_tree(&conf, first_dir, second_dir);
         assert_eq!(exit_code, 0);
         /* }}} */
     }
@@ -270,7 +282,7 @@ mod integration_tests {
         let first_dir = Path::new("../../tests/008/first");
         let second_dir = Path::new("../../tests/008/second");
 
-        let exit_code = cmp_tree::cmp_tree(&conf, &first_dir, &second_dir);
+        let exit_code = cmp_tree::cmp_tree(&conf, first_dir, second_dir);
         assert_eq!(exit_code, 1);
         /* }}} */
     }
@@ -285,7 +297,7 @@ mod integration_tests {
         let first_dir = Path::new("../../tests/011/first");
         let second_dir = Path::new("../../tests/011/second");
 
-        let exit_code = cmp_tree::cmp_tree(&conf, &first_dir, &second_dir);
+        let exit_code = cmp_tree::cmp_tree(&conf, first_dir, second_dir);
         assert_eq!(exit_code, 1);
         /* }}} */
     }
@@ -300,7 +312,7 @@ mod integration_tests {
         let first_dir = Path::new("../../tests/012/first");
         let second_dir = Path::new("../../tests/012/second");
 
-        let exit_code = cmp_tree::cmp_tree(&conf, &first_dir, &second_dir);
+        let exit_code = cmp_tree::cmp_tree(&conf, first_dir, second_dir);
         assert_eq!(exit_code, 1);
         /* }}} */
     }
@@ -312,7 +324,669 @@ mod integration_tests {
         let first_dir = Path::new("../../tests/014/first");
         let second_dir = Path::new("../../tests/014/second");
 
-        let exit_code = cmp_tree::cmp_tree(&conf, &first_dir, &second_dir);
+        let exit_code = cmp_tree::cmp_tree(&conf, first_dir, second_dir);
+        assert_eq!(exit_code, 0);
+        /* }}} */
+    }
+
+    #[test]
+    fn it_symlinks_001_identical_2l_following_differing_soft_links_to_identical_directories() {
+        /* {{{ */
+        let mut conf = cmp_tree::default_config();
+        // By default, `cmp-tree` compares soft links by their link paths. Follow them instead.
+        conf.symlink_mode = cmp_tree::SymlinkMode::Follow;
+        let first_dir = Path::new("../../tests/010/first");
+        let second_dir = Path::new("../../tests/010/second");
+
+        let exit_code = cmp_tree::cmp_tree(&conf, first_dir, second_dir);
+        assert_eq!(exit_code, 0);
+        /* }}} */
+    }
+
+    #[test]
+    fn it_symlinks_002_differing_2l_following_differing_soft_links_to_differing_directories() {
+        /* {{{ */
+        let mut conf = cmp_tree::default_config();
+        // By default, `cmp-tree` compares soft links by their link paths. Follow them instead.
+        conf.symlink_mode = cmp_tree::SymlinkMode::Follow;
+        let first_dir = Path::new("../../tests/009/first");
+        let second_dir = Path::new("../../tests/009/second");
+
+        let exit_code = cmp_tree::cmp_tree(&conf, first_dir, second_dir);
+        assert_eq!(exit_code, 1);
+        /* }}} */
+    }
+
+    #[test]
+    fn it_symlinks_003_differing_2l_strict_differing_soft_links_to_identical_directories() {
+        /* {{{ */
+        let mut conf = cmp_tree::default_config();
+        // Follow soft links, but also require their link paths to be identical.
+        conf.symlink_mode = cmp_tree::SymlinkMode::FollowAndCompare;
+        let first_dir = Path::new("../../tests/010/first");
+        let second_dir = Path::new("../../tests/010/second");
+
+        let exit_code = cmp_tree::cmp_tree(&conf, first_dir, second_dir);
+        assert_eq!(exit_code, 1);
+        /* }}} */
+    }
+
+    #[test]
+    fn it_symlinks_004_identical_2l_following_soft_link_cycles_and_dangling_soft_links() {
+        /* {{{ */
+        let mut conf = cmp_tree::default_config();
+        // By default, `cmp-tree` compares soft links by their link paths. Follow them instead.
+        conf.symlink_mode = cmp_tree::SymlinkMode::Follow;
+        let first_dir = Path::new("../../tests/021/first");
+        let second_dir = Path::new("../../tests/021/second");
+
+        let exit_code = cmp_tree::cmp_tree(&conf, first_dir, second_dir);
+        assert_eq!(exit_code, 0);
+        /* }}} */
+    }
+
+    #[test]
+    fn it_symlinks_005_differing_1l_following_soft_link_vs_dangling_soft_link() {
+        /* {{{ */
+        let mut conf = cmp_tree::default_config();
+        // By default, `cmp-tree` compares soft links by their link paths. Follow them instead.
+        conf.symlink_mode = cmp_tree::SymlinkMode::Follow;
+        let first_dir = Path::new("../../tests/022/first");
+        let second_dir = Path::new("../../tests/022/second");
+
+        let exit_code = cmp_tree::cmp_tree(&conf, first_dir, second_dir);
+        assert_eq!(exit_code, 1);
+        /* }}} */
+    }
+
+    #[test]
+    fn it_symlinks_006_differing_2l_differently_written_link_paths_to_the_same_places() {
+        /* {{{ */
+        let conf = cmp_tree::default_config();
+        let first_dir = Path::new("../../tests/023/first");
+        let second_dir = Path::new("../../tests/023/second");
+
+        let exit_code = cmp_tree::cmp_tree(&conf, first_dir, second_dir);
+        assert_eq!(exit_code, 1);
+        /* }}} */
+    }
+
+    #[test]
+    fn it_symlinks_007_identical_2l_normalized_differently_written_link_paths_to_the_same_places() {
+        /* {{{ */
+        let mut conf = cmp_tree::default_config();
+        // By default, `cmp-tree` compares link paths exactly as they are written. Normalize them.
+        conf.normalize_link_targets = true;
+        let first_dir = Path::new("../../tests/023/first");
+        let second_dir = Path::new("../../tests/023/second");
+
+        let exit_code = cmp_tree::cmp_tree(&conf, first_dir, second_dir);
+        assert_eq!(exit_code, 0);
+        /* }}} */
+    }
+
+    #[test]
+    fn it_jobs_001_differing_2l_two_threads_single_differing_regular_file() {
+        /* {{{ */
+        let mut conf = cmp_tree::default_config();
+        conf.jobs = Some(2);
+        let first_dir = Path::new("../../tests/004/first");
+        let second_dir = Path::new("../../tests/004/second");
+
+        let exit_code = cmp_tree::cmp_tree(&conf, first_dir, second_dir);
+        assert_eq!(exit_code, 1);
+        /* }}} */
+    }
+
+    #[test]
+    fn it_jobs_002_identical_1l_four_threads_fifos() {
+        /* {{{ */
+        /* The FIFOs cannot be compared, which must not keep the other threads waiting forever */
+        let mut conf = cmp_tree::default_config();
+        conf.jobs = Some(4);
+        let first_dir = Path::new("../../tests/038/first");
+        let second_dir = Path::new("../../tests/038/second");
+
+        let exit_code = cmp_tree::cmp_tree(&conf, first_dir, second_dir);
+        assert_eq!(exit_code, 0);
+        /* }}} */
+    }
+
+    #[test]
+    fn it_large_files_001_identical_1l_split_into_ranges() {
+        /* {{{ */
+        let mut conf = cmp_tree::default_config();
+        conf.large_file_threshold = Some(1);
+        conf.buffer_size = 4096;
+        let first_dir = Path::new("../../tests/001/first");
+        let second_dir = Path::new("../../tests/001/second");
+
+        let exit_code = cmp_tree::cmp_tree(&conf, first_dir, second_dir);
+        assert_eq!(exit_code, 0);
+        /* }}} */
+    }
+
+    #[test]
+    fn it_large_files_002_differing_1l_split_into_ranges_last_byte_differs() {
+        /* {{{ */
+        let mut conf = cmp_tree::default_config();
+        conf.large_file_threshold = Some(1 << 20);
+        conf.buffer_size = 1 << 16;
+        conf.jobs = Some(4);
+        let first_dir = Path::new("../../tests/024/first");
+        let second_dir = Path::new("../../tests/024/second");
+
+        let exit_code = cmp_tree::cmp_tree(&conf, first_dir, second_dir);
+        assert_eq!(exit_code, 1);
+        /* }}} */
+    }
+
+    #[test]
+    fn it_io_001_identical_1l_mmap() {
+        /* {{{ */
+        let mut conf = cmp_tree::default_config();
+        conf.io_mode = cmp_tree::IoMode::Mmap;
+        let first_dir = Path::new("../../tests/001/first");
+        let second_dir = Path::new("../../tests/001/second");
+
+        let exit_code = cmp_tree::cmp_tree(&conf, first_dir, second_dir);
+        assert_eq!(exit_code, 0);
+        /* }}} */
+    }
+
+    #[test]
+    fn it_io_002_differing_2l_mmap_single_differing_regular_file() {
+        /* {{{ */
+        let mut conf = cmp_tree::default_config();
+        conf.io_mode = cmp_tree::IoMode::Mmap;
+        let first_dir = Path::new("../../tests/004/first");
+        let second_dir = Path::new("../../tests/004/second");
+
+        let exit_code = cmp_tree::cmp_tree(&conf, first_dir, second_dir);
+        assert_eq!(exit_code, 1);
+        /* }}} */
+    }
+
+    #[test]
+    #[cfg(all(feature = "io-uring", target_os = "linux"))]
+    fn it_io_003_identical_2l_uring_multiple_regular_files() {
+        /* {{{ */
+        let mut conf = cmp_tree::default_config();
+        conf.io_mode = cmp_tree::IoMode::Uring;
+        let first_dir = Path::new("../../tests/003/first");
+        let second_dir = Path::new("../../tests/003/second");
+
+        let exit_code = cmp_tree::cmp_tree(&conf, first_dir, second_dir);
+        assert_eq!(exit_code, 0);
+        /* }}} */
+    }
+
+    #[test]
+    #[cfg(all(feature = "io-uring", target_os = "linux"))]
+    fn it_io_004_differing_2l_uring_single_differing_regular_file() {
+        /* {{{ */
+        let mut conf = cmp_tree::default_config();
+        conf.io_mode = cmp_tree::IoMode::Uring;
+        let first_dir = Path::new("../../tests/004/first");
+        let second_dir = Path::new("../../tests/004/second");
+
+        let exit_code = cmp_tree::cmp_tree(&conf, first_dir, second_dir);
+        assert_eq!(exit_code, 1);
+        /* }}} */
+    }
+
+    #[test]
+    fn it_disk_order_001_differing_2l_single_differing_regular_file() {
+        /* {{{ */
+        let mut conf = cmp_tree::default_config();
+        conf.disk_order = true;
+        let first_dir = Path::new("../../tests/004/first");
+        let second_dir = Path::new("../../tests/004/second");
+
+        let exit_code = cmp_tree::cmp_tree(&conf, first_dir, second_dir);
+        assert_eq!(exit_code, 1);
+        /* }}} */
+    }
+
+    #[test]
+    fn it_shared_extents_001_identical_1l_hard_linked_and_reflinked_files() {
+        /* {{{ */
+        let mut conf = cmp_tree::default_config();
+        conf.shared_extents = true;
+        let first_dir = Path::new("../../tests/025/first");
+        let second_dir = Path::new("../../tests/025/second");
+
+        let exit_code = cmp_tree::cmp_tree(&conf, first_dir, second_dir);
+        assert_eq!(exit_code, 0);
+        /* }}} */
+    }
+
+    #[test]
+    fn it_shared_extents_002_differing_2l_single_differing_regular_file() {
+        /* {{{ */
+        let mut conf = cmp_tree::default_config();
+        conf.shared_extents = true;
+        let first_dir = Path::new("../../tests/004/first");
+        let second_dir = Path::new("../../tests/004/second");
+
+        let exit_code = cmp_tree::cmp_tree(&conf, first_dir, second_dir);
+        assert_eq!(exit_code, 1);
+        /* }}} */
+    }
+
+    #[test]
+    fn it_quick_001_identical_1l() {
+        /* {{{ */
+        let mut conf = cmp_tree::default_config();
+        conf.quick = true;
+        let first_dir = Path::new("../../tests/001/first");
+        let second_dir = Path::new("../../tests/001/second");
+
+        let exit_code = cmp_tree::cmp_tree(&conf, first_dir, second_dir);
+        assert_eq!(exit_code, 0);
+        /* }}} */
+    }
+
+    #[test]
+    fn it_quick_002_differing_4l_only_directories_missing_multiple_subdirs() {
+        /* {{{ */
+        let mut conf = cmp_tree::default_config();
+        conf.quick = true;
+        let first_dir = Path::new("../../tests/007/first");
+        let second_dir = Path::new("../../tests/007/second");
+
+        let exit_code = cmp_tree::cmp_tree(&conf, first_dir, second_dir);
+        assert_eq!(exit_code, 1);
+        /* }}} */
+    }
+
+    #[test]
+    fn it_shallow_001_differing_1l_same_size_and_modification_time() {
+        /* {{{ */
+        let conf = cmp_tree::default_config();
+        let first_dir = Path::new("../../tests/026/first");
+        let second_dir = Path::new("../../tests/026/second");
+
+        let exit_code = cmp_tree::cmp_tree(&conf, first_dir, second_dir);
+        assert_eq!(exit_code, 1);
+        /* }}} */
+    }
+
+    #[test]
+    fn it_shallow_002_identical_1l_same_size_and_modification_time() {
+        /* {{{ */
+        let mut conf = cmp_tree::default_config();
+        conf.shallow = true;
+        let first_dir = Path::new("../../tests/026/first");
+        let second_dir = Path::new("../../tests/026/second");
+
+        let exit_code = cmp_tree::cmp_tree(&conf, first_dir, second_dir);
+        assert_eq!(exit_code, 0);
+        /* }}} */
+    }
+
+    #[test]
+    fn it_shallow_003_differing_1l_same_contents_different_modification_time() {
+        /* {{{ */
+        let mut conf = cmp_tree::default_config();
+        conf.shallow = true;
+        conf.compare_modification_times = true;
+        let first_dir = Path::new("../../tests/026/first");
+        let second_dir = Path::new("../../tests/026/second");
+
+        let exit_code = cmp_tree::cmp_tree(&conf, first_dir, second_dir);
+        assert_eq!(exit_code, 1);
+        /* }}} */
+    }
+
+    #[test]
+    fn it_sample_001_identical_1l() {
+        /* {{{ */
+        let mut conf = cmp_tree::default_config();
+        conf.sample_blocks = Some(4);
+        conf.sample_seed = 42;
+        let first_dir = Path::new("../../tests/001/first");
+        let second_dir = Path::new("../../tests/001/second");
+
+        let exit_code = cmp_tree::cmp_tree(&conf, first_dir, second_dir);
+        assert_eq!(exit_code, 0);
+        /* }}} */
+    }
+
+    #[test]
+    fn it_sample_002_differing_2l_single_differing_regular_file() {
+        /* {{{ */
+        let mut conf = cmp_tree::default_config();
+        conf.sample_blocks = Some(4);
+        conf.buffer_size = 4096;
+        let first_dir = Path::new("../../tests/004/first");
+        let second_dir = Path::new("../../tests/004/second");
+
+        let exit_code = cmp_tree::cmp_tree(&conf, first_dir, second_dir);
+        assert_eq!(exit_code, 1);
+        /* }}} */
+    }
+
+    #[test]
+    fn it_sparse_001_identical_1l_sparse_and_dense_files() {
+        /* {{{ */
+        let conf = cmp_tree::default_config();
+        let first_dir = Path::new("../../tests/027/first");
+        let second_dir = Path::new("../../tests/027/second");
+
+        let exit_code = cmp_tree::cmp_tree(&conf, first_dir, second_dir);
+        assert_eq!(exit_code, 0);
+        /* }}} */
+    }
+
+    #[test]
+    fn it_sparse_002_differing_1l_different_sparseness() {
+        /* {{{ */
+        let mut conf = cmp_tree::default_config();
+        conf.compare_sparseness = true;
+        let first_dir = Path::new("../../tests/027/first");
+        let second_dir = Path::new("../../tests/027/second");
+
+        let exit_code = cmp_tree::cmp_tree(&conf, first_dir, second_dir);
+        assert_eq!(exit_code, 1);
+        /* }}} */
+    }
+
+    #[test]
+    fn it_text_001_differing_1l_line_endings() {
+        /* {{{ */
+        let conf = cmp_tree::default_config();
+        let first_dir = Path::new("../../tests/028/first");
+        let second_dir = Path::new("../../tests/028/second");
+
+        let exit_code = cmp_tree::cmp_tree(&conf, first_dir, second_dir);
+        assert_eq!(exit_code, 1);
+        /* }}} */
+    }
+
+    #[test]
+    fn it_text_002_differing_1l_line_endings_and_binary_file() {
+        /* {{{ */
+        let mut conf = cmp_tree::default_config();
+        conf.text = true;
+        conf.ignore_bom = true;
+        conf.ignore_trailing_newline = true;
+        let first_dir = Path::new("../../tests/028/first");
+        let second_dir = Path::new("../../tests/028/second");
+
+        let exit_code = cmp_tree::cmp_tree(&conf, first_dir, second_dir);
+        assert_eq!(exit_code, 1);
+        /* }}} */
+    }
+
+    #[test]
+    fn it_whitespace_001_differing_1l_trailing_space_only() {
+        /* {{{ */
+        let mut conf = cmp_tree::default_config();
+        conf.text = true;
+        conf.ignore_trailing_space = true;
+        let first_dir = Path::new("../../tests/029/first");
+        let second_dir = Path::new("../../tests/029/second");
+
+        let exit_code = cmp_tree::cmp_tree(&conf, first_dir, second_dir);
+        assert_eq!(exit_code, 1);
+        /* }}} */
+    }
+
+    #[test]
+    fn it_whitespace_002_identical_1l_all_space_and_blank_lines() {
+        /* {{{ */
+        let mut conf = cmp_tree::default_config();
+        conf.text = true;
+        conf.ignore_all_space = true;
+        conf.ignore_blank_lines = true;
+        let first_dir = Path::new("../../tests/029/first");
+        let second_dir = Path::new("../../tests/029/second");
+
+        let exit_code = cmp_tree::cmp_tree(&conf, first_dir, second_dir);
+        assert_eq!(exit_code, 0);
+        /* }}} */
+    }
+
+    #[test]
+    fn it_mask_001_differing_1l_one_file_masked() {
+        /* {{{ */
+        let mut conf = cmp_tree::default_config();
+        conf.text = true;
+        conf.masks.push(cmp_tree::config::Mask {
+            glob: globset::Glob::new("*.h").unwrap().compile_matcher(),
+            regex: regex::bytes::Regex::new("[0-9T:-]{19}").unwrap(),
+            whole_line: false,
+        });
+        let first_dir = Path::new("../../tests/030/first");
+        let second_dir = Path::new("../../tests/030/second");
+
+        let exit_code = cmp_tree::cmp_tree(&conf, first_dir, second_dir);
+        assert_eq!(exit_code, 1);
+        /* }}} */
+    }
+
+    #[test]
+    fn it_mask_002_identical_1l_all_files_masked() {
+        /* {{{ */
+        let mut conf = cmp_tree::default_config();
+        conf.text = true;
+        conf.masks.push(cmp_tree::config::Mask {
+            glob: globset::Glob::new("*.h").unwrap().compile_matcher(),
+            regex: regex::bytes::Regex::new("[0-9T:-]{19}").unwrap(),
+            whole_line: false,
+        });
+        conf.masks.push(cmp_tree::config::Mask {
+            glob: globset::Glob::new("logs/*.log").unwrap().compile_matcher(),
+            regex: regex::bytes::Regex::new("^Host:").unwrap(),
+            whole_line: true,
+        });
+        let first_dir = Path::new("../../tests/030/first");
+        let second_dir = Path::new("../../tests/030/second");
+
+        let exit_code = cmp_tree::cmp_tree(&conf, first_dir, second_dir);
+        assert_eq!(exit_code, 0);
+        /* }}} */
+    }
+
+    #[test]
+    fn it_structured_001_differing_2l_differing_data() {
+        /* {{{ */
+        let mut conf = cmp_tree::default_config();
+        conf.structured.push(globset::Glob::new("*").unwrap().compile_matcher());
+        let first_dir = Path::new("../../tests/031/first");
+        let second_dir = Path::new("../../tests/031/second");
+
+        let exit_code = cmp_tree::cmp_tree(&conf, first_dir, second_dir);
+        assert_eq!(exit_code, 1);
+        /* }}} */
+    }
+
+    #[test]
+    fn it_decompress_001_differing_1l_one_payload_differs() {
+        /* {{{ */
+        let mut conf = cmp_tree::default_config();
+        conf.decompress = true;
+        let first_dir = Path::new("../../tests/032/first");
+        let second_dir = Path::new("../../tests/032/second");
+
+        let exit_code = cmp_tree::cmp_tree(&conf, first_dir, second_dir);
+        assert_eq!(exit_code, 1);
+        /* }}} */
+    }
+
+    #[test]
+    fn it_archive_001_identical_2l_directory_and_tar_gz() {
+        /* {{{ */
+        let mut conf = cmp_tree::default_config();
+        conf.compare_modes = true;
+        let first_dir = Path::new("../../tests/033/first/release-1.0");
+        let second_dir = Path::new("../../tests/033/second/release-1.0.tar.gz!/release-1.0");
+
+        let exit_code = cmp_tree::cmp_tree(&conf, first_dir, second_dir);
+        assert_eq!(exit_code, 0);
+        /* }}} */
+    }
+
+    #[test]
+    fn it_archive_002_identical_2l_directory_and_zip() {
+        /* {{{ */
+        let mut conf = cmp_tree::default_config();
+        conf.compare_modes = true;
+        let first_dir = Path::new("../../tests/033/first/release-1.0");
+        let second_dir = Path::new("../../tests/033/second/release-1.0.zip!/release-1.0");
+
+        let exit_code = cmp_tree::cmp_tree(&conf, first_dir, second_dir);
+        assert_eq!(exit_code, 0);
+        /* }}} */
+    }
+
+    #[test]
+    fn it_archive_003_identical_3l_two_compressed_tars() {
+        /* {{{ */
+        let conf = cmp_tree::default_config();
+        let first_dir = Path::new("../../tests/033/second/release-1.0.tar.gz");
+        let second_dir = Path::new("../../tests/033/second/release-1.0.tar.zst");
+
+        let exit_code = cmp_tree::cmp_tree(&conf, first_dir, second_dir);
+        assert_eq!(exit_code, 0);
+        /* }}} */
+    }
+
+    #[test]
+    fn it_archive_004_differing_3l_changed_tar() {
+        /* {{{ */
+        let conf = cmp_tree::default_config();
+        let first_dir = Path::new("../../tests/033/first");
+        let second_dir = Path::new("../../tests/033/second/changed.tar");
+
+        let exit_code = cmp_tree::cmp_tree(&conf, first_dir, second_dir);
+        assert_eq!(exit_code, 1);
+        /* }}} */
+    }
+
+    #[test]
+    fn it_archive_005_error_1l_missing_directory_in_archive() {
+        /* {{{ */
+        let conf = cmp_tree::default_config();
+        let first_dir = Path::new("../../tests/033/first/release-1.0");
+        let second_dir = Path::new("../../tests/033/second/release-1.0.zip!/missing");
+
+        let exit_code = cmp_tree::cmp_tree(&conf, first_dir, second_dir);
+        assert_eq!(exit_code, 2);
+        /* }}} */
+    }
+
+    #[test]
+    fn it_recurse_001_differing_1l_archives_differ() {
+        /* {{{ */
+        let mut conf = cmp_tree::default_config();
+        conf.recurse_archives = true;
+        let first_dir = Path::new("../../tests/034/first");
+        let second_dir = Path::new("../../tests/034/second");
+
+        let exit_code = cmp_tree::cmp_tree(&conf, first_dir, second_dir);
+        assert_eq!(exit_code, 1);
+        /* }}} */
+    }
+
+    #[test]
+    fn it_elf_001_identical_1l_build_id_and_debug_sections_differ() {
+        /* {{{ */
+        let mut conf = cmp_tree::default_config();
+        conf.elf = true;
+        let first_dir = Path::new("../../tests/035/first/bin");
+        let second_dir = Path::new("../../tests/035/second/bin");
+
+        let exit_code = cmp_tree::cmp_tree(&conf, first_dir, second_dir);
+        assert_eq!(exit_code, 0);
+        /* }}} */
+    }
+
+    #[test]
+    fn it_elf_002_differing_2l_code_differs() {
+        /* {{{ */
+        let mut conf = cmp_tree::default_config();
+        conf.elf = true;
+        let first_dir = Path::new("../../tests/035/first");
+        let second_dir = Path::new("../../tests/035/second");
+
+        let exit_code = cmp_tree::cmp_tree(&conf, first_dir, second_dir);
+        assert_eq!(exit_code, 1);
+        /* }}} */
+    }
+
+    #[test]
+    fn it_images_001_differing_1l_dimensions_differ() {
+        /* {{{ */
+        let mut conf = cmp_tree::default_config();
+        conf.images = true;
+        conf.image_tolerance = 3;
+        let first_dir = Path::new("../../tests/036/first");
+        let second_dir = Path::new("../../tests/036/second");
+
+        let exit_code = cmp_tree::cmp_tree(&conf, first_dir, second_dir);
+        assert_eq!(exit_code, 1);
+        /* }}} */
+    }
+
+    #[test]
+    fn it_external_001_identical_1l_same_ignoring_case() {
+        /* {{{ */
+        let mut conf = cmp_tree::default_config();
+        conf.external_commands.push(cmp_tree::config::ExternalCommand {
+            glob: globset::Glob::new("*.txt").unwrap().compile_matcher(),
+            argv: vec![String::from("../../tests/037/cmp-ignoring-case.sh")],
+        });
+        let first_dir = Path::new("../../tests/037/first/same");
+        let second_dir = Path::new("../../tests/037/second/same");
+
+        let exit_code = cmp_tree::cmp_tree(&conf, first_dir, second_dir);
+        assert_eq!(exit_code, 0);
+        /* }}} */
+    }
+
+    #[test]
+    fn it_external_002_error_2l_command_fails() {
+        /* {{{ */
+        let mut conf = cmp_tree::default_config();
+        conf.external_commands.push(cmp_tree::config::ExternalCommand {
+            glob: globset::Glob::new("*.txt").unwrap().compile_matcher(),
+            argv: vec![String::from("sh"), String::from("-c"), String::from("exit 3")],
+        });
+        let first_dir = Path::new("../../tests/037/first");
+        let second_dir = Path::new("../../tests/037/second");
+
+        let exit_code = cmp_tree::cmp_tree(&conf, first_dir, second_dir);
+        assert_eq!(exit_code, 2);
+        /* }}} */
+    }
+
+    #[test]
+    fn it_archive_006_error_2l_text_with_archive() {
+        /* {{{ */
+        /* Files in archives are only compared byte-for-byte, so asking for them to be compared as
+         * text is an error rather than silently ignored */
+        let mut conf = cmp_tree::default_config();
+        conf.text = true;
+        let first_dir = Path::new("../../tests/033/first/release-1.0");
+        let second_dir = Path::new("../../tests/033/second/release-1.0.tar.gz!/release-1.0");
+
+        let exit_code = cmp_tree::cmp_tree(&conf, first_dir, second_dir);
+        assert_eq!(exit_code, 2);
+        /* }}} */
+    }
+
+    #[test]
+    fn it_archive_007_identical_2l_shallow_with_archive() {
+        /* {{{ */
+        /* Flags that only change how files are read are ignored, with a warning */
+        let mut conf = cmp_tree::default_config();
+        conf.shallow = true;
+        let first_dir = Path::new("../../tests/033/first/release-1.0");
+        let second_dir = Path::new("../../tests/033/second/release-1.0.tar.gz!/release-1.0");
+
+        let exit_code = cmp_tree::cmp_tree(&conf, first_dir, second_dir);
         assert_eq!(exit_code, 0);
         /* }}} */
     }
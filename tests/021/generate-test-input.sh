@@ -0,0 +1,44 @@
+#!/usr/bin/env bash
+
+# Exit immediately if a command fails
+set -e
+
+write_filler_text_file () {
+	# {{{
+	printf "This is a file with some text.
+
+It should be identical to the corresponding file in the other directory \
+tree.\n" > "$1"
+	# }}}
+}
+
+build_first () {
+	cur_wd=$(pwd)
+	mkdir -p "first/"
+	cd "first/"
+
+	mkdir -p "adirectory/"
+	cd "adirectory/"
+	write_filler_text_file "file.txt"
+	# A soft link that points back up to its own parent directory
+	ln -Tfs "../" "parent"
+	cd ..
+
+	# A soft link that points to the root of the directory tree it is in
+	ln -Tfs "./" "root"
+	# A soft link that points to a file that does not exist
+	ln -Tfs "does-not-exist.txt" "dangling"
+
+	# Return to the working directory where this function started
+	cd "$cur_wd"
+}
+
+build_second () {
+	# Copy all the contents of the first/ to second/, preserving file
+	# metadata.
+	cp -arT "first/" "second/"
+}
+
+# The body of the script, build the first input directory tree and the second
+build_first
+build_second
@@ -0,0 +1,41 @@
+#!/usr/bin/env bash
+
+# Exit immediately if a command fails
+set -e
+
+write_filler_text_file () {
+	# {{{
+	printf "This is a file with some text.
+
+It should be identical to the corresponding file in the other directory \
+tree.\n" > "$1"
+	# }}}
+}
+
+build_first () {
+	cur_wd=$(pwd)
+	mkdir -p "first/"
+	cd "first/"
+
+	write_filler_text_file "file.txt"
+	ln -Tfs "file.txt" "link"
+
+	# Return to the working directory where this function started
+	cd "$cur_wd"
+}
+
+build_second () {
+	cur_wd=$(pwd)
+	mkdir -p "second/"
+	cd "second/"
+
+	write_filler_text_file "file.txt"
+	ln -Tfs "does-not-exist.txt" "link"
+
+	# Return to the working directory where this function started
+	cd "$cur_wd"
+}
+
+# The body of the script, build the first input directory tree and the second
+build_first
+build_second
@@ -0,0 +1,60 @@
+#!/usr/bin/env bash
+
+# Exit immediately if a command fails
+set -e
+
+write_filler_text_file () {
+	# {{{
+	printf "This is a file with some text.
+
+It should be identical to the corresponding file in the other directory \
+tree.\n" > "$1"
+	# }}}
+}
+
+build_common () {
+	cur_wd=$(pwd)
+	mkdir -p "$1"
+	cd "$1"
+
+	mkdir -p "lib64/"
+	write_filler_text_file "lib64/file.txt"
+	mkdir -p "subdir/"
+
+	# Return to the working directory where this function started
+	cd "$cur_wd"
+}
+
+build_first () {
+	cur_wd=$(pwd)
+	build_common "first/"
+	cd "first/"
+
+	ln -Tfs "./lib64" "lib"
+	# An absolute link path that points into this directory tree
+	ln -Tfs "$(pwd)/lib64" "abslib"
+	cd "subdir/"
+	ln -Tfs "../lib64/" "lib"
+
+	# Return to the working directory where this function started
+	cd "$cur_wd"
+}
+
+build_second () {
+	cur_wd=$(pwd)
+	build_common "second/"
+	cd "second/"
+
+	ln -Tfs "lib64" "lib"
+	# An absolute link path that points into this directory tree
+	ln -Tfs "$(pwd)/lib64" "abslib"
+	cd "subdir/"
+	ln -Tfs "../subdir/../lib64" "lib"
+
+	# Return to the working directory where this function started
+	cd "$cur_wd"
+}
+
+# The body of the script, build the first input directory tree and the second
+build_first
+build_second
@@ -0,0 +1,44 @@
+#!/usr/bin/env bash
+
+# Exit immediately if a command fails
+set -e
+
+write_large_file () {
+	# {{{
+	# Roughly 2.5 MiB of deterministic content
+	seq 1 400000 > "$1"
+	# }}}
+}
+
+build_first () {
+	cur_wd=$(pwd)
+	mkdir -p "first/"
+	cd "first/"
+
+	write_large_file "identical.bin"
+	write_large_file "last-byte.bin"
+
+	# Return to the working directory where this function started
+	cd "$cur_wd"
+}
+
+build_second () {
+	cur_wd=$(pwd)
+	mkdir -p "second/"
+	cd "second/"
+
+	write_large_file "identical.bin"
+	write_large_file "last-byte.bin"
+	# Overwrite the very last byte of the file (a newline) without changing its
+	# size
+	size=$(stat -c%s "last-byte.bin")
+	printf "x" | dd of="last-byte.bin" bs=1 seek=$((size - 1)) conv=notrunc \
+		status=none
+
+	# Return to the working directory where this function started
+	cd "$cur_wd"
+}
+
+# The body of the script, build the first input directory tree and the second
+build_first
+build_second
@@ -0,0 +1,45 @@
+#!/usr/bin/env bash
+
+# Exit immediately if a command fails
+set -e
+
+write_file () {
+	# {{{
+	# Roughly 575 KiB of deterministic content
+	seq 1 100000 > "$1"
+	# }}}
+}
+
+build_first () {
+	cur_wd=$(pwd)
+	mkdir -p "first/"
+	cd "first/"
+
+	write_file "hard-linked.txt"
+	write_file "reflinked.txt"
+	write_file "copied.txt"
+
+	# Return to the working directory where this function started
+	cd "$cur_wd"
+}
+
+build_second () {
+	cur_wd=$(pwd)
+	mkdir -p "second/"
+	cd "second/"
+
+	# A hard link shares all of its extents with the file it links to on any
+	# file system
+	ln "../first/hard-linked.txt" "hard-linked.txt"
+	# A reflink copy only shares its extents on file systems that support it
+	# (e.g. Btrfs, XFS). Elsewhere, it is a regular copy
+	cp --reflink=auto "../first/reflinked.txt" "reflinked.txt"
+	write_file "copied.txt"
+
+	# Return to the working directory where this function started
+	cd "$cur_wd"
+}
+
+# The body of the script, build the first input directory tree and the second
+build_first
+build_second
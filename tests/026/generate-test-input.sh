@@ -0,0 +1,40 @@
+#!/usr/bin/env bash
+
+# Exit immediately if a command fails
+set -e
+
+build_first () {
+	cur_wd=$(pwd)
+	mkdir -p "first/"
+	cd "first/"
+
+	printf "hello\n" > "unverified.txt"
+	touch -d "2000-01-01 00:00:00" "unverified.txt"
+	printf "hello\n" > "touched.txt"
+	touch -d "2000-01-01 00:00:00" "touched.txt"
+
+	# Return to the working directory where this function started
+	cd "$cur_wd"
+}
+
+build_second () {
+	cur_wd=$(pwd)
+	mkdir -p "second/"
+	cd "second/"
+
+	# Same size and modification time as in the first directory tree, but
+	# different contents
+	printf "jello\n" > "unverified.txt"
+	touch -d "2000-01-01 00:00:00" "unverified.txt"
+	# Same contents as in the first directory tree, but a different
+	# modification time
+	printf "hello\n" > "touched.txt"
+	touch -d "2001-01-01 00:00:00" "touched.txt"
+
+	# Return to the working directory where this function started
+	cd "$cur_wd"
+}
+
+# The body of the script, build the first input directory tree and the second
+build_first
+build_second
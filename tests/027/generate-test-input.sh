@@ -0,0 +1,46 @@
+#!/usr/bin/env bash
+
+# Exit immediately if a command fails
+set -e
+
+write_sparse_file () {
+	# {{{
+	# A 64 MiB file that is all holes, save for two small pieces of data
+	truncate -s 64M "$1"
+	printf "start" | dd of="$1" bs=1 seek=0 conv=notrunc status=none
+	printf "middle" | dd of="$1" bs=1 seek=$((32 * 1024 * 1024)) conv=notrunc \
+		status=none
+	# }}}
+}
+
+build_first () {
+	cur_wd=$(pwd)
+	mkdir -p "first/"
+	cd "first/"
+
+	write_sparse_file "sparse.img"
+	write_sparse_file "dense.img"
+
+	# Return to the working directory where this function started
+	cd "$cur_wd"
+}
+
+build_second () {
+	cur_wd=$(pwd)
+	mkdir -p "second/"
+	cd "second/"
+
+	write_sparse_file "sparse.img"
+	# Same contents as in the first directory tree, but with every hole filled
+	# in with zeros
+	write_sparse_file "dense.img.tmp"
+	cp --sparse=never "dense.img.tmp" "dense.img"
+	rm "dense.img.tmp"
+
+	# Return to the working directory where this function started
+	cd "$cur_wd"
+}
+
+# The body of the script, build the first input directory tree and the second
+build_first
+build_second
@@ -0,0 +1,40 @@
+#!/usr/bin/env bash
+
+# Exit immediately if a command fails
+set -e
+
+build_first () {
+	cur_wd=$(pwd)
+	mkdir -p "first/"
+	cd "first/"
+
+	printf "first line\nsecond line\n" > "crlf.txt"
+	printf "first line\nsecond line\n" > "bom.txt"
+	printf "first line\nsecond line\n" > "trailing-newline.txt"
+	printf "\x00\x01\r\n" > "binary.bin"
+
+	# Return to the working directory where this function started
+	cd "$cur_wd"
+}
+
+build_second () {
+	cur_wd=$(pwd)
+	mkdir -p "second/"
+	cd "second/"
+
+	# Windows line endings
+	printf "first line\r\nsecond line\r\n" > "crlf.txt"
+	# Windows line endings and a UTF-8 byte order mark
+	printf "\xEF\xBB\xBFfirst line\r\nsecond line\r\n" > "bom.txt"
+	# Windows line endings and no newline at the end of the file
+	printf "first line\r\nsecond line" > "trailing-newline.txt"
+	# Binary files are never compared as text
+	printf "\x00\x01\n" > "binary.bin"
+
+	# Return to the working directory where this function started
+	cd "$cur_wd"
+}
+
+# The body of the script, build the first input directory tree and the second
+build_first
+build_second
@@ -0,0 +1,37 @@
+#!/usr/bin/env bash
+
+# Exit immediately if a command fails
+set -e
+
+build_first () {
+	cur_wd=$(pwd)
+	mkdir -p "first/"
+	cd "first/"
+
+	printf "fn main() {\n    run();\n}\n" > "trailing-space.rs"
+	printf "fn main() {\n    run();\n}\n" > "indentation.rs"
+	printf "fn main() {\n    run();\n}\n" > "blank-lines.rs"
+
+	# Return to the working directory where this function started
+	cd "$cur_wd"
+}
+
+build_second () {
+	cur_wd=$(pwd)
+	mkdir -p "second/"
+	cd "second/"
+
+	# Trailing spaces and tabs
+	printf "fn main() { \n    run();\t\n}\n" > "trailing-space.rs"
+	# Tabs instead of spaces
+	printf "fn main() {\n\trun();\n}\n" > "indentation.rs"
+	# Extra blank lines, one of which is only whitespace
+	printf "\nfn main() {\n\n    run();\n  \n}\n" > "blank-lines.rs"
+
+	# Return to the working directory where this function started
+	cd "$cur_wd"
+}
+
+# The body of the script, build the first input directory tree and the second
+build_first
+build_second
@@ -0,0 +1,41 @@
+#!/usr/bin/env bash
+
+# Exit immediately if a command fails
+set -e
+
+build_first () {
+	cur_wd=$(pwd)
+	mkdir -p "first/"
+	cd "first/"
+
+	printf "#define VERSION \"1.2.0\"\n#define BUILD_TIME \"2024-01-01T10:00:00\"\n" > "version.h"
+	mkdir -p "logs/"
+	printf "Building...\nHost: alpha.example.com\nDone\n" > "logs/build.log"
+	mkdir -p "windows/"
+	printf "#define VERSION \"1.2.0\"\n#define BUILD_TIME \"2024-01-01T10:00:00\"\n" > "windows/version.h"
+
+	# Return to the working directory where this function started
+	cd "$cur_wd"
+}
+
+build_second () {
+	cur_wd=$(pwd)
+	mkdir -p "second/"
+	cd "second/"
+
+	# A different build timestamp
+	printf "#define VERSION \"1.2.0\"\n#define BUILD_TIME \"2024-05-06T11:22:33\"\n" > "version.h"
+	mkdir -p "logs/"
+	# A different build host
+	printf "Building...\nHost: beta.example.com\nDone\n" > "logs/build.log"
+	mkdir -p "windows/"
+	# A different build timestamp and line endings
+	printf "#define VERSION \"1.2.0\"\r\n#define BUILD_TIME \"2024-05-06T11:22:33\"\r\n" > "windows/version.h"
+
+	# Return to the working directory where this function started
+	cd "$cur_wd"
+}
+
+# The body of the script, build the first input directory tree and the second
+build_first
+build_second
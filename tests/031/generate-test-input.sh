@@ -0,0 +1,42 @@
+#!/usr/bin/env bash
+
+# Exit immediately if a command fails
+set -e
+
+build_first () {
+	cur_wd=$(pwd)
+	mkdir -p "first/"
+	cd "first/"
+
+	printf '{"name": "app", "port": 8080, "hosts": ["a", "b"]}\n' > "app.json"
+	printf "[package]\nname = \"app\"\nversion = \"1.0.0\"\n\n[dependencies]\nserde = \"1\"\n" \
+		> "Cargo.toml"
+	mkdir -p "deploy/"
+	printf "replicas: 3\nimage:\n  name: app\n  tag: v1\n" > "deploy/values.yaml"
+
+	# Return to the working directory where this function started
+	cd "$cur_wd"
+}
+
+build_second () {
+	cur_wd=$(pwd)
+	mkdir -p "second/"
+	cd "second/"
+
+	# Reordered keys and different formatting, but the same data
+	printf '{\n  "hosts": [\n    "a",\n    "b"\n  ],\n  "port": 8080,\n  "name": "app"\n}\n' \
+		> "app.json"
+	# Reordered tables and a comment, but the same data
+	printf "# The manifest\n[dependencies]\nserde = '1'\n\n[package]\nversion = \"1.0.0\"\nname = \"app\"\n" \
+		> "Cargo.toml"
+	mkdir -p "deploy/"
+	# A different image tag
+	printf "image: {name: app, tag: v2}\nreplicas: 3\n" > "deploy/values.yaml"
+
+	# Return to the working directory where this function started
+	cd "$cur_wd"
+}
+
+# The body of the script, build the first input directory tree and the second
+build_first
+build_second
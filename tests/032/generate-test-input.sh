@@ -0,0 +1,45 @@
+#!/usr/bin/env bash
+
+# Exit immediately if a command fails
+set -e
+
+# Prints the same few hundred lines of text every time it is called
+payload () {
+	for i in $(seq 1 500); do
+		printf "Line %d of the payload\n" "$i"
+	done
+}
+
+build_first () {
+	cur_wd=$(pwd)
+	mkdir -p "first/"
+	cd "first/"
+
+	payload | gzip -1 > "payload.txt.gz"
+	payload | xz -0 > "payload.txt.xz"
+	payload | zstd -q -1 > "payload.txt.zst"
+	payload | gzip -9 > "changed.txt.gz"
+
+	# Return to the working directory where this function started
+	cd "$cur_wd"
+}
+
+build_second () {
+	cur_wd=$(pwd)
+	mkdir -p "second/"
+	cd "second/"
+
+	# The same payloads, compressed at other compression levels
+	payload | gzip -9 > "payload.txt.gz"
+	payload | xz -9 --check=sha256 > "payload.txt.xz"
+	payload | zstd -q -19 > "payload.txt.zst"
+	# A different payload
+	(payload; printf "One more line\n") | gzip -9 > "changed.txt.gz"
+
+	# Return to the working directory where this function started
+	cd "$cur_wd"
+}
+
+# The body of the script, build the first input directory tree and the second
+build_first
+build_second
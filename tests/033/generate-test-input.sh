@@ -0,0 +1,46 @@
+#!/usr/bin/env bash
+
+# Exit immediately if a command fails
+set -e
+
+build_first () {
+	cur_wd=$(pwd)
+	mkdir -p "first/release-1.0/"
+	cd "first/release-1.0/"
+
+	printf "A release\n" > "README"
+	mkdir -p "bin/" "src/"
+	printf "#!/bin/sh\necho run\n" > "bin/run.sh"
+	chmod 755 "bin/run.sh"
+	printf "int main(void) {\n\treturn 0;\n}\n" > "src/main.c"
+	ln -sf "README" "README.link"
+
+	# Return to the working directory where this function started
+	cd "$cur_wd"
+}
+
+build_second () {
+	cur_wd=$(pwd)
+	mkdir -p "second/"
+	cd "second/"
+
+	# The first directory tree, archived in every format
+	tar -C "../first/" -czf "release-1.0.tar.gz" "release-1.0"
+	tar -C "../first/" -cf - "release-1.0" | zstd -q -f -o "release-1.0.tar.zst"
+	rm -f "release-1.0.zip"
+	(cd "../first/" && zip -q -r -y "../second/release-1.0.zip" "release-1.0")
+
+	# The first directory tree with one regular file changed, archived
+	rm -rf "changed/"
+	cp -a "../first/release-1.0/" "changed/"
+	printf "int main(void) {\n\treturn 1;\n}\n" > "changed/src/main.c"
+	tar -cf "changed.tar" --transform "s|^changed|release-1.0|" "changed"
+	rm -rf "changed/"
+
+	# Return to the working directory where this function started
+	cd "$cur_wd"
+}
+
+# The body of the script, build the first input directory tree and the second
+build_first
+build_second
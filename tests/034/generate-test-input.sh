@@ -0,0 +1,52 @@
+#!/usr/bin/env bash
+
+# Exit immediately if a command fails
+set -e
+
+# Builds a Java archive from a manifest naming the given version, in the working directory
+build_jar () {
+	rm -rf "jar/" "app.jar"
+	mkdir -p "jar/META-INF/" "jar/com/example/"
+	printf "Manifest-Version: 1.0\nImplementation-Version: %s\n" "$1" > "jar/META-INF/MANIFEST.MF"
+	printf "class App\n" > "jar/com/example/App.class"
+	(cd "jar/" && zip -q -X -r "../app.jar" "META-INF" "com")
+	rm -rf "jar/"
+}
+
+# Builds a zip archive with the same files in it every time, but with the given modification time
+build_bundle () {
+	rm -rf "bundle/" "bundle.zip"
+	mkdir -p "bundle/"
+	printf "config\n" > "bundle/config.txt"
+	touch -d "$1" "bundle/config.txt"
+	(cd "bundle/" && zip -q -X "../bundle.zip" "config.txt")
+	rm -rf "bundle/"
+}
+
+build_first () {
+	cur_wd=$(pwd)
+	mkdir -p "first/lib/"
+	cd "first/lib/"
+
+	build_jar "1.0"
+	build_bundle "2020-01-01 00:00:00"
+
+	# Return to the working directory where this function started
+	cd "$cur_wd"
+}
+
+build_second () {
+	cur_wd=$(pwd)
+	mkdir -p "second/lib/"
+	cd "second/lib/"
+
+	build_jar "1.1"
+	build_bundle "2021-01-01 00:00:00"
+
+	# Return to the working directory where this function started
+	cd "$cur_wd"
+}
+
+# The body of the script, build the first input directory tree and the second
+build_first
+build_second
@@ -0,0 +1,44 @@
+#!/usr/bin/env bash
+
+# Exit immediately if a command fails
+set -e
+
+# Writes a small C program that exits with the given exit code to `main.c`
+write_program () {
+	printf "int main(void) {\n\treturn %s;\n}\n" "$1" > "main.c"
+}
+
+build_first () {
+	cur_wd=$(pwd)
+	mkdir -p "first/bin/" "first/changed/"
+	cd "first/"
+
+	write_program "0"
+	gcc -O2 -Wl,--build-id=0x01 -o "bin/app" "main.c"
+	gcc -O2 -Wl,--build-id=0x01 -o "changed/app" "main.c"
+	rm -f "main.c"
+
+	# Return to the working directory where this function started
+	cd "$cur_wd"
+}
+
+build_second () {
+	cur_wd=$(pwd)
+	mkdir -p "second/bin/" "second/changed/"
+	cd "second/"
+
+	# The same program, but with a different build ID and with debug sections
+	write_program "0"
+	gcc -O2 -g -Wl,--build-id=0x02 -o "bin/app" "main.c"
+	# A program that exits with a different exit code
+	write_program "1"
+	gcc -O2 -Wl,--build-id=0x01 -o "changed/app" "main.c"
+	rm -f "main.c"
+
+	# Return to the working directory where this function started
+	cd "$cur_wd"
+}
+
+# The body of the script, build the first input directory tree and the second
+build_first
+build_second
@@ -0,0 +1,73 @@
+#!/usr/bin/env bash
+
+# Exit immediately if a command fails
+set -e
+
+# Writes the images of one of the directory trees to the working directory. The images are PNG
+# images written by hand, so that how they are encoded (their compression level and metadata
+# chunks) is known exactly. Takes the name of the directory tree, "first" or "second".
+write_images () {
+	python3 - "$1" <<'PYTHON'
+import struct, sys, zlib
+
+def chunk(kind, data):
+	return struct.pack(">I", len(data)) + kind + data \
+		+ struct.pack(">I", zlib.crc32(kind + data) & 0xFFFFFFFF)
+
+def write_png(path, width, height, pixel, level, text=None):
+	rows = b"".join(b"\x00" + b"".join(bytes(pixel(x, y)) for x in range(width))
+		for y in range(height))
+	png = b"\x89PNG\r\n\x1a\n"
+	png += chunk(b"IHDR", struct.pack(">IIBBBBB", width, height, 8, 6, 0, 0, 0))
+	if text is not None:
+		png += chunk(b"tEXt", b"Software\x00" + text)
+	png += chunk(b"IDAT", zlib.compress(rows, level))
+	png += chunk(b"IEND", b"")
+	with open(path, "wb") as f:
+		f.write(png)
+
+def gradient(x, y):
+	return (x * 30, y * 60, 128, 255)
+
+# The red channel of one pixel is 3 higher in the second tree
+def nudged(x, y):
+	r, g, b, a = gradient(x, y)
+	return (r + 3 if (x, y) == (2, 1) else r, g, b, a)
+
+if sys.argv[1] == "first":
+	write_png("logo.png", 4, 4, gradient, 9)
+	write_png("icon.png", 4, 4, gradient, 9)
+	write_png("banner.png", 4, 4, gradient, 9)
+else:
+	# The same pixels, compressed differently and with a metadata chunk
+	write_png("logo.png", 4, 4, gradient, 1, b"asset-pipeline 2.0")
+	write_png("icon.png", 4, 4, nudged, 9)
+	write_png("banner.png", 8, 4, gradient, 9)
+PYTHON
+}
+
+build_first () {
+	cur_wd=$(pwd)
+	mkdir -p "first/"
+	cd "first/"
+
+	write_images "first"
+
+	# Return to the working directory where this function started
+	cd "$cur_wd"
+}
+
+build_second () {
+	cur_wd=$(pwd)
+	mkdir -p "second/"
+	cd "second/"
+
+	write_images "second"
+
+	# Return to the working directory where this function started
+	cd "$cur_wd"
+}
+
+# The body of the script, build the first input directory tree and the second
+build_first
+build_second
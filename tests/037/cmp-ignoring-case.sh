@@ -0,0 +1,6 @@
+#!/usr/bin/env bash
+
+# An external comparator for `cmp-tree --external`. Compares two files the way `cmp` does, but
+# ignoring the case of ASCII letters. Exits with 0 if the files match, 1 if they differ and 2 if
+# either could not be read.
+cmp -s <(tr '[:upper:]' '[:lower:]' < "$1") <(tr '[:upper:]' '[:lower:]' < "$2")
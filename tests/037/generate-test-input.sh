@@ -0,0 +1,33 @@
+#!/usr/bin/env bash
+
+# Exit immediately if a command fails
+set -e
+
+build_first () {
+	cur_wd=$(pwd)
+	mkdir -p "first/same/" "first/changed/"
+	cd "first/"
+
+	printf "Release notes\n" > "same/notes.txt"
+	printf "Release notes\n" > "changed/notes.txt"
+
+	# Return to the working directory where this function started
+	cd "$cur_wd"
+}
+
+build_second () {
+	cur_wd=$(pwd)
+	mkdir -p "second/same/" "second/changed/"
+	cd "second/"
+
+	# The same text in a different case, then different text
+	printf "RELEASE NOTES\n" > "same/notes.txt"
+	printf "Release nodes\n" > "changed/notes.txt"
+
+	# Return to the working directory where this function started
+	cd "$cur_wd"
+}
+
+# The body of the script, build the first input directory tree and the second
+build_first
+build_second